@@ -0,0 +1,720 @@
+//! Modem Command Client
+//!
+//! Typed async API over the request/response protocol. Each method frames one
+//! request, waits for the matching `Ack`/`Error` frame and decodes the result.
+//! Event frames that arrive while a command is outstanding are queued and can
+//! be drained with [`ModemClient::next_event`].
+//!
+//! Requests use sequenced framing by default, so a late response to a request
+//! that already timed out is discarded instead of being taken as the answer to
+//! the next command.
+//!
+//! A request the modem NAKs because its frame was corrupted or dropped is resent
+//! with the same sequence number, up to [`DEFAULT_MAX_RETRANSMITS`] times. A
+//! `Duplicate` NAK means the modem already ran the request, so the client keeps
+//! waiting for its response and fails with `HostError::ResponseLost` if none
+//! arrives.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use ble_modem_protocol::batch::{BatchResponse, BATCH_STOP_ON_ERROR};
+use ble_modem_protocol::capabilities::Capabilities;
+use ble_modem_protocol::codec::{AdvData, AdvParams, Request};
+use ble_modem_protocol::error::ErrorResponse;
+use ble_modem_protocol::events::{EventHeader, EventMask, EventSubscription, SocEvent, SystemEvent};
+use ble_modem_protocol::identity::DeviceIdentity;
+use ble_modem_protocol::link::{LinkStats, Nak, NakReason};
+use ble_modem_protocol::pool::PoolStats;
+use ble_modem_protocol::power::{RebootMode, WakePolarity, FACTORY_RESET_TOKEN};
+use ble_modem_protocol::requests::*;
+use ble_modem_protocol::{Packet, RequestCode, ResponseCode, MAX_MESSAGE_SIZE, PROTOCOL_VERSION, UNSOLICITED_SEQ};
+
+use crate::error::HostError;
+use crate::event::{BleEvent, ModemEvent};
+use crate::transport::{Defragmenter, Transport};
+use crate::types::{
+    Batch, BdAddr, CharacteristicHandles, CharacteristicParams, ConnParams, HvxType, ServiceType, TxPowerRole, Uuid,
+};
+
+/// Default time to wait for a response frame
+pub const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Default number of times a NAKed request is resent
+pub const DEFAULT_MAX_RETRANSMITS: u8 = 2;
+
+/// `NRF_SUCCESS` status returned by SoftDevice backed commands
+const NRF_SUCCESS: u32 = 0;
+
+/// Big-endian reader for response payloads
+struct ResponseReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ResponseReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, HostError> {
+        Ok(self.read_slice(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, HostError> {
+        let bytes = self.read_slice(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, HostError> {
+        let bytes = self.read_slice(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, HostError> {
+        let bytes = self.read_slice(8)?;
+        let mut value = [0u8; 8];
+        value.copy_from_slice(bytes);
+        Ok(u64::from_be_bytes(value))
+    }
+
+    fn read_slice(&mut self, len: usize) -> Result<&'a [u8], HostError> {
+        if self.offset + len > self.data.len() {
+            return Err(HostError::InvalidResponse);
+        }
+        let slice = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(slice)
+    }
+
+    /// Read the leading `NRF_*` status and fail on anything but success
+    fn read_status(&mut self) -> Result<(), HostError> {
+        match self.read_u32()? {
+            NRF_SUCCESS => Ok(()),
+            code => Err(HostError::SoftDevice(code)),
+        }
+    }
+}
+
+/// Check whether a response belongs to the request sent with `expected`
+///
+/// Unsequenced responses are accepted as-is since they carry nothing to check.
+fn matches_seq(expected: Option<u16>, received: Option<u16>) -> bool {
+    match (expected, received) {
+        (Some(expected), Some(received)) => expected == received,
+        _ => true,
+    }
+}
+
+/// Check whether a NAK refers to the request sent with `expected`
+///
+/// NAKs for frames too corrupted to read their sequence number apply to the
+/// outstanding request.
+fn nak_matches(expected: Option<u16>, nak: &Nak) -> bool {
+    match (expected, nak.request_seq()) {
+        (Some(expected), Some(rejected)) => expected == rejected,
+        _ => true,
+    }
+}
+
+/// Client for a BLE modem reachable over `T`
+pub struct ModemClient<T: Transport> {
+    transport: T,
+    timeout: Duration,
+    max_retransmits: u8,
+    events: VecDeque<ModemEvent>,
+    defragmenter: Defragmenter,
+    /// Next request sequence number, `None` when using legacy framing
+    next_seq: Option<u16>,
+}
+
+impl<T: Transport> ModemClient<T> {
+    /// Create a client using the default response timeout
+    pub fn new(transport: T) -> Self {
+        Self::with_timeout(transport, DEFAULT_RESPONSE_TIMEOUT)
+    }
+
+    /// Create a client with a custom response timeout
+    pub fn with_timeout(transport: T, timeout: Duration) -> Self {
+        Self {
+            transport,
+            timeout,
+            max_retransmits: DEFAULT_MAX_RETRANSMITS,
+            events: VecDeque::new(),
+            defragmenter: Defragmenter::new(),
+            next_seq: Some(UNSOLICITED_SEQ.wrapping_add(1)),
+        }
+    }
+
+    /// Use legacy (unsequenced) framing for firmware without sequence support
+    pub fn with_legacy_framing(mut self) -> Self {
+        self.next_seq = None;
+        self
+    }
+
+    /// Set how many times a NAKed request is resent before failing with `HostError::Nak`
+    pub fn with_max_retransmits(mut self, max_retransmits: u8) -> Self {
+        self.max_retransmits = max_retransmits;
+        self
+    }
+
+    /// Release the underlying transport
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Send a raw request and return the `Ack` payload
+    pub async fn request(&mut self, code: RequestCode, payload: &[u8]) -> Result<Vec<u8>, HostError> {
+        let seq = self.allocate_seq();
+        let packet = Packet::new_request_for_sending(code, payload)?.with_seq(seq);
+        self.send_packet(&packet).await?;
+        let mut retransmits = 0;
+        let mut duplicate = false;
+
+        loop {
+            let response = match self.receive_packet().await {
+                Err(HostError::Timeout) if duplicate => return Err(HostError::ResponseLost),
+                response => response?,
+            };
+            match ResponseCode::from_u16(response.code) {
+                // Stale response to an earlier request that timed out
+                Some(ResponseCode::Ack) | Some(ResponseCode::Error) if !matches_seq(seq, response.seq) => continue,
+                Some(ResponseCode::Ack) => return Ok(response.payload.to_vec()),
+                Some(ResponseCode::Error) => {
+                    return Err(HostError::Device(
+                        ErrorResponse::decode(&response.payload).map_err(|_| HostError::InvalidResponse)?,
+                    ));
+                }
+                Some(ResponseCode::BleEvent) | Some(ResponseCode::SocEvent) | Some(ResponseCode::SystemEvent) => {
+                    self.queue_event(&response)?
+                }
+                Some(ResponseCode::Nak) => {
+                    let nak = Nak::decode(&response.payload)?;
+                    if !nak_matches(seq, &nak) {
+                        continue;
+                    }
+                    // Already run: the response is still to come, or was lost
+                    if nak.reason == NakReason::Duplicate {
+                        duplicate = true;
+                        continue;
+                    }
+                    if !nak.reason.is_retransmittable() || retransmits >= self.max_retransmits {
+                        return Err(HostError::Nak(nak.reason));
+                    }
+                    retransmits += 1;
+                    self.send_packet(&packet).await?;
+                }
+                None => return Err(HostError::UnexpectedResponse(response.code)),
+            }
+        }
+    }
+
+    /// Send every frame of a request packet
+    async fn send_packet(&mut self, packet: &Packet) -> Result<(), HostError> {
+        for frame in packet.request_frames()? {
+            self.transport.send_frame(&frame).await?;
+        }
+        Ok(())
+    }
+
+    /// Encode a typed request from the protocol table, send it and return the `Ack` payload
+    pub async fn send<'a, R: Request<'a>>(&mut self, request: &R) -> Result<Vec<u8>, HostError> {
+        let mut payload: heapless::Vec<u8, MAX_MESSAGE_SIZE> = heapless::Vec::new();
+        request.encode(&mut payload)?;
+        self.request(R::CODE, &payload).await
+    }
+
+    /// BATCH: run several commands in one round trip
+    ///
+    /// Returns one result per sub-command that was run, in order: the `Ack`
+    /// payload, or the device error. With `stop_on_error` the modem stops at the
+    /// first failing sub-command, so fewer results than sub-commands come back.
+    pub async fn batch(
+        &mut self,
+        batch: &Batch,
+        stop_on_error: bool,
+    ) -> Result<Vec<Result<Vec<u8>, HostError>>, HostError> {
+        let request = BatchRequest {
+            flags: if stop_on_error { BATCH_STOP_ON_ERROR } else { 0 },
+            commands: batch.as_bytes(),
+        };
+        let payload = self.send(&request).await?;
+        let response = BatchResponse::decode(&payload)?;
+
+        let mut results = Vec::with_capacity(response.executed as usize);
+        for entry in response.results() {
+            let entry = entry?;
+            results.push(match ResponseCode::from_u16(entry.code) {
+                Some(ResponseCode::Ack) => Ok(entry.payload.to_vec()),
+                Some(ResponseCode::Error) => Err(HostError::Device(
+                    ErrorResponse::decode(entry.payload).map_err(|_| HostError::InvalidResponse)?,
+                )),
+                _ => return Err(HostError::UnexpectedResponse(entry.code)),
+            });
+        }
+        if results.len() != response.executed as usize {
+            return Err(HostError::InvalidResponse);
+        }
+        Ok(results)
+    }
+
+    /// Return the next event, waiting for one if none is queued
+    pub async fn next_event(&mut self) -> Result<ModemEvent, HostError> {
+        if let Some(event) = self.events.pop_front() {
+            return Ok(event);
+        }
+
+        loop {
+            let packet = self.read_packet().await?;
+            match ResponseCode::from_u16(packet.code) {
+                Some(ResponseCode::BleEvent) | Some(ResponseCode::SocEvent) | Some(ResponseCode::SystemEvent) => {
+                    self.queue_event(&packet)?;
+                    if let Some(event) = self.events.pop_front() {
+                        return Ok(event);
+                    }
+                }
+                // Responses without an outstanding request are stale; drop them
+                Some(ResponseCode::Ack) | Some(ResponseCode::Error) | Some(ResponseCode::Nak) => continue,
+                None => return Err(HostError::UnexpectedResponse(packet.code)),
+            }
+        }
+    }
+
+    /// Take an already received event without waiting
+    pub fn try_next_event(&mut self) -> Option<ModemEvent> {
+        self.events.pop_front()
+    }
+
+    /// Take the next request sequence number, skipping the unsolicited one
+    fn allocate_seq(&mut self) -> Option<u16> {
+        let seq = self.next_seq?;
+        let mut next = seq.wrapping_add(1);
+        if next == UNSOLICITED_SEQ {
+            next = next.wrapping_add(1);
+        }
+        self.next_seq = Some(next);
+        Some(seq)
+    }
+
+    async fn receive_packet(&mut self) -> Result<Packet, HostError> {
+        tokio::time::timeout(self.timeout, self.read_packet())
+            .await
+            .map_err(|_| HostError::Timeout)?
+    }
+
+    /// Receive frames until a complete packet is available
+    async fn read_packet(&mut self) -> Result<Packet, HostError> {
+        loop {
+            let frame = self.transport.receive_frame().await?;
+            if let Some(packet) = self.defragmenter.response(&frame)? {
+                return Ok(packet);
+            }
+        }
+    }
+
+    fn queue_event(&mut self, packet: &Packet) -> Result<(), HostError> {
+        let event = match ResponseCode::from_u16(packet.code) {
+            Some(ResponseCode::BleEvent) => {
+                let (header, body) = EventHeader::decode(&packet.payload)?;
+                ModemEvent::Ble {
+                    header,
+                    event: BleEvent::decode(body)?,
+                }
+            }
+            Some(ResponseCode::SocEvent) => {
+                let (header, body) = EventHeader::decode(&packet.payload)?;
+                ModemEvent::Soc {
+                    header,
+                    event: SocEvent::decode(body)?,
+                }
+            }
+            Some(ResponseCode::SystemEvent) => {
+                let (header, body) = EventHeader::decode(&packet.payload)?;
+                ModemEvent::System {
+                    header,
+                    event: SystemEvent::decode(body)?,
+                }
+            }
+            _ => return Err(HostError::UnexpectedResponse(packet.code)),
+        };
+        self.events.push_back(event);
+        Ok(())
+    }
+
+    // System Commands
+
+    /// GET_INFO: returns the firmware version in BCD format
+    pub async fn get_info(&mut self) -> Result<u32, HostError> {
+        let payload = self.send(&GetInfoRequest {}).await?;
+        ResponseReader::new(&payload).read_u32()
+    }
+
+    /// GET_INFO: returns the modem's identity (device ID, factory address, versions, build ID)
+    pub async fn get_identity(&mut self) -> Result<DeviceIdentity, HostError> {
+        let payload = self.send(&GetInfoRequest {}).await?;
+        DeviceIdentity::decode(&payload).map_err(|_| HostError::InvalidResponse)
+    }
+
+    /// HELLO: negotiate capabilities, returns the firmware's protocol version, limits and supported commands
+    pub async fn hello(&mut self) -> Result<Capabilities, HostError> {
+        let payload = self
+            .send(&HelloRequest {
+                host_version: Some(PROTOCOL_VERSION),
+            })
+            .await?;
+        Capabilities::decode(&payload).map_err(|_| HostError::InvalidResponse)
+    }
+
+    /// ECHO: returns the payload sent
+    pub async fn echo(&mut self, data: &[u8]) -> Result<Vec<u8>, HostError> {
+        self.send(&EchoRequest { data }).await
+    }
+
+    /// SHUTDOWN: power down the modem until the host link's select line goes low
+    pub async fn shutdown(&mut self) -> Result<(), HostError> {
+        self.send(&ShutdownRequest {
+            wake_pin: None,
+            wake_polarity: None,
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// SHUTDOWN: power down the modem until `wake_pin` (P0.n, or `WAKE_PIN_LINK`) reaches `polarity`
+    ///
+    /// The modem resets when it wakes.
+    pub async fn shutdown_with_wake(&mut self, wake_pin: u8, polarity: WakePolarity) -> Result<(), HostError> {
+        self.send(&ShutdownRequest {
+            wake_pin: Some(wake_pin),
+            wake_polarity: Some(polarity as u8),
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// REBOOT: reset the modem
+    ///
+    /// The modem sends a `SystemEvent::Boot` event once it is back up.
+    pub async fn reboot(&mut self) -> Result<(), HostError> {
+        self.send(&RebootRequest { mode: None }).await.map(|_| ())
+    }
+
+    /// FACTORY_RESET: wipe bonds, GATT schema and configuration, then reset the modem
+    ///
+    /// The modem sends a `SystemEvent::Boot` event with `ResetReason::FactoryReset` once it is back up.
+    pub async fn factory_reset(&mut self) -> Result<(), HostError> {
+        self.send(&FactoryResetRequest {
+            token: FACTORY_RESET_TOKEN,
+        })
+        .await
+        .map(|_| ())
+    }
+
+    /// REBOOT: reset the modem into the application or the bootloader
+    pub async fn reboot_into(&mut self, mode: RebootMode) -> Result<(), HostError> {
+        self.send(&RebootRequest { mode: Some(mode as u8) }).await.map(|_| ())
+    }
+
+    // Event Subscription
+
+    /// SET_EVENT_MASK: choose the event categories the modem forwards, returns the mask in effect
+    pub async fn set_event_mask(&mut self, mask: EventMask) -> Result<EventMask, HostError> {
+        let payload = self.send(&SetEventMaskRequest { mask: mask.bits() }).await?;
+        ResponseReader::new(&payload)
+            .read_u32()
+            .map(EventMask::from_bits_truncate)
+    }
+
+    /// GET_EVENT_MASK: read the event mask and the muted characteristics
+    pub async fn get_event_mask(&mut self) -> Result<EventSubscription, HostError> {
+        let payload = self.send(&GetEventMaskRequest {}).await?;
+        EventSubscription::decode(&payload).map_err(|_| HostError::InvalidResponse)
+    }
+
+    /// SET_CHAR_EVENT_FILTER: mute (`enabled == false`) or unmute the events of one characteristic
+    pub async fn set_char_event_filter(&mut self, char_handle: u16, enabled: bool) -> Result<(), HostError> {
+        self.send(&SetCharEventFilterRequest { char_handle, enabled })
+            .await
+            .map(|_| ())
+    }
+
+    /// CLEAR_CHAR_EVENT_FILTERS: unmute every characteristic
+    pub async fn clear_char_event_filters(&mut self) -> Result<(), HostError> {
+        self.send(&ClearCharEventFiltersRequest {}).await.map(|_| ())
+    }
+
+    /// GET_CLOCK: read the device uptime in microseconds (the clock of event timestamps)
+    pub async fn get_clock(&mut self) -> Result<u64, HostError> {
+        let payload = self.send(&GetClockRequest {}).await?;
+        ResponseReader::new(&payload).read_u64()
+    }
+
+    /// GET_LINK_STATS: read the link traffic and failure counters, zeroing them if `reset`
+    pub async fn get_link_stats(&mut self, reset: bool) -> Result<LinkStats, HostError> {
+        let payload = self.send(&GetLinkStatsRequest { reset }).await?;
+        Ok(LinkStats::decode(&payload)?)
+    }
+
+    /// GET_POOL_STATS: read the TX buffer pool usage, restarting peak tracking if `reset`
+    pub async fn get_pool_stats(&mut self, reset: bool) -> Result<PoolStats, HostError> {
+        let payload = self.send(&GetPoolStatsRequest { reset }).await?;
+        Ok(PoolStats::decode(&payload)?)
+    }
+
+    // UUID Management
+
+    /// REGISTER_UUID_GROUP: register a 128-bit vendor UUID base, returns its handle
+    pub async fn register_uuid_group(&mut self, uuid_base: [u8; 16]) -> Result<u8, HostError> {
+        let payload = self.send(&RegisterUuidGroupRequest { uuid_base }).await?;
+        ResponseReader::new(&payload).read_u8()
+    }
+
+    // GAP Operations - Address Management
+
+    /// GAP_GET_ADDR: read the device address
+    pub async fn gap_get_addr(&mut self) -> Result<BdAddr, HostError> {
+        let payload = self.send(&GapGetAddrRequest {}).await?;
+        let mut reader = ResponseReader::new(&payload);
+        reader.read_status()?;
+        let addr_type = reader.read_u8()?;
+        let mut bytes = [0u8; 6];
+        bytes.copy_from_slice(reader.read_slice(6)?);
+        Ok(BdAddr { addr_type, bytes })
+    }
+
+    /// GAP_SET_ADDR: set the device address
+    pub async fn gap_set_addr(&mut self, addr: BdAddr) -> Result<(), HostError> {
+        let request = GapSetAddrRequest {
+            addr_type: addr.addr_type,
+            addr: addr.bytes,
+        };
+        let payload = self.send(&request).await?;
+        ResponseReader::new(&payload).read_status()
+    }
+
+    // GAP Operations - Advertising Control
+
+    /// GAP_ADV_START: start advertising on `adv_handle`
+    pub async fn gap_adv_start(&mut self, adv_handle: u8, conn_cfg_tag: u8) -> Result<(), HostError> {
+        let payload = self
+            .send(&GapAdvStartRequest {
+                adv_handle,
+                conn_cfg_tag,
+            })
+            .await?;
+        ResponseReader::new(&payload).read_status()
+    }
+
+    /// GAP_ADV_STOP: stop advertising on `adv_handle`
+    pub async fn gap_adv_stop(&mut self, adv_handle: u8) -> Result<(), HostError> {
+        let payload = self.send(&GapAdvStopRequest { adv_handle }).await?;
+        ResponseReader::new(&payload).read_status()
+    }
+
+    /// GAP_ADV_SET_CONFIGURE: set advertising and scan response data, returns the handle
+    pub async fn gap_adv_set_configure(
+        &mut self,
+        adv_handle: u8,
+        adv_data: &[u8],
+        scan_data: &[u8],
+    ) -> Result<u8, HostError> {
+        let request = GapAdvSetConfigureRequest {
+            adv_handle,
+            data_present: true,
+            data: Some(AdvData { adv_data, scan_data }),
+            params: None,
+        };
+        let payload = self.send(&request).await?;
+        let mut reader = ResponseReader::new(&payload);
+        reader.read_status()?;
+        reader.read_u8()
+    }
+
+    /// GAP_ADV_SET_CONFIGURE: set the advertising interval, timeout and filter policy, returns the handle
+    ///
+    /// The advertising data is left unchanged. Applied straight away if advertising is active.
+    pub async fn gap_adv_set_params(&mut self, adv_handle: u8, params: AdvParams) -> Result<u8, HostError> {
+        let request = GapAdvSetConfigureRequest {
+            adv_handle,
+            data_present: false,
+            data: Some(AdvData {
+                adv_data: &[],
+                scan_data: &[],
+            }),
+            params: Some(params),
+        };
+        let payload = self.send(&request).await?;
+        let mut reader = ResponseReader::new(&payload);
+        reader.read_status()?;
+        reader.read_u8()
+    }
+
+    // GAP Operations - Device Configuration
+
+    /// GAP_GET_NAME: read the device name
+    pub async fn gap_get_name(&mut self) -> Result<Vec<u8>, HostError> {
+        let payload = self.send(&GapGetNameRequest { present: true }).await?;
+        let mut reader = ResponseReader::new(&payload);
+        reader.read_status()?;
+        let len = reader.read_u16()? as usize;
+        Ok(reader.read_slice(len)?.to_vec())
+    }
+
+    /// GAP_SET_NAME: set the device name with open security mode
+    pub async fn gap_set_name(&mut self, name: &[u8]) -> Result<(), HostError> {
+        let request = GapSetNameRequest {
+            sec_mode: 1,
+            sec_level: 1,
+            name,
+        };
+        let payload = self.send(&request).await?;
+        ResponseReader::new(&payload).read_status()
+    }
+
+    /// GAP_CONN_PARAMS_GET: read the peripheral preferred connection parameters
+    pub async fn gap_conn_params_get(&mut self) -> Result<ConnParams, HostError> {
+        let payload = self.send(&GapConnParamsGetRequest {}).await?;
+        let mut reader = ResponseReader::new(&payload);
+        reader.read_status()?;
+        Ok(ConnParams {
+            min_conn_interval: reader.read_u16()?,
+            max_conn_interval: reader.read_u16()?,
+            slave_latency: reader.read_u16()?,
+            conn_sup_timeout: reader.read_u16()?,
+        })
+    }
+
+    /// GAP_CONN_PARAMS_SET: set the peripheral preferred connection parameters
+    pub async fn gap_conn_params_set(&mut self, params: ConnParams) -> Result<(), HostError> {
+        let request = GapConnParamsSetRequest {
+            min_conn_interval: params.min_conn_interval,
+            max_conn_interval: params.max_conn_interval,
+            slave_latency: params.slave_latency,
+            conn_sup_timeout: params.conn_sup_timeout,
+        };
+        let payload = self.send(&request).await?;
+        ResponseReader::new(&payload).read_status()
+    }
+
+    // GAP Operations - Connection Management
+
+    /// GAP_CONN_PARAM_UPDATE: request new parameters for an active connection
+    pub async fn gap_conn_param_update(&mut self, conn_handle: u16, params: ConnParams) -> Result<(), HostError> {
+        let request = GapConnParamUpdateRequest {
+            conn_handle,
+            min_conn_interval: params.min_conn_interval,
+            max_conn_interval: params.max_conn_interval,
+            slave_latency: params.slave_latency,
+            conn_sup_timeout: params.conn_sup_timeout,
+        };
+        self.send(&request).await.map(|_| ())
+    }
+
+    /// GAP_DATA_LENGTH_UPDATE: request a data length update for a connection
+    pub async fn gap_data_length_update(
+        &mut self,
+        conn_handle: u16,
+        tx_octets: u16,
+        tx_time_us: u16,
+    ) -> Result<(), HostError> {
+        let request = GapDataLengthUpdateRequest {
+            conn_handle,
+            tx_octets,
+            tx_time_us,
+        };
+        self.send(&request).await.map(|_| ())
+    }
+
+    /// GAP_PHY_UPDATE: request a PHY update (bitmask: 0x01=1M, 0x02=2M, 0x04=Coded)
+    pub async fn gap_phy_update(&mut self, conn_handle: u16, tx_phys: u8, rx_phys: u8) -> Result<(), HostError> {
+        let request = GapPhyUpdateRequest {
+            conn_handle,
+            tx_phys,
+            rx_phys,
+            coded_phy: 0,
+        };
+        self.send(&request).await.map(|_| ())
+    }
+
+    /// GAP_DISCONNECT: terminate a connection with an HCI reason code
+    pub async fn gap_disconnect(&mut self, conn_handle: u16, reason: u8) -> Result<(), HostError> {
+        self.send(&GapDisconnectRequest { conn_handle, reason })
+            .await
+            .map(|_| ())
+    }
+
+    // GAP Operations - Power & RSSI
+
+    /// GAP_SET_TX_POWER: set the transmit power in dBm for a role
+    pub async fn gap_set_tx_power(&mut self, role: TxPowerRole, conn_handle: u16, dbm: i8) -> Result<(), HostError> {
+        let request = GapSetTxPowerRequest {
+            role: role as u8,
+            conn_handle,
+            tx_power: dbm,
+        };
+        self.send(&request).await.map(|_| ())
+    }
+
+    // GATT Server Operations
+
+    /// GATTS_SERVICE_ADD: create a service, returns its handle
+    pub async fn gatts_service_add(&mut self, uuid: Uuid, service_type: ServiceType) -> Result<u16, HostError> {
+        let request = GattsServiceAddRequest {
+            uuid,
+            service_type: service_type as u8,
+        };
+        let payload = self.send(&request).await?;
+        ResponseReader::new(&payload).read_u16()
+    }
+
+    /// GATTS_CHARACTERISTIC_ADD: add a characteristic to a service
+    pub async fn gatts_characteristic_add(
+        &mut self,
+        params: &CharacteristicParams<'_>,
+    ) -> Result<CharacteristicHandles, HostError> {
+        let request = GattsCharacteristicAddRequest {
+            service_handle: params.service_handle,
+            uuid: params.uuid,
+            properties: params.properties,
+            max_length: params.max_length,
+            initial_value: params.initial_value,
+            permissions: Some(params.permissions),
+        };
+        let payload = self.send(&request).await?;
+        let mut reader = ResponseReader::new(&payload);
+        Ok(CharacteristicHandles {
+            value_handle: reader.read_u16()?,
+            cccd_handle: reader.read_u16()?,
+            sccd_handle: reader.read_u16()?,
+        })
+    }
+
+    /// GATTS_MTU_REPLY: reply to an MTU exchange request
+    pub async fn gatts_mtu_reply(&mut self, conn_handle: u16, mtu: u16) -> Result<(), HostError> {
+        self.send(&GattsMtuReplyRequest { conn_handle, mtu }).await.map(|_| ())
+    }
+
+    /// GATTS_HVX: send a notification or indication
+    pub async fn gatts_hvx(
+        &mut self,
+        conn_handle: u16,
+        char_handle: u16,
+        hvx_type: HvxType,
+        data: &[u8],
+    ) -> Result<(), HostError> {
+        let request = GattsHvxRequest {
+            conn_handle,
+            char_handle,
+            hvx_type: hvx_type as u8,
+            data,
+        };
+        self.send(&request).await.map(|_| ())
+    }
+
+    /// GATTS_SYS_ATTR_SET: restore system attributes (CCCD states) for a bonded peer
+    pub async fn gatts_sys_attr_set(&mut self, conn_handle: u16, sys_attr: &[u8]) -> Result<(), HostError> {
+        self.send(&GattsSysAttrSetRequest { conn_handle, sys_attr })
+            .await
+            .map(|_| ())
+    }
+}
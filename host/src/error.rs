@@ -0,0 +1,67 @@
+//! Host Client Errors
+
+use core::fmt;
+
+use ble_modem_protocol::error::ErrorResponse;
+use ble_modem_protocol::link::NakReason;
+use ble_modem_protocol::ProtocolError;
+
+/// Errors returned by the host client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostError {
+    /// The link to the modem was closed
+    Disconnected,
+    /// Transport I/O failure
+    Io(std::io::ErrorKind),
+    /// A frame could not be encoded or decoded
+    Protocol(ProtocolError),
+    /// No response arrived before the timeout expired
+    Timeout,
+    /// The modem answered with an `Error` frame
+    Device(ErrorResponse),
+    /// The SoftDevice rejected the operation with this `NRF_ERROR_*` code
+    SoftDevice(u32),
+    /// The modem answered with an unknown response code
+    UnexpectedResponse(u16),
+    /// The response payload did not match the expected layout
+    InvalidResponse,
+    /// The modem rejected the request frame (after any retransmissions)
+    Nak(NakReason),
+    /// The modem ran the request, but its response was lost and could not be sent again
+    ResponseLost,
+}
+
+impl From<ProtocolError> for HostError {
+    fn from(err: ProtocolError) -> Self {
+        HostError::Protocol(err)
+    }
+}
+
+impl From<std::io::Error> for HostError {
+    fn from(err: std::io::Error) -> Self {
+        HostError::Io(err.kind())
+    }
+}
+
+impl fmt::Display for HostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostError::Disconnected => write!(f, "modem link disconnected"),
+            HostError::Io(kind) => write!(f, "transport I/O error: {kind}"),
+            HostError::Protocol(err) => write!(f, "protocol error: {err:?}"),
+            HostError::Timeout => write!(f, "timed out waiting for modem response"),
+            HostError::Device(err) => write!(
+                f,
+                "modem returned error category 0x{:04X} (detail {}) for request 0x{:04X}, NRF error 0x{:08X}",
+                err.category, err.detail, err.request_code, err.nrf_error
+            ),
+            HostError::SoftDevice(code) => write!(f, "SoftDevice returned error 0x{code:08X}"),
+            HostError::UnexpectedResponse(code) => write!(f, "unexpected response code 0x{code:04X}"),
+            HostError::InvalidResponse => write!(f, "malformed response payload"),
+            HostError::Nak(reason) => write!(f, "modem rejected the request frame: {reason:?}"),
+            HostError::ResponseLost => write!(f, "modem ran the request but its response was lost"),
+        }
+    }
+}
+
+impl std::error::Error for HostError {}
@@ -0,0 +1,172 @@
+//! Modem Event Decoding
+//!
+//! Decodes the payload of `ResponseCode::BleEvent` frames produced by
+//! `BleModemEvent::serialize` in the firmware.
+//!
+//! Event payload format: [Event Header (12)] [Event ID (1)] [Reserved (1)] [Fields (little-endian)]
+//! (see [`EventHeader`]). `ResponseCode::SocEvent` frames carry a single
+//! [`SocEvent`] ID after the same header, `ResponseCode::SystemEvent` frames a
+//! [`SystemEvent`].
+
+pub use ble_modem_protocol::events::{EventHeader, SocEvent, SystemEvent};
+
+use crate::error::HostError;
+
+/// BLE_GAP_EVT_CONNECTED
+pub const EVT_CONNECTED: u8 = 0x11;
+/// BLE_GAP_EVT_DISCONNECTED
+pub const EVT_DISCONNECTED: u8 = 0x12;
+/// BLE_GATTS_EVT_WRITE
+pub const EVT_GATTS_WRITE: u8 = 0x50;
+/// BLE_GATTS_EVT_READ
+pub const EVT_GATTS_READ: u8 = 0x51;
+/// BLE_GATTS_EVT_EXCHANGE_MTU_REQUEST
+pub const EVT_MTU_EXCHANGE: u8 = 0x52;
+/// BLE_GATTS_EVT_CCCD_WRITE
+pub const EVT_CCCD_WRITE: u8 = 0x53;
+
+/// Event frames received from the modem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModemEvent {
+    /// `ResponseCode::BleEvent` frame
+    Ble { header: EventHeader, event: BleEvent },
+    /// `ResponseCode::SocEvent` frame
+    Soc { header: EventHeader, event: SocEvent },
+    /// `ResponseCode::SystemEvent` frame
+    System { header: EventHeader, event: SystemEvent },
+}
+
+/// Typed BLE events forwarded by the modem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BleEvent {
+    Connected {
+        conn_handle: u16,
+        peer_addr: [u8; 6],
+        addr_type: u8,
+    },
+    Disconnected {
+        conn_handle: u16,
+        reason: u8,
+    },
+    GattsWrite {
+        conn_handle: u16,
+        char_handle: u16,
+        data: Vec<u8>,
+    },
+    GattsRead {
+        conn_handle: u16,
+        char_handle: u16,
+    },
+    MtuExchange {
+        conn_handle: u16,
+        client_mtu: u16,
+        server_mtu: u16,
+    },
+    CccdWrite {
+        conn_handle: u16,
+        char_handle: u16,
+        notifications: bool,
+        indications: bool,
+    },
+    /// Event ID not known to this client version
+    Unknown {
+        event_id: u8,
+        data: Vec<u8>,
+    },
+}
+
+/// Little-endian reader for event fields
+struct EventReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> EventReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, HostError> {
+        Ok(self.read_slice(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, HostError> {
+        let bytes = self.read_slice(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_slice(&mut self, len: usize) -> Result<&'a [u8], HostError> {
+        if self.offset + len > self.data.len() {
+            return Err(HostError::InvalidResponse);
+        }
+        let slice = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(slice)
+    }
+}
+
+impl BleEvent {
+    /// Decode a `BleEvent` frame payload
+    pub fn decode(payload: &[u8]) -> Result<Self, HostError> {
+        if payload.len() < 2 {
+            return Err(HostError::InvalidResponse);
+        }
+
+        let event_id = payload[0];
+        let mut reader = EventReader::new(&payload[2..]);
+
+        let event = match event_id {
+            EVT_CONNECTED => {
+                let conn_handle = reader.read_u16()?;
+                let addr_type = reader.read_u8()?;
+                let mut peer_addr = [0u8; 6];
+                peer_addr.copy_from_slice(reader.read_slice(6)?);
+                BleEvent::Connected {
+                    conn_handle,
+                    peer_addr,
+                    addr_type,
+                }
+            }
+            EVT_DISCONNECTED => BleEvent::Disconnected {
+                conn_handle: reader.read_u16()?,
+                reason: reader.read_u8()?,
+            },
+            EVT_GATTS_WRITE => {
+                let conn_handle = reader.read_u16()?;
+                let char_handle = reader.read_u16()?;
+                let len = reader.read_u8()? as usize;
+                BleEvent::GattsWrite {
+                    conn_handle,
+                    char_handle,
+                    data: reader.read_slice(len)?.to_vec(),
+                }
+            }
+            EVT_GATTS_READ => BleEvent::GattsRead {
+                conn_handle: reader.read_u16()?,
+                char_handle: reader.read_u16()?,
+            },
+            EVT_MTU_EXCHANGE => BleEvent::MtuExchange {
+                conn_handle: reader.read_u16()?,
+                client_mtu: reader.read_u16()?,
+                server_mtu: reader.read_u16()?,
+            },
+            EVT_CCCD_WRITE => {
+                let conn_handle = reader.read_u16()?;
+                let char_handle = reader.read_u16()?;
+                let cccd_value = reader.read_u8()?;
+                BleEvent::CccdWrite {
+                    conn_handle,
+                    char_handle,
+                    notifications: cccd_value & 0x01 != 0,
+                    indications: cccd_value & 0x02 != 0,
+                }
+            }
+            _ => BleEvent::Unknown {
+                event_id,
+                data: payload[2..].to_vec(),
+            },
+        };
+
+        Ok(event)
+    }
+}
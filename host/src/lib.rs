@@ -0,0 +1,23 @@
+//! nRF52820 BLE Modem Host Client
+//!
+//! `std` companion to the modem firmware for Linux hosts. It frames requests
+//! with the shared `ble-modem-protocol` crate, exposes typed async methods for
+//! each modem command and decodes `BleEvent` frames into typed events.
+//!
+//! - `client`: Typed command API on top of any [`Transport`]
+//! - `event`: Decoding of event frames sent by the modem
+//! - `transport`: Link abstraction, COBS byte-stream framing and an in-memory loopback for testing
+//! - `types`: Typed command parameters and results
+//! - `error`: Host-side error type
+
+pub mod client;
+pub mod error;
+pub mod event;
+pub mod transport;
+pub mod types;
+
+pub use ble_modem_protocol as protocol;
+pub use client::ModemClient;
+pub use error::HostError;
+pub use event::{BleEvent, ModemEvent};
+pub use transport::{loopback, CobsTransport, LoopbackDevice, LoopbackTransport, Transport};
@@ -0,0 +1,217 @@
+//! Host Transport Layer
+//!
+//! A [`Transport`] moves complete protocol frames between the host and the modem.
+//! SPI links (spidev) implement it outside this crate. [`CobsTransport`] frames
+//! any byte stream (e.g. a serial port opened with RTS/CTS) for the firmware's
+//! UART link, and the in-memory [`loopback`] pair lets the client be exercised
+//! without hardware.
+
+use std::future::Future;
+use std::time::Instant;
+
+use ble_modem_protocol::cobs::{self, CobsDecoder, DELIMITER};
+use ble_modem_protocol::fragment::{Reassembler, MAX_REASSEMBLED_SIZE, REASSEMBLY_TIMEOUT_MS};
+use ble_modem_protocol::{Frame, Packet, ResponseCode, MAX_PAYLOAD_SIZE};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use crate::error::HostError;
+
+/// Link carrying serialized protocol frames to and from the modem
+pub trait Transport {
+    /// Send one complete request frame to the modem
+    fn send_frame(&mut self, frame: &[u8]) -> impl Future<Output = Result<(), HostError>> + Send;
+
+    /// Receive the next complete frame sent by the modem
+    fn receive_frame(&mut self) -> impl Future<Output = Result<Vec<u8>, HostError>> + Send;
+}
+
+/// Turns received frames into packets, reassembling fragments
+pub(crate) struct Defragmenter {
+    reassembler: Reassembler<MAX_REASSEMBLED_SIZE>,
+    epoch: Instant,
+}
+
+impl Defragmenter {
+    pub(crate) fn new() -> Self {
+        Self {
+            reassembler: Reassembler::new(REASSEMBLY_TIMEOUT_MS),
+            epoch: Instant::now(),
+        }
+    }
+
+    /// Feed a response frame, returning the packet once it is complete
+    pub(crate) fn response(&mut self, data: &[u8]) -> Result<Option<Packet>, HostError> {
+        self.push(data, Packet::from_response_frame)
+    }
+
+    /// Feed a request frame, returning the packet once it is complete
+    pub(crate) fn request(&mut self, data: &[u8]) -> Result<Option<Packet>, HostError> {
+        self.push(data, Packet::from_request_frame)
+    }
+
+    fn push(
+        &mut self,
+        data: &[u8],
+        decode: fn(&Frame<'_>) -> Result<Packet, ble_modem_protocol::ProtocolError>,
+    ) -> Result<Option<Packet>, HostError> {
+        let frame = Frame::parse(data)?;
+        if frame.fragment.is_none() {
+            return Ok(Some(decode(&frame)?));
+        }
+
+        let now_ms = self.epoch.elapsed().as_millis() as u64;
+        match self.reassembler.push(&frame, now_ms)? {
+            Some(complete) => Ok(Some(decode(&complete)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Largest COBS-encoded frame (without the delimiter)
+const MAX_ENCODED_FRAME_SIZE: usize = cobs::max_encoded_len(MAX_PAYLOAD_SIZE);
+
+/// Transport over a byte stream, framing each frame with COBS and a 0x00 delimiter
+pub struct CobsTransport<S> {
+    stream: S,
+    decoder: CobsDecoder<MAX_ENCODED_FRAME_SIZE>,
+    read_buffer: [u8; 256],
+    read_pos: usize,
+    read_len: usize,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> CobsTransport<S> {
+    /// Wrap a byte stream connected to the modem's UART
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            decoder: CobsDecoder::new(),
+            read_buffer: [0; 256],
+            read_pos: 0,
+            read_len: 0,
+        }
+    }
+
+    /// Release the underlying stream
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> Transport for CobsTransport<S> {
+    async fn send_frame(&mut self, frame: &[u8]) -> Result<(), HostError> {
+        let mut encoded = [0u8; MAX_ENCODED_FRAME_SIZE + 1];
+        let len = cobs::encode(frame, &mut encoded)?;
+        encoded[len] = DELIMITER;
+        self.stream.write_all(&encoded[..len + 1]).await?;
+        self.stream.flush().await?;
+        Ok(())
+    }
+
+    async fn receive_frame(&mut self) -> Result<Vec<u8>, HostError> {
+        loop {
+            while self.read_pos < self.read_len {
+                let byte = self.read_buffer[self.read_pos];
+                self.read_pos += 1;
+                // Malformed frames are dropped; the decoder resynchronizes on the next delimiter
+                if let Ok(Some(frame)) = self.decoder.push(byte) {
+                    return Ok(frame.to_vec());
+                }
+            }
+
+            self.read_pos = 0;
+            self.read_len = self.stream.read(&mut self.read_buffer).await?;
+            if self.read_len == 0 {
+                return Err(HostError::Disconnected);
+            }
+        }
+    }
+}
+
+/// Host end of an in-memory loopback link
+pub struct LoopbackTransport {
+    to_device: mpsc::UnboundedSender<Vec<u8>>,
+    from_device: mpsc::UnboundedReceiver<Vec<u8>>,
+}
+
+/// Simulated modem end of an in-memory loopback link
+pub struct LoopbackDevice {
+    to_host: mpsc::UnboundedSender<Vec<u8>>,
+    from_host: mpsc::UnboundedReceiver<Vec<u8>>,
+    defragmenter: Defragmenter,
+}
+
+/// Create a connected host/device loopback pair
+pub fn loopback() -> (LoopbackTransport, LoopbackDevice) {
+    let (to_device, from_host) = mpsc::unbounded_channel();
+    let (to_host, from_device) = mpsc::unbounded_channel();
+
+    (
+        LoopbackTransport { to_device, from_device },
+        LoopbackDevice {
+            to_host,
+            from_host,
+            defragmenter: Defragmenter::new(),
+        },
+    )
+}
+
+impl Transport for LoopbackTransport {
+    async fn send_frame(&mut self, frame: &[u8]) -> Result<(), HostError> {
+        self.to_device.send(frame.to_vec()).map_err(|_| HostError::Disconnected)
+    }
+
+    async fn receive_frame(&mut self) -> Result<Vec<u8>, HostError> {
+        self.from_device.recv().await.ok_or(HostError::Disconnected)
+    }
+}
+
+impl LoopbackDevice {
+    /// Receive the next raw frame written by the host
+    pub async fn receive_frame(&mut self) -> Option<Vec<u8>> {
+        self.from_host.recv().await
+    }
+
+    /// Receive and parse the next request written by the host, reassembling fragments
+    pub async fn receive_request(&mut self) -> Option<Result<Packet, HostError>> {
+        loop {
+            let frame = self.receive_frame().await?;
+            match self.defragmenter.request(&frame) {
+                Ok(Some(packet)) => return Some(Ok(packet)),
+                Ok(None) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    /// Send a raw frame to the host
+    pub fn send_frame(&self, frame: &[u8]) -> Result<(), HostError> {
+        self.to_host.send(frame.to_vec()).map_err(|_| HostError::Disconnected)
+    }
+
+    /// Frame and send a response or event to the host
+    pub fn send_response(&self, code: ResponseCode, payload: &[u8]) -> Result<(), HostError> {
+        self.send_sequenced_response(None, code, payload)
+    }
+
+    /// Frame and send a response to `request`, echoing its sequence number
+    pub fn reply(&self, request: &Packet, code: ResponseCode, payload: &[u8]) -> Result<(), HostError> {
+        self.send_sequenced_response(request.seq, code, payload)
+    }
+
+    /// Frame and send a response or event with an explicit sequence number
+    ///
+    /// Payloads too large for one frame are sent as fragments.
+    pub fn send_sequenced_response(
+        &self,
+        seq: Option<u16>,
+        code: ResponseCode,
+        payload: &[u8],
+    ) -> Result<(), HostError> {
+        let packet = Packet::new_response(code, payload)?.with_seq(seq);
+        for frame in packet.response_frames()? {
+            self.send_frame(&frame)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+//! Typed Command Parameters
+//!
+//! Plain data types used by the [`ModemClient`](crate::ModemClient) command API.
+
+use ble_modem_protocol::batch::BatchEntry;
+use ble_modem_protocol::codec::Request;
+use ble_modem_protocol::MAX_MESSAGE_SIZE;
+
+use crate::error::HostError;
+
+/// Bluetooth device address as reported by `GapGetAddr`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BdAddr {
+    /// 0=Public, 1=RandomStatic, 2=RandomPrivateResolvable, 3=RandomPrivateNonResolvable
+    pub addr_type: u8,
+    pub bytes: [u8; 6],
+}
+
+/// Connection parameters (matches SoftDevice `ble_gap_conn_params_t`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnParams {
+    /// Connection interval minimum (1.25ms units)
+    pub min_conn_interval: u16,
+    /// Connection interval maximum (1.25ms units)
+    pub max_conn_interval: u16,
+    pub slave_latency: u16,
+    /// Connection supervisory timeout (10ms units)
+    pub conn_sup_timeout: u16,
+}
+
+/// UUID as encoded in GATTS commands
+pub use ble_modem_protocol::codec::Uuid;
+
+/// Sub-commands collected for [`ModemClient::batch`](crate::ModemClient::batch)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Batch {
+    commands: Vec<u8>,
+    len: usize,
+}
+
+impl Batch {
+    /// Create an empty batch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a typed request from the protocol table
+    pub fn push<'a, R: Request<'a>>(&mut self, request: &R) -> Result<&mut Self, HostError> {
+        let mut payload: heapless::Vec<u8, MAX_MESSAGE_SIZE> = heapless::Vec::new();
+        request.encode(&mut payload)?;
+        let mut entry: heapless::Vec<u8, MAX_MESSAGE_SIZE> = heapless::Vec::new();
+        BatchEntry {
+            code: R::CODE as u16,
+            payload: &payload,
+        }
+        .encode(&mut entry)?;
+        self.commands.extend_from_slice(&entry);
+        self.len += 1;
+        Ok(self)
+    }
+
+    /// Number of sub-commands
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if no sub-commands were added
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Encoded sub-command entries
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.commands
+    }
+}
+
+/// GATT service type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ServiceType {
+    Primary = 1,
+    Secondary = 2,
+}
+
+/// Parameters for `gatts_characteristic_add`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharacteristicParams<'a> {
+    pub service_handle: u16,
+    pub uuid: Uuid,
+    /// BLE characteristic properties bitmask (READ=0x02, WRITE=0x08, NOTIFY=0x10, ...)
+    pub properties: u8,
+    pub max_length: u16,
+    pub initial_value: &'a [u8],
+    pub permissions: u8,
+}
+
+/// Attribute handles assigned to a new characteristic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharacteristicHandles {
+    pub value_handle: u16,
+    pub cccd_handle: u16,
+    pub sccd_handle: u16,
+}
+
+/// Handle value operation type for `gatts_hvx`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum HvxType {
+    Notification = 0x01,
+    Indication = 0x02,
+}
+
+/// TX power role for `gap_set_tx_power`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum TxPowerRole {
+    Advertising = 0x01,
+    Scanning = 0x02,
+    Connection = 0x03,
+}
@@ -0,0 +1,833 @@
+//! Host client tests against the in-memory loopback transport
+
+use ble_modem_host::protocol::batch::{BatchEntries, BatchEntry, BATCH_STOP_ON_ERROR};
+use ble_modem_host::protocol::capabilities::{Capabilities, CommandBitmap, SoftDeviceVersion};
+use ble_modem_host::protocol::codec::{AdvParams, Request};
+use ble_modem_host::protocol::error::{ErrorCategory, ErrorResponse};
+use ble_modem_host::protocol::events::{EventHeader, EventMask, EventSubscription, SocEvent, SystemEvent};
+use ble_modem_host::protocol::identity::DeviceIdentity;
+use ble_modem_host::protocol::link::{LinkStats, Nak, NakReason, LINK_STATS_SIZE, NAK_SIZE};
+use ble_modem_host::protocol::pool::PoolStats;
+use ble_modem_host::protocol::power::{RebootMode, ResetReason, WakePolarity};
+use ble_modem_host::protocol::requests::{BatchRequest, EchoRequest, GapAdvStopRequest};
+use ble_modem_host::protocol::{RequestCode, ResponseCode, PROTOCOL_VERSION, UNSOLICITED_SEQ};
+use ble_modem_host::types::{Batch, CharacteristicHandles, CharacteristicParams, HvxType, ServiceType, Uuid};
+use ble_modem_host::{loopback, BleEvent, HostError, ModemClient, ModemEvent};
+
+#[tokio::test]
+async fn test_get_info_roundtrip() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::GetInfo));
+        assert!(request.payload.is_empty());
+        device
+            .send_response(ResponseCode::Ack, &0x0001u32.to_be_bytes())
+            .unwrap();
+    });
+
+    assert_eq!(client.get_info().await.unwrap(), 0x0001);
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_identity() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let identity = DeviceIdentity {
+        firmware_version: 0x0001,
+        device_id: 0xDEAD_BEEF_0102_0304,
+        addr_type: 1,
+        factory_address: [0x11, 0x22, 0x33, 0x44, 0x55, 0xE6],
+        softdevice: SoftDeviceVersion {
+            fwid: 0x0123,
+            ll_version: 12,
+            company_id: 0x0059,
+        },
+        crate_version: "0.1.0".try_into().unwrap(),
+        build_id: "abcdef01".try_into().unwrap(),
+    };
+    let mut payload: heapless::Vec<u8, 64> = heapless::Vec::new();
+    identity.encode(&mut payload).unwrap();
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::GetInfo));
+        device.reply(&request, ResponseCode::Ack, &payload).unwrap();
+    });
+
+    assert_eq!(client.get_identity().await.unwrap(), identity);
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_hello_capabilities() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let mut commands = CommandBitmap::new();
+    commands.insert(RequestCode::Hello);
+    commands.insert(RequestCode::GattsHvx);
+    let capabilities = Capabilities {
+        protocol_version: PROTOCOL_VERSION,
+        firmware_version: 0x0001,
+        max_message_size: 528,
+        max_services: 8,
+        max_characteristics: 32,
+        tx_pool_size: 8,
+        max_mtu: 128,
+        max_connections: 2,
+        softdevice: SoftDeviceVersion {
+            fwid: 0x0123,
+            ll_version: 0x0C,
+            company_id: 0x0059,
+        },
+        commands,
+        build_id: "c0ffee00".try_into().unwrap(),
+    };
+
+    let expected = capabilities.clone();
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::Hello));
+        assert_eq!(request.payload.as_slice(), &PROTOCOL_VERSION.to_be_bytes());
+
+        let mut payload: heapless::Vec<u8, 128> = heapless::Vec::new();
+        capabilities.encode(&mut payload).unwrap();
+        device.reply(&request, ResponseCode::Ack, &payload).unwrap();
+    });
+
+    let reported = client.hello().await.unwrap();
+    assert_eq!(reported, expected);
+    assert!(reported.commands.contains(RequestCode::GattsHvx));
+    assert!(!reported.commands.contains(RequestCode::GapScanStart));
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_event_subscription_commands() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::SetEventMask));
+        assert_eq!(request.payload.as_slice(), &[0x00, 0x00, 0x00, 0x03]);
+        device.reply(&request, ResponseCode::Ack, &request.payload).unwrap();
+
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::SetCharEventFilter));
+        assert_eq!(request.payload.as_slice(), &[0x00, 0x12, 0x00]);
+        device.reply(&request, ResponseCode::Ack, &[]).unwrap();
+
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::GetEventMask));
+        device
+            .reply(&request, ResponseCode::Ack, &[0x00, 0x00, 0x00, 0x03, 0x01, 0x00, 0x12])
+            .unwrap();
+    });
+
+    let mask = EventMask::GAP_CONNECTION.union(EventMask::GATTS_WRITE);
+    assert_eq!(client.set_event_mask(mask).await.unwrap(), mask);
+    client.set_char_event_filter(0x0012, false).await.unwrap();
+
+    let subscription = client.get_event_mask().await.unwrap();
+    assert_eq!(
+        subscription,
+        EventSubscription {
+            mask,
+            muted_handles: heapless::Vec::from_slice(&[0x0012]).unwrap(),
+        }
+    );
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_clock() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::GetClock));
+        device
+            .reply(&request, ResponseCode::Ack, &12_345_678u64.to_be_bytes())
+            .unwrap();
+    });
+
+    assert_eq!(client.get_clock().await.unwrap(), 12_345_678);
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_link_stats() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let stats = LinkStats {
+        frames_received: 42,
+        frames_sent: 40,
+        invalid_crc: 2,
+        tx_queue_high_water: 3,
+        ..Default::default()
+    };
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::GetLinkStats));
+        assert_eq!(request.payload.as_slice(), &[1]);
+        let mut payload: heapless::Vec<u8, LINK_STATS_SIZE> = heapless::Vec::new();
+        stats.encode(&mut payload).unwrap();
+        device.reply(&request, ResponseCode::Ack, &payload).unwrap();
+    });
+
+    assert_eq!(client.get_link_stats(true).await.unwrap(), stats);
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_pool_stats() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::GetPoolStats));
+        assert_eq!(request.payload.as_slice(), &[0]);
+        device
+            .reply(&request, ResponseCode::Ack, &[8, 1, 6, 0, 0, 0, 3])
+            .unwrap();
+    });
+
+    let stats = client.get_pool_stats(false).await.unwrap();
+    assert_eq!(
+        stats,
+        PoolStats {
+            size: 8,
+            allocated: 1,
+            peak: 6,
+            alloc_failures: 3,
+        }
+    );
+    assert_eq!(stats.available(), 7);
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_shutdown_with_wake_pin() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::Shutdown));
+        assert_eq!(request.payload.as_slice(), &[14, 1]);
+        device.reply(&request, ResponseCode::Ack, &[]).unwrap();
+
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert!(request.payload.is_empty());
+        device.reply(&request, ResponseCode::Ack, &[]).unwrap();
+    });
+
+    client.shutdown_with_wake(14, WakePolarity::High).await.unwrap();
+    client.shutdown().await.unwrap();
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_reboot_into_bootloader_then_boot_event() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::Reboot));
+        assert_eq!(request.payload.as_slice(), &[1]);
+        device.reply(&request, ResponseCode::Ack, &[]).unwrap();
+
+        // [Event Seq (4)] [Timestamp (8)] [Boot (1)] [Reason (1)] [RESETREAS (4)]
+        let mut event = vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x00];
+        event.extend_from_slice(&[0x02, ResetReason::HostRequested.id(), 0, 0, 0, 4]);
+        device.send_response(ResponseCode::SystemEvent, &event).unwrap();
+    });
+
+    client.reboot_into(RebootMode::Bootloader).await.unwrap();
+    device_task.await.unwrap();
+
+    assert_eq!(
+        client.next_event().await.unwrap(),
+        ModemEvent::System {
+            header: EventHeader {
+                seq: 0,
+                timestamp_us: 256,
+            },
+            event: SystemEvent::Boot {
+                reason: ResetReason::HostRequested,
+                resetreas: 4,
+            },
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_factory_reset_sends_token() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::FactoryReset));
+        assert_eq!(request.payload.as_slice(), b"FRST");
+        device.reply(&request, ResponseCode::Ack, &[]).unwrap();
+    });
+
+    client.factory_reset().await.unwrap();
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_adv_set_params_keeps_data() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::GapAdvSetConfigure));
+        assert_eq!(
+            request.payload.as_slice(),
+            &[1, 0, 0, 0, 0, 0, 0x00, 0x20, 0x01, 0xF4, 2]
+        );
+        device.reply(&request, ResponseCode::Ack, &[0, 0, 0, 0, 1]).unwrap();
+    });
+
+    let params = AdvParams {
+        interval: 0x0020,
+        timeout: 500,
+        filter_policy: 2,
+    };
+    assert_eq!(client.gap_adv_set_params(1, params).await, Ok(1));
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_echo_roundtrip() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::Echo));
+        device.send_response(ResponseCode::Ack, &request.payload).unwrap();
+    });
+
+    assert_eq!(client.echo(b"hello modem").await.unwrap(), b"hello modem");
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_gatts_service_and_characteristic_encoding() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::GattsServiceAdd));
+        assert_eq!(request.payload.as_slice(), &[0x00, 0x0D, 0x18, 0x01]);
+        device
+            .send_response(ResponseCode::Ack, &0x0010u16.to_be_bytes())
+            .unwrap();
+
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::GattsCharacteristicAdd));
+        assert_eq!(
+            request.payload.as_slice(),
+            &[0x00, 0x10, 0x02, 0x00, 0x34, 0x12, 0x12, 0x00, 0x14, 0x01, 0xAA, 0x00]
+        );
+        device
+            .send_response(ResponseCode::Ack, &[0x00, 0x12, 0x00, 0x13, 0x00, 0x00])
+            .unwrap();
+    });
+
+    let service = client
+        .gatts_service_add(Uuid::Uuid16(0x180D), ServiceType::Primary)
+        .await
+        .unwrap();
+    assert_eq!(service, 0x0010);
+
+    let handles = client
+        .gatts_characteristic_add(&CharacteristicParams {
+            service_handle: service,
+            uuid: Uuid::VendorSpecific {
+                base_id: 0,
+                offset: 0x1234,
+            },
+            properties: 0x12,
+            max_length: 20,
+            initial_value: &[0xAA],
+            permissions: 0,
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        handles,
+        CharacteristicHandles {
+            value_handle: 0x12,
+            cccd_handle: 0x13,
+            sccd_handle: 0,
+        }
+    );
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_error_response() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        device.receive_request().await.unwrap().unwrap();
+        device
+            .send_response(ResponseCode::Error, &0x0002u16.to_be_bytes())
+            .unwrap();
+    });
+
+    let result = client.gatts_hvx(1, 0x12, HvxType::Notification, &[1, 2, 3]).await;
+    assert_eq!(
+        result,
+        Err(HostError::Device(ErrorResponse {
+            category: ErrorCategory::InvalidPayload as u16,
+            request_code: 0,
+            detail: 0,
+            nrf_error: 0,
+        }))
+    );
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_batch_results() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let mut batch = Batch::new();
+    batch
+        .push(&EchoRequest { data: b"hi" })
+        .unwrap()
+        .push(&GapAdvStopRequest { adv_handle: 1 })
+        .unwrap();
+    assert_eq!(batch.len(), 2);
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::Batch));
+        let decoded = BatchRequest::decode(&request.payload).unwrap();
+        assert_eq!(decoded.flags, BATCH_STOP_ON_ERROR);
+        let codes: Vec<u16> = BatchEntries::new(decoded.commands)
+            .map(|entry| entry.unwrap().code)
+            .collect();
+        assert_eq!(codes, [RequestCode::Echo as u16, RequestCode::GapAdvStop as u16]);
+
+        // Echo succeeded, advertising stop failed with a SoftDevice error
+        let error = ErrorResponse {
+            category: ErrorCategory::SoftDevice as u16,
+            request_code: RequestCode::GapAdvStop as u16,
+            detail: 0,
+            nrf_error: 8,
+        };
+        let mut error_payload: heapless::Vec<u8, 16> = heapless::Vec::new();
+        error.encode(&mut error_payload).unwrap();
+
+        let mut payload: heapless::Vec<u8, 64> = heapless::Vec::new();
+        payload.push(2).unwrap();
+        BatchEntry {
+            code: ResponseCode::Ack.to_u16(),
+            payload: b"hi",
+        }
+        .encode(&mut payload)
+        .unwrap();
+        BatchEntry {
+            code: ResponseCode::Error.to_u16(),
+            payload: &error_payload,
+        }
+        .encode(&mut payload)
+        .unwrap();
+        device.reply(&request, ResponseCode::Ack, &payload).unwrap();
+        error
+    });
+
+    let results = client.batch(&batch, true).await.unwrap();
+    let error = device_task.await.unwrap();
+    assert_eq!(results, [Ok(b"hi".to_vec()), Err(HostError::Device(error))]);
+}
+
+#[tokio::test]
+async fn test_structured_error_response() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let error = ErrorResponse {
+        category: ErrorCategory::SoftDevice as u16,
+        request_code: RequestCode::GapDisconnect as u16,
+        detail: 0,
+        nrf_error: 0x3002,
+    };
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        let mut payload: heapless::Vec<u8, 16> = heapless::Vec::new();
+        error.encode(&mut payload).unwrap();
+        device.reply(&request, ResponseCode::Error, &payload).unwrap();
+    });
+
+    match client.gap_disconnect(1, 0x13).await {
+        Err(HostError::Device(err)) => {
+            assert_eq!(err, error);
+            assert_eq!(err.category(), Some(ErrorCategory::SoftDevice));
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_softdevice_status_is_reported() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        device.receive_request().await.unwrap().unwrap();
+        // NRF_ERROR_NO_MEM
+        device
+            .send_response(ResponseCode::Ack, &0x0004u32.to_be_bytes())
+            .unwrap();
+    });
+
+    assert_eq!(client.gap_adv_start(1, 0).await, Err(HostError::SoftDevice(0x0004)));
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_events_interleaved_with_response_are_queued() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        device.receive_request().await.unwrap().unwrap();
+        // Connected event: seq 7 at 1000us, handle 0x0001, addr type 1, address
+        device
+            .send_response(
+                ResponseCode::BleEvent,
+                &[
+                    0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0x03, 0xE8, 0x11, 0x00, 0x01, 0x00, 0x01, 1, 2, 3, 4, 5, 6,
+                ],
+            )
+            .unwrap();
+        device.send_response(ResponseCode::Ack, &[0, 0, 0, 0]).unwrap();
+        // GATTS write event after the response: seq 8 at 2000us
+        device
+            .send_response(
+                ResponseCode::BleEvent,
+                &[
+                    0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0x07, 0xD0, 0x50, 0x00, 0x01, 0x00, 0x12, 0x00, 2, 0xBE, 0xEF,
+                ],
+            )
+            .unwrap();
+    });
+
+    client.gap_adv_stop(1).await.unwrap();
+    assert_eq!(
+        client.try_next_event(),
+        Some(ModemEvent::Ble {
+            header: EventHeader {
+                seq: 7,
+                timestamp_us: 1000,
+            },
+            event: BleEvent::Connected {
+                conn_handle: 1,
+                peer_addr: [1, 2, 3, 4, 5, 6],
+                addr_type: 1,
+            },
+        })
+    );
+    assert_eq!(
+        client.next_event().await.unwrap(),
+        ModemEvent::Ble {
+            header: EventHeader {
+                seq: 8,
+                timestamp_us: 2000,
+            },
+            event: BleEvent::GattsWrite {
+                conn_handle: 1,
+                char_handle: 0x12,
+                data: vec![0xBE, 0xEF],
+            },
+        }
+    );
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_soc_event_is_decoded() {
+    let (transport, device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    // Power-failure warning: seq 3 at 500us
+    device
+        .send_response(
+            ResponseCode::SocEvent,
+            &[0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0x01, 0xF4, 0x01],
+        )
+        .unwrap();
+
+    assert_eq!(
+        client.next_event().await.unwrap(),
+        ModemEvent::Soc {
+            header: EventHeader {
+                seq: 3,
+                timestamp_us: 500,
+            },
+            event: SocEvent::PowerFailureWarning,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_pool_low_event_is_decoded() {
+    let (transport, device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    // Pool low with 6 of 8 buffers in use: seq 4 at 1000us
+    device
+        .send_response(
+            ResponseCode::SystemEvent,
+            &[0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0x03, 0xE8, 0x01, 6, 8],
+        )
+        .unwrap();
+
+    assert_eq!(
+        client.next_event().await.unwrap(),
+        ModemEvent::System {
+            header: EventHeader {
+                seq: 4,
+                timestamp_us: 1000,
+            },
+            event: SystemEvent::PoolLow { allocated: 6, size: 8 },
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_corrupted_frame_is_rejected() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        device.receive_request().await.unwrap().unwrap();
+        device.send_frame(&[0x00, 0x06, 0xAC, 0x50, 0xDE, 0xAD]).unwrap();
+    });
+
+    assert!(matches!(client.shutdown().await, Err(HostError::Protocol(_))));
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_timeout_without_response() {
+    let (transport, _device) = loopback();
+    let mut client = ModemClient::with_timeout(transport, std::time::Duration::from_millis(10));
+
+    assert_eq!(client.reboot().await, Err(HostError::Timeout));
+}
+
+#[tokio::test]
+async fn test_stale_response_is_discarded() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::with_timeout(transport, std::time::Duration::from_millis(50));
+
+    // First request is never answered in time
+    assert_eq!(client.get_info().await, Err(HostError::Timeout));
+    let first = device.receive_request().await.unwrap().unwrap();
+
+    let device_task = tokio::spawn(async move {
+        let second = device.receive_request().await.unwrap().unwrap();
+        assert_ne!(first.seq, second.seq);
+
+        // The late answer to the first request must not satisfy the second
+        device
+            .reply(&first, ResponseCode::Ack, &0x0001u32.to_be_bytes())
+            .unwrap();
+        device.reply(&second, ResponseCode::Ack, b"second").unwrap();
+    });
+
+    assert_eq!(client.echo(b"second").await.unwrap(), b"second");
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_nak_triggers_retransmission() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        let first = device.receive_request().await.unwrap().unwrap();
+        // Corrupted frame: the device could not read the sequence number
+        device
+            .send_sequenced_response(Some(UNSOLICITED_SEQ), ResponseCode::Nak, &[0x02, 0x00, 0x00])
+            .unwrap();
+
+        let retransmitted = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(retransmitted.seq, first.seq);
+        assert_eq!(retransmitted.payload, first.payload);
+        device.reply(&retransmitted, ResponseCode::Ack, b"ping").unwrap();
+    });
+
+    assert_eq!(client.echo(b"ping").await.unwrap(), b"ping");
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_nak_limits() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::with_timeout(transport, std::time::Duration::from_millis(50)).with_max_retransmits(1);
+
+    let device_task = tokio::spawn(async move {
+        // Queue full twice: one retransmission, then give up
+        for _ in 0..2 {
+            let request = device.receive_request().await.unwrap().unwrap();
+            let mut nak: heapless::Vec<u8, NAK_SIZE> = heapless::Vec::new();
+            Nak {
+                reason: NakReason::Overflow,
+                seq: request.seq.unwrap(),
+            }
+            .encode(&mut nak)
+            .unwrap();
+            device.reply(&request, ResponseCode::Nak, &nak).unwrap();
+        }
+
+        // A duplicate is never resent: the modem already ran the request
+        let request = device.receive_request().await.unwrap().unwrap();
+        let seq = request.seq.unwrap().to_be_bytes();
+        device
+            .reply(
+                &request,
+                ResponseCode::Nak,
+                &[NakReason::Duplicate as u8, seq[0], seq[1]],
+            )
+            .unwrap();
+        // Keep the link open while the client waits for the lost response
+        device
+    });
+
+    assert_eq!(client.echo(b"a").await, Err(HostError::Nak(NakReason::Overflow)));
+    assert_eq!(client.echo(b"b").await, Err(HostError::ResponseLost));
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_duplicate_nak_waits_for_response() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+
+    let device_task = tokio::spawn(async move {
+        // The retransmission of a request still running is NAKed, then the original response follows
+        let request = device.receive_request().await.unwrap().unwrap();
+        let seq = request.seq.unwrap().to_be_bytes();
+        device
+            .reply(
+                &request,
+                ResponseCode::Nak,
+                &[NakReason::Duplicate as u8, seq[0], seq[1]],
+            )
+            .unwrap();
+        device.reply(&request, ResponseCode::Ack, b"ping").unwrap();
+    });
+
+    assert_eq!(client.echo(b"ping").await.unwrap(), b"ping");
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_large_payload_is_fragmented() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport);
+    let data: Vec<u8> = (0..512u16).map(|i| i as u8).collect();
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.request_code(), Some(RequestCode::Echo));
+        assert_eq!(request.payload.len(), 512);
+        device.reply(&request, ResponseCode::Ack, &request.payload).unwrap();
+    });
+
+    assert_eq!(client.echo(&data).await.unwrap(), data);
+    device_task.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_legacy_framing() {
+    let (transport, mut device) = loopback();
+    let mut client = ModemClient::new(transport).with_legacy_framing();
+
+    let device_task = tokio::spawn(async move {
+        let request = device.receive_request().await.unwrap().unwrap();
+        assert_eq!(request.seq, None);
+        device.reply(&request, ResponseCode::Ack, &request.payload).unwrap();
+    });
+
+    assert_eq!(client.echo(b"legacy").await.unwrap(), b"legacy");
+    device_task.await.unwrap();
+}
+
+#[test]
+fn test_decode_cccd_write_event() {
+    let event = BleEvent::decode(&[0x53, 0x00, 0x02, 0x00, 0x13, 0x00, 0x03]).unwrap();
+    assert_eq!(
+        event,
+        BleEvent::CccdWrite {
+            conn_handle: 2,
+            char_handle: 0x13,
+            notifications: true,
+            indications: true,
+        }
+    );
+
+    assert_eq!(BleEvent::decode(&[0x12, 0x00, 0x01]), Err(HostError::InvalidResponse));
+}
+
+#[tokio::test]
+async fn test_cobs_transport_roundtrip() {
+    use ble_modem_host::protocol::cobs::{self, CobsDecoder, DELIMITER};
+    use ble_modem_host::protocol::Packet;
+    use ble_modem_host::CobsTransport;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let (host_stream, mut device_stream) = tokio::io::duplex(1024);
+    let mut client = ModemClient::new(CobsTransport::new(host_stream));
+
+    let device_task = tokio::spawn(async move {
+        let mut decoder = CobsDecoder::<512>::new();
+        let request = loop {
+            let byte = device_stream.read_u8().await.unwrap();
+            if let Some(frame) = decoder.push(byte).unwrap() {
+                break Packet::new_request(frame).unwrap();
+            }
+        };
+        assert_eq!(request.request_code(), Some(RequestCode::Echo));
+
+        let response = Packet::new_response(ResponseCode::Ack, &request.payload)
+            .unwrap()
+            .with_seq(request.seq)
+            .serialize()
+            .unwrap();
+        let mut encoded = [0u8; 512];
+        let len = cobs::encode(&response, &mut encoded).unwrap();
+        // Leading delimiter and noise before the frame must be skipped
+        device_stream.write_all(&[DELIMITER, 0x05, DELIMITER]).await.unwrap();
+        device_stream.write_all(&encoded[..len]).await.unwrap();
+        device_stream.write_u8(DELIMITER).await.unwrap();
+    });
+
+    assert_eq!(client.echo(&[0x00, 0xAB, 0x00]).await.unwrap(), [0x00, 0xAB, 0x00]);
+    device_task.await.unwrap();
+}
@@ -0,0 +1,106 @@
+//! Batched Commands
+//!
+//! BATCH carries several sub-commands in one request. The modem runs them in
+//! order and answers with a single `Ack` listing every sub-result, so bringing
+//! up a GATT database takes one round trip instead of dozens.
+//!
+//! BATCH request payload (big-endian):
+//! [Flags (1)] { [Request Code (2)] [Payload Length (2)] [Payload] } ...
+//!
+//! BATCH response payload:
+//! [Executed Count (1)] { [Response Code (2)] [Payload Length (2)] [Payload] } ...
+//!
+//! Each sub-result is the response the sub-command would have produced on its
+//! own: an `Ack` with its results or an `Error` payload. With
+//! [`BATCH_STOP_ON_ERROR`] set, execution stops at the first `Error` and the
+//! remaining sub-commands are neither run nor listed. Batches cannot be nested.
+
+use heapless::Vec;
+
+use crate::serialization::{write_slice, write_u16, PayloadReader};
+use crate::ProtocolError;
+
+/// Stop executing a batch after the first sub-command that fails
+pub const BATCH_STOP_ON_ERROR: u8 = 0x01;
+
+/// Size of an entry header (code and payload length)
+pub const BATCH_ENTRY_HEADER_SIZE: usize = 4;
+
+/// A sub-command of a batch request, or a sub-result of its response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BatchEntry<'a> {
+    /// Request code (sub-commands) or response code (sub-results)
+    pub code: u16,
+    pub payload: &'a [u8],
+}
+
+impl BatchEntry<'_> {
+    /// Append the encoded entry to a buffer
+    pub fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        let len = u16::try_from(self.payload.len()).map_err(|_| ProtocolError::InvalidLength)?;
+        write_u16(buffer, self.code)?;
+        write_u16(buffer, len)?;
+        write_slice(buffer, self.payload)
+    }
+}
+
+/// Iterator over the entries of a batch request or response
+///
+/// Yields an error once and stops if an entry is truncated.
+pub struct BatchEntries<'a> {
+    reader: PayloadReader<'a>,
+    len: usize,
+    failed: bool,
+}
+
+impl<'a> BatchEntries<'a> {
+    /// Iterate over the entries packed back to back in `data`
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            reader: PayloadReader::new(data),
+            len: data.len(),
+            failed: false,
+        }
+    }
+
+    fn read_entry(&mut self) -> Result<BatchEntry<'a>, ProtocolError> {
+        let code = self.reader.read_u16()?;
+        let len = self.reader.read_u16()? as usize;
+        let payload = self.reader.read_slice(len)?;
+        Ok(BatchEntry { code, payload })
+    }
+}
+
+impl<'a> Iterator for BatchEntries<'a> {
+    type Item = Result<BatchEntry<'a>, ProtocolError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.reader.offset() == self.len {
+            return None;
+        }
+        let entry = self.read_entry();
+        self.failed = entry.is_err();
+        Some(entry)
+    }
+}
+
+/// Decoded BATCH response
+pub struct BatchResponse<'a> {
+    /// Number of sub-commands that were run
+    pub executed: u8,
+    results: &'a [u8],
+}
+
+impl<'a> BatchResponse<'a> {
+    /// Split a BATCH response payload into its count and sub-results
+    pub fn decode(payload: &'a [u8]) -> Result<Self, ProtocolError> {
+        let (&executed, results) = payload.split_first().ok_or(ProtocolError::InvalidLength)?;
+        Ok(Self { executed, results })
+    }
+
+    /// Sub-results, in execution order
+    pub fn results(&self) -> BatchEntries<'a> {
+        BatchEntries::new(self.results)
+    }
+}
@@ -0,0 +1,147 @@
+//! Capability Negotiation
+//!
+//! The HELLO command returns a [`Capabilities`] record describing the firmware
+//! build: protocol version, supported commands, resource limits and versions.
+//! Hosts use it to adapt to a firmware build instead of probing commands.
+//!
+//! Response layout (big-endian):
+//! [Protocol Version (2)] [Firmware Version (4)] [Max Message Size (2)]
+//! [Max Services (1)] [Max Characteristics (1)] [TX Pool Size (1)] [Max MTU (2)]
+//! [Max Connections (1)] [SoftDevice FWID (2)] [LL Version (1)] [Company ID (2)]
+//! [Command Bitmap (32)] [Build ID Length (1)] [Build ID (0-N)]
+
+use heapless::{String, Vec};
+
+use crate::serialization::{write_slice, write_u16, write_u32, write_u8, PayloadReader};
+use crate::{ProtocolError, RequestCode};
+
+/// Size of the supported-command bitmap in bytes (one bit per code below 0x100)
+pub const COMMAND_BITMAP_SIZE: usize = 32;
+
+/// Maximum build ID length
+pub const MAX_BUILD_ID_LEN: usize = 16;
+
+/// Set of request codes supported by a firmware build
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CommandBitmap([u8; COMMAND_BITMAP_SIZE]);
+
+impl CommandBitmap {
+    /// Create an empty bitmap
+    pub const fn new() -> Self {
+        Self([0; COMMAND_BITMAP_SIZE])
+    }
+
+    /// Mark a command as supported
+    pub fn insert(&mut self, code: RequestCode) {
+        if let Some((byte, bit)) = Self::position(code as u16) {
+            self.0[byte] |= bit;
+        }
+    }
+
+    /// Check if a command is supported
+    pub fn contains(&self, code: RequestCode) -> bool {
+        Self::position(code as u16).is_some_and(|(byte, bit)| self.0[byte] & bit != 0)
+    }
+
+    /// Raw bitmap bytes (bit `n % 8` of byte `n / 8` is request code `n`)
+    pub fn as_bytes(&self) -> &[u8; COMMAND_BITMAP_SIZE] {
+        &self.0
+    }
+
+    fn position(code: u16) -> Option<(usize, u8)> {
+        let code = code as usize;
+        (code < COMMAND_BITMAP_SIZE * 8).then(|| (code / 8, 1 << (code % 8)))
+    }
+}
+
+/// SoftDevice version as reported by `sd_ble_version_get`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SoftDeviceVersion {
+    /// SoftDevice firmware ID (e.g. 0x0123 for S140 7.3.0)
+    pub fwid: u16,
+    /// Link layer version
+    pub ll_version: u8,
+    /// Company identifier
+    pub company_id: u16,
+}
+
+/// Capabilities reported by the HELLO command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    pub protocol_version: u16,
+    /// Firmware version in BCD format (same value as GET_INFO)
+    pub firmware_version: u32,
+    /// Largest packet payload, including fragmentation
+    pub max_message_size: u16,
+    pub max_services: u8,
+    pub max_characteristics: u8,
+    pub tx_pool_size: u8,
+    pub max_mtu: u16,
+    pub max_connections: u8,
+    pub softdevice: SoftDeviceVersion,
+    pub commands: CommandBitmap,
+    /// Build identifier (git revision of the firmware)
+    pub build_id: String<MAX_BUILD_ID_LEN>,
+}
+
+impl Capabilities {
+    /// Append the encoded record to a payload buffer
+    pub fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u16(buffer, self.protocol_version)?;
+        write_u32(buffer, self.firmware_version)?;
+        write_u16(buffer, self.max_message_size)?;
+        write_u8(buffer, self.max_services)?;
+        write_u8(buffer, self.max_characteristics)?;
+        write_u8(buffer, self.tx_pool_size)?;
+        write_u16(buffer, self.max_mtu)?;
+        write_u8(buffer, self.max_connections)?;
+        write_u16(buffer, self.softdevice.fwid)?;
+        write_u8(buffer, self.softdevice.ll_version)?;
+        write_u16(buffer, self.softdevice.company_id)?;
+        write_slice(buffer, self.commands.as_bytes())?;
+        write_u8(buffer, self.build_id.len() as u8)?;
+        write_slice(buffer, self.build_id.as_bytes())
+    }
+
+    /// Decode a record from a HELLO response payload
+    pub fn decode(payload: &[u8]) -> Result<Self, ProtocolError> {
+        let mut reader = PayloadReader::new(payload);
+
+        let protocol_version = reader.read_u16()?;
+        let firmware_version = reader.read_u32()?;
+        let max_message_size = reader.read_u16()?;
+        let max_services = reader.read_u8()?;
+        let max_characteristics = reader.read_u8()?;
+        let tx_pool_size = reader.read_u8()?;
+        let max_mtu = reader.read_u16()?;
+        let max_connections = reader.read_u8()?;
+        let softdevice = SoftDeviceVersion {
+            fwid: reader.read_u16()?,
+            ll_version: reader.read_u8()?,
+            company_id: reader.read_u16()?,
+        };
+
+        let mut bitmap = [0u8; COMMAND_BITMAP_SIZE];
+        bitmap.copy_from_slice(reader.read_slice(COMMAND_BITMAP_SIZE)?);
+
+        let build_id_len = reader.read_u8()? as usize;
+        let build_id =
+            core::str::from_utf8(reader.read_slice(build_id_len)?).map_err(|_| ProtocolError::InvalidData)?;
+
+        Ok(Self {
+            protocol_version,
+            firmware_version,
+            max_message_size,
+            max_services,
+            max_characteristics,
+            tx_pool_size,
+            max_mtu,
+            max_connections,
+            softdevice,
+            commands: CommandBitmap(bitmap),
+            build_id: String::try_from(build_id).map_err(|_| ProtocolError::InvalidLength)?,
+        })
+    }
+}
@@ -0,0 +1,180 @@
+//! Consistent Overhead Byte Stuffing (COBS)
+//!
+//! Byte-stream links (UART) carry the same frames as the SPI link, COBS-encoded
+//! and terminated by a `0x00` delimiter. Encoding removes every zero byte from
+//! the frame so the delimiter is unambiguous, at a cost of at most one byte per
+//! 254 bytes of input plus one.
+
+use heapless::Vec;
+
+use crate::ProtocolError;
+
+/// Frame delimiter on the wire
+pub const DELIMITER: u8 = 0x00;
+
+/// Worst-case encoded size of `len` bytes (without the delimiter)
+pub const fn max_encoded_len(len: usize) -> usize {
+    len + len / 254 + 1
+}
+
+/// Encode `src` into `dst`, returning the encoded length (without the delimiter)
+pub fn encode(src: &[u8], dst: &mut [u8]) -> Result<usize, ProtocolError> {
+    let mut code_index = 0;
+    let mut write = 1;
+    let mut code: u8 = 1;
+
+    for &byte in src {
+        if byte != 0 {
+            *dst.get_mut(write).ok_or(ProtocolError::BufferFull)? = byte;
+            write += 1;
+            code += 1;
+        }
+
+        // Close the block on a zero byte or once it holds 254 data bytes
+        if byte == 0 || code == 0xFF {
+            *dst.get_mut(code_index).ok_or(ProtocolError::BufferFull)? = code;
+            code_index = write;
+            write += 1;
+            code = 1;
+        }
+    }
+
+    *dst.get_mut(code_index).ok_or(ProtocolError::BufferFull)? = code;
+    Ok(write)
+}
+
+/// Decode `src` (without the delimiter) into `dst`, returning the decoded length
+pub fn decode(src: &[u8], dst: &mut [u8]) -> Result<usize, ProtocolError> {
+    let mut read = 0;
+    let mut write = 0;
+
+    while read < src.len() {
+        let code = src[read];
+        if code == 0 {
+            return Err(ProtocolError::InvalidData);
+        }
+        read += 1;
+
+        let run = code as usize - 1;
+        let block = src.get(read..read + run).ok_or(ProtocolError::InvalidData)?;
+        if block.contains(&0) {
+            return Err(ProtocolError::InvalidData);
+        }
+        dst.get_mut(write..write + run)
+            .ok_or(ProtocolError::BufferFull)?
+            .copy_from_slice(block);
+        read += run;
+        write += run;
+
+        // A zero follows every block except full (0xFF) blocks and the final one
+        if code != 0xFF && read < src.len() {
+            *dst.get_mut(write).ok_or(ProtocolError::BufferFull)? = 0;
+            write += 1;
+        }
+    }
+
+    Ok(write)
+}
+
+/// Decode a COBS block in place, returning the decoded length
+///
+/// Decoded data is never longer than its encoding and never moves forward, so
+/// the receive buffer can be reused.
+pub fn decode_in_place(buffer: &mut [u8]) -> Result<usize, ProtocolError> {
+    let mut read = 0;
+    let mut write = 0;
+
+    while read < buffer.len() {
+        let code = buffer[read];
+        if code == 0 {
+            return Err(ProtocolError::InvalidData);
+        }
+        read += 1;
+
+        let run = code as usize - 1;
+        if read + run > buffer.len() || buffer[read..read + run].contains(&0) {
+            return Err(ProtocolError::InvalidData);
+        }
+        buffer.copy_within(read..read + run, write);
+        read += run;
+        write += run;
+
+        if code != 0xFF && read < buffer.len() {
+            buffer[write] = 0;
+            write += 1;
+        }
+    }
+
+    Ok(write)
+}
+
+/// Streaming decoder splitting a byte stream into delimited frames
+///
+/// Feed received bytes with [`CobsDecoder::push`]; every delimiter completes a
+/// frame. Oversized or corrupt frames are reported once and the decoder
+/// resynchronizes on the next delimiter.
+pub struct CobsDecoder<const N: usize> {
+    buffer: Vec<u8, N>,
+    overflow: bool,
+    complete: bool,
+}
+
+impl<const N: usize> CobsDecoder<N> {
+    /// Create an empty decoder
+    pub const fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            overflow: false,
+            complete: false,
+        }
+    }
+
+    /// Feed one byte, returning the decoded frame when a delimiter completes it
+    ///
+    /// Empty frames (back-to-back delimiters) are skipped.
+    pub fn push(&mut self, byte: u8) -> Result<Option<&[u8]>, ProtocolError> {
+        if core::mem::take(&mut self.complete) {
+            self.buffer.clear();
+        }
+
+        if byte != DELIMITER {
+            if self.buffer.push(byte).is_err() {
+                self.overflow = true;
+            }
+            return Ok(None);
+        }
+
+        if core::mem::take(&mut self.overflow) {
+            self.buffer.clear();
+            return Err(ProtocolError::BufferFull);
+        }
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+
+        match decode_in_place(&mut self.buffer) {
+            Ok(len) => {
+                self.buffer.truncate(len);
+                self.complete = true;
+                Ok(Some(&self.buffer))
+            }
+            Err(err) => {
+                self.buffer.clear();
+                Err(err)
+            }
+        }
+    }
+
+    /// Discard any partially received frame
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.overflow = false;
+        self.complete = false;
+    }
+}
+
+impl<const N: usize> Default for CobsDecoder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,438 @@
+//! Payload Field Codecs
+//!
+//! Building blocks for the request structs generated by `protocol_table!`.
+//! Each field of a request names a codec that fixes its wire encoding:
+//! - [`Be`]: big-endian integers, `bool`, fixed byte arrays and [`Uuid`] (the default)
+//! - [`Le`]: little-endian integers (GAP connection commands)
+//! - [`Len8`] / [`Len16`]: byte slices behind a u8 / big-endian u16 length prefix
+//! - [`Rest`]: all remaining payload bytes
+//! - [`Opt`]: trailing value that may be omitted
+//!
+//! A codec's `MIN_LEN` is the number of bytes the field needs at minimum; the
+//! generated `MIN_LEN` of a request is their sum.
+
+use heapless::Vec;
+
+use crate::serialization::{write_slice, write_u16, write_u32, write_u8, PayloadReader};
+use crate::ProtocolError;
+
+/// Wire encoding of a field of type `T`
+pub trait Codec<'a, T> {
+    /// Minimum number of payload bytes the field occupies
+    const MIN_LEN: usize;
+
+    /// Read the field from a request payload
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<T, ProtocolError>;
+
+    /// Append the field to a request payload
+    fn encode<const N: usize>(value: &T, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError>;
+}
+
+/// Big-endian / raw encoding (default)
+pub struct Be;
+
+/// Little-endian integer encoding
+pub struct Le;
+
+/// Byte slice prefixed with a u8 length
+pub struct Len8;
+
+/// Byte slice prefixed with a big-endian u16 length
+pub struct Len16;
+
+/// Remaining payload bytes
+pub struct Rest;
+
+/// Optional trailing value, present if any payload bytes are left
+pub struct Opt;
+
+impl<'a> Codec<'a, u8> for Be {
+    const MIN_LEN: usize = 1;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<u8, ProtocolError> {
+        reader.read_u8()
+    }
+
+    fn encode<const N: usize>(value: &u8, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u8(buffer, *value)
+    }
+}
+
+impl<'a> Codec<'a, i8> for Be {
+    const MIN_LEN: usize = 1;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<i8, ProtocolError> {
+        reader.read_u8().map(|value| value as i8)
+    }
+
+    fn encode<const N: usize>(value: &i8, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u8(buffer, *value as u8)
+    }
+}
+
+impl<'a> Codec<'a, bool> for Be {
+    const MIN_LEN: usize = 1;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<bool, ProtocolError> {
+        reader.read_u8().map(|value| value != 0)
+    }
+
+    fn encode<const N: usize>(value: &bool, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u8(buffer, *value as u8)
+    }
+}
+
+impl<'a> Codec<'a, u16> for Be {
+    const MIN_LEN: usize = 2;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<u16, ProtocolError> {
+        reader.read_u16()
+    }
+
+    fn encode<const N: usize>(value: &u16, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u16(buffer, *value)
+    }
+}
+
+impl<'a> Codec<'a, u32> for Be {
+    const MIN_LEN: usize = 4;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<u32, ProtocolError> {
+        reader.read_u32()
+    }
+
+    fn encode<const N: usize>(value: &u32, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u32(buffer, *value)
+    }
+}
+
+impl<'a, const L: usize> Codec<'a, [u8; L]> for Be {
+    const MIN_LEN: usize = L;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<[u8; L], ProtocolError> {
+        let mut value = [0u8; L];
+        value.copy_from_slice(reader.read_slice(L)?);
+        Ok(value)
+    }
+
+    fn encode<const N: usize>(value: &[u8; L], buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_slice(buffer, value)
+    }
+}
+
+impl<'a> Codec<'a, u16> for Le {
+    const MIN_LEN: usize = 2;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<u16, ProtocolError> {
+        let bytes = reader.read_slice(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn encode<const N: usize>(value: &u16, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_slice(buffer, &value.to_le_bytes())
+    }
+}
+
+impl<'a> Codec<'a, u32> for Le {
+    const MIN_LEN: usize = 4;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<u32, ProtocolError> {
+        let bytes = reader.read_slice(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn encode<const N: usize>(value: &u32, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_slice(buffer, &value.to_le_bytes())
+    }
+}
+
+impl<'a> Codec<'a, &'a [u8]> for Len8 {
+    const MIN_LEN: usize = 1;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<&'a [u8], ProtocolError> {
+        let len = reader.read_u8()? as usize;
+        reader.read_slice(len)
+    }
+
+    fn encode<const N: usize>(value: &&'a [u8], buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        let len = u8::try_from(value.len()).map_err(|_| ProtocolError::InvalidLength)?;
+        write_u8(buffer, len)?;
+        write_slice(buffer, value)
+    }
+}
+
+impl<'a> Codec<'a, &'a [u8]> for Len16 {
+    const MIN_LEN: usize = 2;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<&'a [u8], ProtocolError> {
+        let len = reader.read_u16()? as usize;
+        reader.read_slice(len)
+    }
+
+    fn encode<const N: usize>(value: &&'a [u8], buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        let len = u16::try_from(value.len()).map_err(|_| ProtocolError::InvalidLength)?;
+        write_u16(buffer, len)?;
+        write_slice(buffer, value)
+    }
+}
+
+impl<'a> Codec<'a, &'a [u8]> for Rest {
+    const MIN_LEN: usize = 0;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<&'a [u8], ProtocolError> {
+        reader.read_slice(reader.remaining())
+    }
+
+    fn encode<const N: usize>(value: &&'a [u8], buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_slice(buffer, value)
+    }
+}
+
+impl<'a, T> Codec<'a, Option<T>> for Opt
+where
+    Be: Codec<'a, T>,
+{
+    const MIN_LEN: usize = 0;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<Option<T>, ProtocolError> {
+        if reader.remaining() == 0 {
+            return Ok(None);
+        }
+        <Be as Codec<'a, T>>::decode(reader).map(Some)
+    }
+
+    fn encode<const N: usize>(value: &Option<T>, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        match value {
+            Some(value) => <Be as Codec<'a, T>>::encode(value, buffer),
+            None => Ok(()),
+        }
+    }
+}
+
+/// UUID as encoded in GATTS commands: [UUID Type (1)] [UUID (2, 16 or 3 bytes)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Uuid {
+    /// Type 0: Bluetooth SIG 16-bit UUID (little-endian on the wire)
+    Uuid16(u16),
+    /// Type 1: full 128-bit UUID
+    Uuid128([u8; 16]),
+    /// Type 2: offset into a base registered with REGISTER_UUID_GROUP
+    VendorSpecific { base_id: u8, offset: u16 },
+}
+
+impl<'a> Codec<'a, Uuid> for Be {
+    const MIN_LEN: usize = 3;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<Uuid, ProtocolError> {
+        match reader.read_u8()? {
+            0 => Ok(Uuid::Uuid16(<Le as Codec<'a, u16>>::decode(reader)?)),
+            1 => Ok(Uuid::Uuid128(<Be as Codec<'a, [u8; 16]>>::decode(reader)?)),
+            2 => Ok(Uuid::VendorSpecific {
+                base_id: reader.read_u8()?,
+                offset: <Le as Codec<'a, u16>>::decode(reader)?,
+            }),
+            _ => Err(ProtocolError::InvalidData),
+        }
+    }
+
+    fn encode<const N: usize>(value: &Uuid, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        match *value {
+            Uuid::Uuid16(uuid) => {
+                write_u8(buffer, 0)?;
+                <Le as Codec<'a, u16>>::encode(&uuid, buffer)
+            }
+            Uuid::Uuid128(uuid) => {
+                write_u8(buffer, 1)?;
+                write_slice(buffer, &uuid)
+            }
+            Uuid::VendorSpecific { base_id, offset } => {
+                write_u8(buffer, 2)?;
+                write_u8(buffer, base_id)?;
+                <Le as Codec<'a, u16>>::encode(&offset, buffer)
+            }
+        }
+    }
+}
+
+/// Advertising and scan response data of GAP_ADV_SET_CONFIGURE:
+/// [Adv Data Length (2)] [Scan Data Length (2)] [Adv Data] [Scan Data]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AdvData<'a> {
+    pub adv_data: &'a [u8],
+    pub scan_data: &'a [u8],
+}
+
+impl<'a> Codec<'a, AdvData<'a>> for Be {
+    const MIN_LEN: usize = 4;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<AdvData<'a>, ProtocolError> {
+        let adv_len = reader.read_u16()? as usize;
+        let scan_len = reader.read_u16()? as usize;
+        Ok(AdvData {
+            adv_data: reader.read_slice(adv_len)?,
+            scan_data: reader.read_slice(scan_len)?,
+        })
+    }
+
+    fn encode<const N: usize>(value: &AdvData<'a>, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        let adv_len = u16::try_from(value.adv_data.len()).map_err(|_| ProtocolError::InvalidLength)?;
+        let scan_len = u16::try_from(value.scan_data.len()).map_err(|_| ProtocolError::InvalidLength)?;
+        write_u16(buffer, adv_len)?;
+        write_u16(buffer, scan_len)?;
+        write_slice(buffer, value.adv_data)?;
+        write_slice(buffer, value.scan_data)
+    }
+}
+
+/// Advertising parameters of GAP_ADV_SET_CONFIGURE:
+/// [Interval (2)] [Timeout (2)] [Filter Policy (1)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AdvParams {
+    /// Advertising interval in 0.625 ms units
+    pub interval: u16,
+    /// Advertising timeout in 10 ms units, 0 to advertise until stopped
+    pub timeout: u16,
+    /// `BLE_GAP_ADV_FP_*`: 0 any, 1 whitelist scan requests, 2 whitelist
+    /// connection requests, 3 whitelist both
+    pub filter_policy: u8,
+}
+
+impl AdvParams {
+    /// Shortest connectable advertising interval (20 ms)
+    pub const INTERVAL_MIN: u16 = 0x0020;
+    /// Longest legacy advertising interval (10.24 s)
+    pub const INTERVAL_MAX: u16 = 0x4000;
+    /// Highest filter policy value
+    pub const FILTER_POLICY_MAX: u8 = 3;
+
+    /// Check the interval and filter policy are in range
+    pub const fn is_valid(&self) -> bool {
+        self.interval >= Self::INTERVAL_MIN
+            && self.interval <= Self::INTERVAL_MAX
+            && self.filter_policy <= Self::FILTER_POLICY_MAX
+    }
+}
+
+impl<'a> Codec<'a, AdvParams> for Be {
+    const MIN_LEN: usize = 5;
+
+    fn decode(reader: &mut PayloadReader<'a>) -> Result<AdvParams, ProtocolError> {
+        Ok(AdvParams {
+            interval: reader.read_u16()?,
+            timeout: reader.read_u16()?,
+            filter_policy: reader.read_u8()?,
+        })
+    }
+
+    fn encode<const N: usize>(value: &AdvParams, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u16(buffer, value.interval)?;
+        write_u16(buffer, value.timeout)?;
+        write_u8(buffer, value.filter_policy)
+    }
+}
+
+/// Request struct generated by `protocol_table!`
+pub trait Request<'a>: Sized {
+    /// Request code the struct is sent with
+    const CODE: crate::RequestCode;
+    /// Minimum payload length
+    const MIN_LEN: usize;
+
+    /// Decode a request payload, checking the minimum length first
+    fn decode(payload: &'a [u8]) -> Result<Self, ProtocolError>;
+
+    /// Append the encoded payload to a buffer
+    fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError>;
+}
+
+/// Generate [`RequestCode`](crate::RequestCode) and the typed request structs
+///
+/// Each entry is `Variant = code` optionally followed by `=> Struct { fields }`,
+/// where a field is `name: Type` with an optional `as Codec` (default [`Be`]).
+/// Entries without a struct (commands whose payload is not modelled yet) have a
+/// minimum length of 0. Borrowing structs must use the lifetime `'a`.
+macro_rules! protocol_table {
+    (
+        $(
+            $(#[$meta:meta])*
+            $variant:ident = $code:literal
+            $( => $request:ident $(<$lt:lifetime>)? {
+                $( $(#[$field_meta:meta])* $field:ident : $ty:ty $(as $codec:ident)? ),* $(,)?
+            } )?
+        ),* $(,)?
+    ) => {
+        /// Request codes sent by host to device
+        #[repr(u16)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        pub enum RequestCode {
+            $( $(#[$meta])* $variant = $code, )*
+        }
+
+        impl RequestCode {
+            /// Every request code, in table order
+            pub const ALL: &'static [RequestCode] = &[ $( RequestCode::$variant, )* ];
+
+            /// Convert from raw u16 value
+            pub fn from_u16(value: u16) -> Option<Self> {
+                match value {
+                    $( $code => Some(Self::$variant), )*
+                    _ => None,
+                }
+            }
+
+            /// Minimum payload length of the request
+            pub const fn min_payload_len(self) -> usize {
+                match self {
+                    $( Self::$variant => $crate::codec::protocol_table!(@min_len $( $request )?), )*
+                }
+            }
+        }
+
+        $( $(
+            #[doc = concat!("Payload of [`RequestCode::", stringify!($variant), "`]")]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+            pub struct $request $(<$lt>)? {
+                $( $(#[$field_meta])* pub $field: $ty, )*
+            }
+
+            impl<'a> $crate::codec::Request<'a> for $request $(<$lt>)? {
+                const CODE: RequestCode = RequestCode::$variant;
+                #[allow(clippy::identity_op)]
+                const MIN_LEN: usize = 0 $( + <$crate::codec::protocol_table!(@codec $($codec)?) as $crate::codec::Codec<'a, $ty>>::MIN_LEN )*;
+
+                fn decode(payload: &'a [u8]) -> Result<Self, $crate::ProtocolError> {
+                    if payload.len() < <Self as $crate::codec::Request<'a>>::MIN_LEN {
+                        return Err($crate::ProtocolError::InvalidLength);
+                    }
+                    #[allow(unused_mut, unused_variables)]
+                    let mut reader = $crate::serialization::PayloadReader::new(payload);
+                    $(
+                        let $field = <$crate::codec::protocol_table!(@codec $($codec)?) as $crate::codec::Codec<'a, $ty>>::decode(&mut reader)?;
+                    )*
+                    Ok(Self { $( $field, )* })
+                }
+
+                #[allow(unused_variables)]
+                fn encode<const N: usize>(&self, buffer: &mut ::heapless::Vec<u8, N>) -> Result<(), $crate::ProtocolError> {
+                    $(
+                        <$crate::codec::protocol_table!(@codec $($codec)?) as $crate::codec::Codec<'a, $ty>>::encode(&self.$field, buffer)?;
+                    )*
+                    Ok(())
+                }
+            }
+        )? )*
+    };
+
+    (@codec) => { $crate::codec::Be };
+    (@codec $codec:ident) => { $crate::codec::$codec };
+
+    (@min_len) => { 0 };
+    (@min_len $request:ident) => { <$request as $crate::codec::Request<'_>>::MIN_LEN };
+}
+
+pub(crate) use protocol_table;
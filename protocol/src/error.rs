@@ -0,0 +1,106 @@
+//! Error Responses
+//!
+//! Payload of `ResponseCode::Error` frames (big-endian):
+//! [Error Category (2)] [Request Code (2)] [Detail (1)] [NRF Error (4)]
+//!
+//! - Error Category: [`ErrorCategory`]; the first two bytes match the original
+//!   2-byte error code, so older hosts keep decoding it.
+//! - Request Code: raw code of the failing request (also set for unknown codes).
+//! - Detail: index of the inner error variant within its category (e.g. which
+//!   state or buffer error), 0 if the category has none.
+//! - NRF Error: raw `NRF_ERROR_*` value returned by the SoftDevice, 0 if none.
+
+use heapless::Vec;
+
+use crate::serialization::{write_u16, write_u32, write_u8, PayloadReader};
+use crate::ProtocolError;
+
+/// Size of an encoded error response payload
+pub const ERROR_RESPONSE_SIZE: usize = 9;
+
+/// Error category (the original 2-byte error code)
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ErrorCategory {
+    UnknownCommand = 0x01,
+    InvalidPayload = 0x02,
+    Buffer = 0x03,
+    Protocol = 0x04,
+    State = 0x05,
+    SoftDevice = 0x06,
+    NotImplemented = 0x07,
+    Service = 0x08,
+    Notification = 0x09,
+}
+
+impl ErrorCategory {
+    /// Convert from raw u16 value
+    pub fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            0x01 => Some(Self::UnknownCommand),
+            0x02 => Some(Self::InvalidPayload),
+            0x03 => Some(Self::Buffer),
+            0x04 => Some(Self::Protocol),
+            0x05 => Some(Self::State),
+            0x06 => Some(Self::SoftDevice),
+            0x07 => Some(Self::NotImplemented),
+            0x08 => Some(Self::Service),
+            0x09 => Some(Self::Notification),
+            _ => None,
+        }
+    }
+}
+
+/// Decoded `ResponseCode::Error` payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ErrorResponse {
+    /// Raw error category (see [`ErrorCategory`])
+    pub category: u16,
+    /// Raw code of the request that failed
+    pub request_code: u16,
+    /// Inner error variant index
+    pub detail: u8,
+    /// Raw SoftDevice return code
+    pub nrf_error: u32,
+}
+
+impl ErrorResponse {
+    /// Known error category, if any
+    pub fn category(&self) -> Option<ErrorCategory> {
+        ErrorCategory::from_u16(self.category)
+    }
+
+    /// Append the encoded payload to a buffer
+    pub fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u16(buffer, self.category)?;
+        write_u16(buffer, self.request_code)?;
+        write_u8(buffer, self.detail)?;
+        write_u32(buffer, self.nrf_error)
+    }
+
+    /// Decode an error payload
+    ///
+    /// Payloads from older firmware carry only the 2-byte category; the missing
+    /// fields decode as 0.
+    pub fn decode(payload: &[u8]) -> Result<Self, ProtocolError> {
+        let mut reader = PayloadReader::new(payload);
+        let category = reader.read_u16()?;
+        if reader.remaining() == 0 {
+            return Ok(Self {
+                category,
+                request_code: 0,
+                detail: 0,
+                nrf_error: 0,
+            });
+        }
+
+        Ok(Self {
+            category,
+            request_code: reader.read_u16()?,
+            detail: reader.read_u8()?,
+            nrf_error: reader.read_u32()?,
+        })
+    }
+}
@@ -0,0 +1,304 @@
+//! Event Frames and Subscription
+//!
+//! Every `BleEvent` frame payload starts with an [`EventHeader`]: a sequence
+//! number incremented for each event sent and the device uptime when the event
+//! was raised. The host can detect lost events from gaps in the sequence and
+//! align timestamps with its own clock using GET_CLOCK.
+//!
+//! Event payload layout:
+//! [Event Seq (4)] [Timestamp µs (8)] [Event ID (1)] [Reserved (1)] [Fields (little-endian)]
+//!
+//! `SocEvent` frames share the header and sequence numbers:
+//! [Event Seq (4)] [Timestamp µs (8)] [SoC Event ID (1)] (see [`SocEvent`])
+//!
+//! So do `SystemEvent` frames, raised by the modem itself:
+//! [Event Seq (4)] [Timestamp µs (8)] [System Event ID (1)] [Fields] (see [`SystemEvent`])
+//!
+//! The host chooses which unsolicited events the modem forwards. Categories are
+//! enabled with an [`EventMask`] (SET_EVENT_MASK), and events tied to a single
+//! characteristic can additionally be muted per attribute handle
+//! (SET_CHAR_EVENT_FILTER). Every category is enabled after reset.
+//!
+//! GET_EVENT_MASK response layout (big-endian):
+//! [Event Mask (4)] [Muted Handle Count (1)] [Muted Handles (2 each)]
+
+use heapless::Vec;
+
+use crate::power::ResetReason;
+use crate::serialization::{write_u16, write_u32, write_u64, write_u8, PayloadReader};
+use crate::ProtocolError;
+
+/// Size of the [`EventHeader`] on the wire
+pub const EVENT_HEADER_SIZE: usize = 12;
+
+/// Sequence number and timestamp leading every event payload (big-endian)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EventHeader {
+    /// Incremented for every event sent, wrapping at `u32::MAX`
+    pub seq: u32,
+    /// Device uptime in microseconds when the event was raised
+    pub timestamp_us: u64,
+}
+
+impl EventHeader {
+    /// Append the encoded header to a buffer
+    pub fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u32(buffer, self.seq)?;
+        write_u64(buffer, self.timestamp_us)
+    }
+
+    /// Split an event payload into its header and the event body
+    pub fn decode(payload: &[u8]) -> Result<(Self, &[u8]), ProtocolError> {
+        let mut reader = PayloadReader::new(payload);
+        let header = Self {
+            seq: reader.read_u32()?,
+            timestamp_us: reader.read_u64()?,
+        };
+        Ok((header, &payload[reader.offset()..]))
+    }
+}
+
+/// Maximum number of muted characteristic handles
+pub const MAX_CHAR_EVENT_FILTERS: usize = 8;
+
+/// Set of event categories forwarded to the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EventMask(u32);
+
+impl EventMask {
+    /// GAP connected / disconnected
+    pub const GAP_CONNECTION: Self = Self(1 << 0);
+    /// GATTS characteristic writes
+    pub const GATTS_WRITE: Self = Self(1 << 1);
+    /// GATTS characteristic reads
+    pub const GATTS_READ: Self = Self(1 << 2);
+    /// CCCD (notification / indication enable) writes
+    pub const CCCD: Self = Self(1 << 3);
+    /// ATT MTU exchange
+    pub const MTU: Self = Self(1 << 4);
+    /// RSSI reports
+    pub const RSSI: Self = Self(1 << 5);
+    /// SoftDevice SoC events (`ResponseCode::SocEvent`)
+    pub const SOC: Self = Self(1 << 6);
+    /// Modem system events (`ResponseCode::SystemEvent`)
+    pub const SYSTEM: Self = Self(1 << 7);
+
+    /// No events
+    pub const NONE: Self = Self(0);
+    /// Every category known to this protocol version
+    pub const ALL: Self = Self((1 << 8) - 1);
+
+    /// Mask from raw bits, dropping categories this protocol version does not know
+    pub const fn from_bits_truncate(bits: u32) -> Self {
+        Self(bits & Self::ALL.0)
+    }
+
+    /// Raw bits as sent on the wire
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Check if every category in `other` is enabled
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Categories enabled in either mask
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Categories enabled in `self` but not in `other`
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl Default for EventMask {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Event subscription state reported by GET_EVENT_MASK
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EventSubscription {
+    pub mask: EventMask,
+    /// Characteristic value handles whose events are not forwarded
+    pub muted_handles: Vec<u16, MAX_CHAR_EVENT_FILTERS>,
+}
+
+impl EventSubscription {
+    /// Append the encoded subscription to a buffer
+    pub fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u32(buffer, self.mask.bits())?;
+        write_u8(buffer, self.muted_handles.len() as u8)?;
+        for &handle in &self.muted_handles {
+            write_u16(buffer, handle)?;
+        }
+        Ok(())
+    }
+
+    /// Decode a subscription from a GET_EVENT_MASK response payload
+    pub fn decode(payload: &[u8]) -> Result<Self, ProtocolError> {
+        let mut reader = PayloadReader::new(payload);
+        let mask = EventMask::from_bits_truncate(reader.read_u32()?);
+        let count = reader.read_u8()? as usize;
+
+        let mut muted_handles = Vec::new();
+        for _ in 0..count {
+            muted_handles
+                .push(reader.read_u16()?)
+                .map_err(|_| ProtocolError::InvalidData)?;
+        }
+
+        Ok(Self { mask, muted_handles })
+    }
+}
+
+/// SoftDevice SoC event (`NRF_SOC_EVTS`), carried by `SocEvent` frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SocEvent {
+    /// HFCLK crystal oscillator started
+    HfclkStarted,
+    /// Supply voltage dropped below the power-failure threshold
+    PowerFailureWarning,
+    /// Flash write or erase finished
+    FlashOperationSuccess,
+    /// Flash write or erase failed (timed out by radio activity)
+    FlashOperationError,
+    /// Radio timeslot request blocked
+    RadioBlocked,
+    /// Radio timeslot request canceled
+    RadioCanceled,
+    /// Radio timeslot signal callback returned an invalid value
+    RadioSignalCallbackInvalidReturn,
+    /// Radio timeslot session idle
+    RadioSessionIdle,
+    /// Radio timeslot session closed
+    RadioSessionClosed,
+    /// USB 3.3V supply ready
+    UsbPowerReady,
+    /// VBUS detected
+    UsbDetected,
+    /// VBUS removed
+    UsbRemoved,
+    /// Event ID not known to this protocol version
+    Unknown(u8),
+}
+
+impl SocEvent {
+    /// Event from its `NRF_SOC_EVTS` value
+    pub const fn from_id(id: u8) -> Self {
+        match id {
+            0 => Self::HfclkStarted,
+            1 => Self::PowerFailureWarning,
+            2 => Self::FlashOperationSuccess,
+            3 => Self::FlashOperationError,
+            4 => Self::RadioBlocked,
+            5 => Self::RadioCanceled,
+            6 => Self::RadioSignalCallbackInvalidReturn,
+            7 => Self::RadioSessionIdle,
+            8 => Self::RadioSessionClosed,
+            9 => Self::UsbPowerReady,
+            10 => Self::UsbDetected,
+            11 => Self::UsbRemoved,
+            other => Self::Unknown(other),
+        }
+    }
+
+    /// `NRF_SOC_EVTS` value sent on the wire
+    pub const fn id(self) -> u8 {
+        match self {
+            Self::HfclkStarted => 0,
+            Self::PowerFailureWarning => 1,
+            Self::FlashOperationSuccess => 2,
+            Self::FlashOperationError => 3,
+            Self::RadioBlocked => 4,
+            Self::RadioCanceled => 5,
+            Self::RadioSignalCallbackInvalidReturn => 6,
+            Self::RadioSessionIdle => 7,
+            Self::RadioSessionClosed => 8,
+            Self::UsbPowerReady => 9,
+            Self::UsbDetected => 10,
+            Self::UsbRemoved => 11,
+            Self::Unknown(id) => id,
+        }
+    }
+
+    /// Append the encoded event body to a buffer
+    pub fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u8(buffer, self.id())
+    }
+
+    /// Decode an event body (after the [`EventHeader`])
+    pub fn decode(body: &[u8]) -> Result<Self, ProtocolError> {
+        PayloadReader::new(body).read_u8().map(Self::from_id)
+    }
+}
+
+/// Modem system event, carried by `SystemEvent` frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SystemEvent {
+    /// TX buffer pool nearly exhausted (ID 0x01): [Allocated (1)] [Pool Size (1)]
+    ///
+    /// Raised once when the pool runs low and re-armed after it drains.
+    PoolLow { allocated: u8, size: u8 },
+    /// Modem started (ID 0x02): [Reset Reason (1)] [RESETREAS (4)]
+    ///
+    /// Sent once after every reset. `resetreas` is the raw `POWER.RESETREAS`
+    /// register the reason was derived from (zero after a power-on reset).
+    Boot { reason: ResetReason, resetreas: u32 },
+    /// Event ID not known to this protocol version (fields skipped)
+    Unknown(u8),
+}
+
+impl SystemEvent {
+    const POOL_LOW: u8 = 0x01;
+    const BOOT: u8 = 0x02;
+
+    /// Event ID sent on the wire
+    pub const fn id(&self) -> u8 {
+        match self {
+            Self::PoolLow { .. } => Self::POOL_LOW,
+            Self::Boot { .. } => Self::BOOT,
+            Self::Unknown(id) => *id,
+        }
+    }
+
+    /// Append the encoded event body to a buffer
+    pub fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u8(buffer, self.id())?;
+        match *self {
+            Self::PoolLow { allocated, size } => {
+                write_u8(buffer, allocated)?;
+                write_u8(buffer, size)
+            }
+            Self::Boot { reason, resetreas } => {
+                write_u8(buffer, reason.id())?;
+                write_u32(buffer, resetreas)
+            }
+            Self::Unknown(_) => Ok(()),
+        }
+    }
+
+    /// Decode an event body (after the [`EventHeader`])
+    pub fn decode(body: &[u8]) -> Result<Self, ProtocolError> {
+        let mut reader = PayloadReader::new(body);
+        match reader.read_u8()? {
+            Self::POOL_LOW => Ok(Self::PoolLow {
+                allocated: reader.read_u8()?,
+                size: reader.read_u8()?,
+            }),
+            Self::BOOT => Ok(Self::Boot {
+                reason: ResetReason::from_id(reader.read_u8()?),
+                resetreas: reader.read_u32()?,
+            }),
+            id => Ok(Self::Unknown(id)),
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Packet Fragmentation
+//!
+//! Packets whose frame would exceed [`MAX_PAYLOAD_SIZE`] are split across several
+//! frames. A fragment sets [`FRAGMENT_FLAG`] in the length header and carries a
+//! fragment header after the (optional) sequence number:
+//! - First: [Marker 0x01] [Index 0] [Total Length (2 bytes, big-endian)]
+//! - Continuation: [Marker 0x02] [Index]
+//! - Last: [Marker 0x03] [Index]
+//!
+//! Concatenating the fragment bodies yields the body of the equivalent
+//! unfragmented frame. Every fragment repeats the packet's sequence number.
+
+use heapless::Vec;
+
+use crate::serialization::{write_u16, write_u8};
+use crate::{encode_frame, Frame, ProtocolError, MAX_MESSAGE_SIZE, MAX_PAYLOAD_SIZE, SEQUENCE_SIZE};
+
+/// Length header flag marking a fragment
+pub const FRAGMENT_FLAG: u16 = 0x4000;
+
+/// Largest frame body a reassembled packet can have (payload + 2-byte code)
+pub const MAX_REASSEMBLED_SIZE: usize = MAX_MESSAGE_SIZE + 2;
+
+/// Fragment body size that fits in a frame with every optional header present
+///
+/// Overhead: length (2) + sequence (2) + first fragment header (4) + CRC (2).
+pub const FRAGMENT_CHUNK_SIZE: usize = MAX_PAYLOAD_SIZE - 2 - SEQUENCE_SIZE - 4 - 2;
+
+/// Maximum number of frames a single packet can span
+pub const MAX_FRAGMENTS: usize = MAX_REASSEMBLED_SIZE.div_ceil(FRAGMENT_CHUNK_SIZE);
+
+/// Default time allowed between the first and last fragment of a packet
+pub const REASSEMBLY_TIMEOUT_MS: u64 = 500;
+
+const MARKER_FIRST: u8 = 0x01;
+const MARKER_CONTINUATION: u8 = 0x02;
+const MARKER_LAST: u8 = 0x03;
+
+/// Fragment header carried by fragmented frames
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FragmentHeader {
+    /// First fragment, announcing the reassembled body length
+    First { total_len: u16 },
+    /// Middle fragment
+    Continuation { index: u8 },
+    /// Final fragment
+    Last { index: u8 },
+}
+
+impl FragmentHeader {
+    /// Decode a fragment header, returning it and its encoded size
+    pub fn decode(data: &[u8]) -> Result<(Self, usize), ProtocolError> {
+        if data.len() < 2 {
+            return Err(ProtocolError::InvalidLength);
+        }
+
+        match (data[0], data[1]) {
+            (MARKER_FIRST, 0) => {
+                if data.len() < 4 {
+                    return Err(ProtocolError::InvalidLength);
+                }
+                let total_len = u16::from_be_bytes([data[2], data[3]]);
+                Ok((Self::First { total_len }, 4))
+            }
+            (MARKER_CONTINUATION, index) => Ok((Self::Continuation { index }, 2)),
+            (MARKER_LAST, index) => Ok((Self::Last { index }, 2)),
+            _ => Err(ProtocolError::InvalidData),
+        }
+    }
+
+    /// Append the encoded header to a frame buffer
+    pub fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        match *self {
+            Self::First { total_len } => {
+                write_u8(buffer, MARKER_FIRST)?;
+                write_u8(buffer, 0)?;
+                write_u16(buffer, total_len)
+            }
+            Self::Continuation { index } => {
+                write_u8(buffer, MARKER_CONTINUATION)?;
+                write_u8(buffer, index)
+            }
+            Self::Last { index } => {
+                write_u8(buffer, MARKER_LAST)?;
+                write_u8(buffer, index)
+            }
+        }
+    }
+
+    /// Size of the encoded header
+    pub fn encoded_len(&self) -> usize {
+        match self {
+            Self::First { .. } => 4,
+            Self::Continuation { .. } | Self::Last { .. } => 2,
+        }
+    }
+
+    /// Position of this fragment within its packet
+    pub fn index(&self) -> u8 {
+        match *self {
+            Self::First { .. } => 0,
+            Self::Continuation { index } | Self::Last { index } => index,
+        }
+    }
+}
+
+/// Iterator over the frames of a serialized packet
+///
+/// Yields a single plain frame when the packet fits, fragments otherwise.
+pub struct Fragments {
+    seq: Option<u16>,
+    body: Vec<u8, MAX_REASSEMBLED_SIZE>,
+    offset: usize,
+    index: u8,
+    done: bool,
+}
+
+impl Fragments {
+    /// Prepare the frames for a packet body given as consecutive parts
+    pub fn new(seq: Option<u16>, parts: &[&[u8]]) -> Result<Self, ProtocolError> {
+        let mut body = Vec::new();
+        for part in parts {
+            body.extend_from_slice(part).map_err(|_| ProtocolError::BufferFull)?;
+        }
+
+        Ok(Self {
+            seq,
+            body,
+            offset: 0,
+            index: 0,
+            done: false,
+        })
+    }
+
+    /// Whether the packet is split across more than one frame
+    pub fn is_fragmented(&self) -> bool {
+        let seq_len = if self.seq.is_some() { SEQUENCE_SIZE } else { 0 };
+        2 + seq_len + self.body.len() + 2 > MAX_PAYLOAD_SIZE
+    }
+}
+
+impl Iterator for Fragments {
+    type Item = Vec<u8, MAX_PAYLOAD_SIZE>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if !self.is_fragmented() {
+            self.done = true;
+            return encode_frame(self.seq, None, &[&self.body]).ok();
+        }
+
+        let end = (self.offset + FRAGMENT_CHUNK_SIZE).min(self.body.len());
+        let header = if self.index == 0 {
+            FragmentHeader::First {
+                total_len: self.body.len() as u16,
+            }
+        } else if end == self.body.len() {
+            FragmentHeader::Last { index: self.index }
+        } else {
+            FragmentHeader::Continuation { index: self.index }
+        };
+
+        // Chunks are sized to fit with every header present, so encoding cannot fail
+        let frame = encode_frame(self.seq, Some(header), &[&self.body[self.offset..end]]).ok();
+
+        self.offset = end;
+        self.index = self.index.wrapping_add(1);
+        self.done = matches!(header, FragmentHeader::Last { .. });
+        frame
+    }
+}
+
+/// Fragment reassembly state for one link direction
+///
+/// Only one packet is reassembled at a time; a new first fragment discards any
+/// partially received packet. Timestamps are caller-supplied milliseconds so the
+/// reassembler works with any clock.
+pub struct Reassembler<const N: usize> {
+    buffer: Vec<u8, N>,
+    seq: Option<u16>,
+    total_len: usize,
+    next_index: u8,
+    started_ms: u64,
+    in_progress: bool,
+    timeout_ms: u64,
+}
+
+impl<const N: usize> Reassembler<N> {
+    /// Create a reassembler that drops packets not completed within `timeout_ms`
+    pub const fn new(timeout_ms: u64) -> Self {
+        Self {
+            buffer: Vec::new(),
+            seq: None,
+            total_len: 0,
+            next_index: 0,
+            started_ms: 0,
+            in_progress: false,
+            timeout_ms,
+        }
+    }
+
+    /// Feed a fragment, returning the reassembled frame once the last one arrives
+    ///
+    /// On error the partially received packet is discarded.
+    pub fn push(&mut self, frame: &Frame<'_>, now_ms: u64) -> Result<Option<Frame<'_>>, ProtocolError> {
+        let Some(header) = frame.fragment else {
+            return Err(ProtocolError::InvalidData);
+        };
+
+        if let FragmentHeader::First { total_len } = header {
+            self.reset();
+            if total_len as usize > N {
+                return Err(ProtocolError::BufferFull);
+            }
+            self.seq = frame.seq;
+            self.total_len = total_len as usize;
+            self.started_ms = now_ms;
+            self.in_progress = true;
+        } else {
+            if !self.in_progress || header.index() != self.next_index || frame.seq != self.seq {
+                self.reset();
+                return Err(ProtocolError::FragmentOutOfOrder);
+            }
+            if self.expire(now_ms) {
+                return Err(ProtocolError::ReassemblyTimeout);
+            }
+        }
+
+        if self.buffer.len() + frame.body.len() > self.total_len {
+            self.reset();
+            return Err(ProtocolError::InvalidLength);
+        }
+        // Cannot fail: total_len was checked against N above
+        let _ = self.buffer.extend_from_slice(frame.body);
+        self.next_index = header.index().wrapping_add(1);
+
+        if let FragmentHeader::Last { .. } = header {
+            if self.buffer.len() != self.total_len {
+                self.reset();
+                return Err(ProtocolError::InvalidLength);
+            }
+            self.in_progress = false;
+            return Ok(Some(Frame {
+                seq: self.seq,
+                fragment: None,
+                body: &self.buffer,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    /// Discard a partial packet whose timeout has elapsed, returning whether one was dropped
+    pub fn expire(&mut self, now_ms: u64) -> bool {
+        if self.in_progress && now_ms.saturating_sub(self.started_ms) > self.timeout_ms {
+            self.reset();
+            return true;
+        }
+        false
+    }
+
+    /// Check if a packet is partially received
+    pub fn is_in_progress(&self) -> bool {
+        self.in_progress
+    }
+
+    /// Discard any partially received packet
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.seq = None;
+        self.total_len = 0;
+        self.next_index = 0;
+        self.in_progress = false;
+    }
+}
@@ -0,0 +1,92 @@
+//! Device Identity
+//!
+//! GET_INFO returns the firmware version followed by the identity of the
+//! individual modem, for provisioning: the factory-programmed device ID and
+//! BLE address from FICR, the SoftDevice version read at runtime, the firmware
+//! crate version and the git revision it was built from. Hosts that only read
+//! the leading firmware version keep working.
+//!
+//! Response layout (big-endian):
+//! [Firmware Version (4)] [Device ID (8)] [Address Type (1)] [Factory Address (6, LSB first)]
+//! [SoftDevice FWID (2)] [LL Version (1)] [Company ID (2)]
+//! [Crate Version Length (1)] [Crate Version (0-N)] [Build ID Length (1)] [Build ID (0-N)]
+
+use heapless::{String, Vec};
+
+use crate::capabilities::{SoftDeviceVersion, MAX_BUILD_ID_LEN};
+use crate::serialization::{write_slice, write_u16, write_u32, write_u64, write_u8, PayloadReader};
+use crate::ProtocolError;
+
+/// Maximum crate version length (`major.minor.patch` plus a short pre-release tag)
+pub const MAX_CRATE_VERSION_LEN: usize = 16;
+
+/// Identity of a modem, returned by GET_INFO
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceIdentity {
+    /// Firmware version in BCD format
+    pub firmware_version: u32,
+    /// Unique device ID: `FICR.DEVICEID[1]` in the high word, `DEVICEID[0]` in
+    /// the low word (the order nrfjprog and Zephyr hwinfo use)
+    pub device_id: u64,
+    /// Factory address type: 0=Public, 1=RandomStatic (`FICR.DEVICEADDRTYPE`)
+    pub addr_type: u8,
+    /// Factory BLE address (`FICR.DEVICEADDR`), LSB first as used by the SoftDevice
+    pub factory_address: [u8; 6],
+    pub softdevice: SoftDeviceVersion,
+    /// Firmware crate version (`CARGO_PKG_VERSION`)
+    pub crate_version: String<MAX_CRATE_VERSION_LEN>,
+    /// Build identifier (git revision of the firmware)
+    pub build_id: String<MAX_BUILD_ID_LEN>,
+}
+
+impl DeviceIdentity {
+    /// Append the encoded record to a payload buffer
+    pub fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u32(buffer, self.firmware_version)?;
+        write_u64(buffer, self.device_id)?;
+        write_u8(buffer, self.addr_type)?;
+        write_slice(buffer, &self.factory_address)?;
+        write_u16(buffer, self.softdevice.fwid)?;
+        write_u8(buffer, self.softdevice.ll_version)?;
+        write_u16(buffer, self.softdevice.company_id)?;
+        write_u8(buffer, self.crate_version.len() as u8)?;
+        write_slice(buffer, self.crate_version.as_bytes())?;
+        write_u8(buffer, self.build_id.len() as u8)?;
+        write_slice(buffer, self.build_id.as_bytes())
+    }
+
+    /// Decode a record from a GET_INFO response payload
+    pub fn decode(payload: &[u8]) -> Result<Self, ProtocolError> {
+        let mut reader = PayloadReader::new(payload);
+
+        let firmware_version = reader.read_u32()?;
+        let device_id = reader.read_u64()?;
+        let addr_type = reader.read_u8()?;
+        let mut factory_address = [0u8; 6];
+        factory_address.copy_from_slice(reader.read_slice(6)?);
+        let softdevice = SoftDeviceVersion {
+            fwid: reader.read_u16()?,
+            ll_version: reader.read_u8()?,
+            company_id: reader.read_u16()?,
+        };
+        let crate_version = read_string(&mut reader)?;
+        let build_id = read_string(&mut reader)?;
+
+        Ok(Self {
+            firmware_version,
+            device_id,
+            addr_type,
+            factory_address,
+            softdevice,
+            crate_version,
+            build_id,
+        })
+    }
+}
+
+/// Read a length-prefixed UTF-8 string
+fn read_string<const N: usize>(reader: &mut PayloadReader<'_>) -> Result<String<N>, ProtocolError> {
+    let len = reader.read_u8()? as usize;
+    let value = core::str::from_utf8(reader.read_slice(len)?).map_err(|_| ProtocolError::InvalidData)?;
+    String::try_from(value).map_err(|_| ProtocolError::InvalidLength)
+}
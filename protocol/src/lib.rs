@@ -0,0 +1,575 @@
+#![no_std]
+
+//! BLE Modem Protocol Definitions
+//!
+//! This crate defines the communication protocol between the host and the BLE modem.
+//! It is shared by the nRF52820 firmware and the host-side client so both ends
+//! agree on request/response codes and frame layout.
+//!
+//! Protocol format:
+//! - Request: [Payload Data] [Request Code (2 bytes, big-endian)]
+//! - Response: [Response Code (2 bytes)] [Payload Data]
+//!
+//! Sequenced framing inserts a 2-byte sequence number after the length header so
+//! the host can match responses to requests, detect lost frames and pipeline
+//! commands. Responses echo the sequence number of their request.
+//!
+//! Packets larger than a single frame are split into fragments, see [`fragment`].
+//! Several requests can be sent in one packet with BATCH, see [`batch`].
+//! Rejected request frames are answered with a NAK, see [`link`].
+//!
+//! Request codes and typed request payloads are generated from the protocol
+//! table in [`requests`].
+//!
+//! Enable the `defmt` feature to derive `defmt::Format` for use in firmware logging.
+
+use core::ops::Range;
+
+use crc::{Crc, CRC_16_IBM_SDLC};
+use heapless::Vec;
+
+pub mod batch;
+pub mod capabilities;
+pub mod cobs;
+pub mod codec;
+pub mod error;
+pub mod events;
+pub mod fragment;
+pub mod identity;
+pub mod link;
+pub mod pool;
+pub mod power;
+pub mod requests;
+
+pub use requests::RequestCode;
+
+/// Protocol version reported in the HELLO handshake
+///
+/// Bumped whenever the frame layout or an existing command changes incompatibly.
+pub const PROTOCOL_VERSION: u16 = 5;
+
+/// Maximum payload size (BLE_EVT_LEN_MAX + 2 bytes)
+pub const MAX_PAYLOAD_SIZE: usize = 247 + 2;
+
+/// Maximum packet payload size, reached by fragmenting across frames
+///
+/// Large enough for a 512-byte attribute value plus its handle/offset fields.
+pub const MAX_MESSAGE_SIZE: usize = 528;
+
+/// Length header flag marking a sequenced frame
+///
+/// Legacy hosts never set this bit (frames are far shorter than 32 KiB), so the
+/// device can serve both framings and answers each request in the framing it used.
+pub const SEQUENCE_FLAG: u16 = 0x8000;
+
+/// Size of the sequence number field in sequenced frames
+pub const SEQUENCE_SIZE: usize = 2;
+
+/// Space a frame built in place reserves in front of its body for the largest header
+///
+/// Length (2) + sequence (2) + first fragment header (4), see [`seal_frame`].
+pub const FRAME_HEADROOM: usize = 2 + SEQUENCE_SIZE + 4;
+
+/// Sequence number carried by unsolicited frames (events) in sequenced framing
+///
+/// Hosts must not use it for requests.
+pub const UNSOLICITED_SEQ: u16 = 0;
+
+/// CRC16-CCITT calculator for message validation
+const CRC16: Crc<u16> = Crc::<u16>::new(&CRC_16_IBM_SDLC);
+
+/// Calculate CRC16 for a message
+pub fn calculate_crc16(data: &[u8]) -> u16 {
+    CRC16.checksum(data)
+}
+
+/// Validate CRC16 for a received message
+pub fn validate_crc16(data: &[u8], expected_crc: u16) -> bool {
+    let calculated_crc = calculate_crc16(data);
+    calculated_crc == expected_crc
+}
+
+/// Response codes sent by device to host
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ResponseCode {
+    /// Command acknowledgment with results
+    Ack = 0xAC50,
+    /// Error response
+    Error = 0xAC51,
+    /// BLE event notification
+    BleEvent = 0x8001,
+    /// System-on-Chip event notification
+    SocEvent = 0x8002,
+    /// Modem system event notification (see [`events::SystemEvent`])
+    SystemEvent = 0x8003,
+    /// Request frame rejected by the link (see [`link`])
+    Nak = 0xAC52,
+}
+
+/// Protocol packet structure
+#[derive(Debug, Clone)]
+pub struct Packet {
+    pub code: u16,
+    /// Sequence number for request/response correlation (`None` for legacy framing)
+    pub seq: Option<u16>,
+    pub payload: Vec<u8, MAX_MESSAGE_SIZE>,
+}
+
+/// Protocol error types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ProtocolError {
+    InvalidLength,
+    InvalidCode,
+    SerializationError,
+    BufferFull,
+    InvalidCrc,
+    InvalidData,
+    /// A fragment was received where a complete frame was expected
+    Fragmented,
+    /// A fragment arrived out of order or without a preceding first fragment
+    FragmentOutOfOrder,
+    /// The remaining fragments of a packet did not arrive in time
+    ReassemblyTimeout,
+}
+
+impl ResponseCode {
+    /// Convert to raw u16 value
+    pub fn to_u16(self) -> u16 {
+        self as u16
+    }
+
+    /// Convert from raw u16 value
+    pub fn from_u16(value: u16) -> Option<Self> {
+        match value {
+            0xAC50 => Some(Self::Ack),
+            0xAC51 => Some(Self::Error),
+            0x8001 => Some(Self::BleEvent),
+            0x8002 => Some(Self::SocEvent),
+            0x8003 => Some(Self::SystemEvent),
+            0xAC52 => Some(Self::Nak),
+            _ => None,
+        }
+    }
+}
+
+impl Packet {
+    /// Create a new request packet from received data
+    /// Format: [Length:2][Payload:N][RequestCode:2][CRC16:2]
+    /// Sequenced: [Length:2 | SEQUENCE_FLAG][Sequence:2][Payload:N][RequestCode:2][CRC16:2]
+    ///
+    /// Fragments are rejected with [`ProtocolError::Fragmented`]; feed them to a
+    /// [`fragment::Reassembler`] instead.
+    pub fn new_request(data: &[u8]) -> Result<Self, ProtocolError> {
+        Self::from_request_frame(&Frame::parse(data)?)
+    }
+
+    /// Create a request packet from a complete (unfragmented or reassembled) frame
+    pub fn from_request_frame(frame: &Frame<'_>) -> Result<Self, ProtocolError> {
+        if frame.fragment.is_some() {
+            return Err(ProtocolError::Fragmented);
+        }
+        let body = frame.body;
+        if body.len() < 2 {
+            return Err(ProtocolError::InvalidLength);
+        }
+
+        // Extract request code (last 2 bytes of the body)
+        let code_offset = body.len() - 2;
+        let code = u16::from_be_bytes([body[code_offset], body[code_offset + 1]]);
+
+        // Extract payload (everything before the code)
+        let mut packet_payload = Vec::new();
+        packet_payload
+            .extend_from_slice(&body[..code_offset])
+            .map_err(|_| ProtocolError::BufferFull)?;
+
+        Ok(Self {
+            code,
+            seq: frame.seq,
+            payload: packet_payload,
+        })
+    }
+
+    /// Create a new response packet (code comes first, then payload)
+    pub fn new_response(code: ResponseCode, payload: &[u8]) -> Result<Self, ProtocolError> {
+        let mut packet_payload = Vec::new();
+        packet_payload
+            .extend_from_slice(payload)
+            .map_err(|_| ProtocolError::BufferFull)?;
+
+        Ok(Self {
+            code: code.to_u16(),
+            seq: None,
+            payload: packet_payload,
+        })
+    }
+
+    /// Parse a response packet from received data (used by the host)
+    /// Format: [Length:2][ResponseCode:2][Payload:N][CRC16:2]
+    /// Sequenced: [Length:2 | SEQUENCE_FLAG][Sequence:2][ResponseCode:2][Payload:N][CRC16:2]
+    pub fn parse_response(data: &[u8]) -> Result<Self, ProtocolError> {
+        Self::from_response_frame(&Frame::parse(data)?)
+    }
+
+    /// Create a response packet from a complete (unfragmented or reassembled) frame
+    pub fn from_response_frame(frame: &Frame<'_>) -> Result<Self, ProtocolError> {
+        if frame.fragment.is_some() {
+            return Err(ProtocolError::Fragmented);
+        }
+        let body = frame.body;
+        if body.len() < 2 {
+            return Err(ProtocolError::InvalidLength);
+        }
+
+        // Response code comes first
+        let code = u16::from_be_bytes([body[0], body[1]]);
+
+        let mut packet_payload = Vec::new();
+        packet_payload
+            .extend_from_slice(&body[2..])
+            .map_err(|_| ProtocolError::BufferFull)?;
+
+        Ok(Self {
+            code,
+            seq: frame.seq,
+            payload: packet_payload,
+        })
+    }
+
+    /// Create a new request packet for sending (used in tests)
+    /// This creates the packet structure, call serialize() to get bytes for transmission
+    pub fn new_request_for_sending(code: RequestCode, payload: &[u8]) -> Result<Self, ProtocolError> {
+        let mut packet_payload = Vec::new();
+        packet_payload
+            .extend_from_slice(payload)
+            .map_err(|_| ProtocolError::BufferFull)?;
+
+        Ok(Self {
+            code: code as u16,
+            seq: None,
+            payload: packet_payload,
+        })
+    }
+
+    /// Set the sequence number (`None` selects legacy framing)
+    pub fn with_seq(mut self, seq: Option<u16>) -> Self {
+        self.seq = seq;
+        self
+    }
+
+    /// Serialize request packet to a single frame for transmission
+    /// Format: [Length:2][Payload:N][RequestCode:2][CRC16:2]
+    ///
+    /// Fails with [`ProtocolError::BufferFull`] if the packet needs fragmenting,
+    /// use [`Packet::request_frames`] for those.
+    pub fn serialize_request(&self) -> Result<Vec<u8, MAX_PAYLOAD_SIZE>, ProtocolError> {
+        encode_frame(self.seq, None, &[&self.payload, &self.code.to_be_bytes()])
+    }
+
+    /// Serialize packet to a single frame for transmission
+    /// Format: [Length:2][ResponseCode:2][Payload:N][CRC16:2]
+    ///
+    /// Fails with [`ProtocolError::BufferFull`] if the packet needs fragmenting,
+    /// use [`Packet::response_frames`] for those.
+    pub fn serialize(&self) -> Result<Vec<u8, MAX_PAYLOAD_SIZE>, ProtocolError> {
+        encode_frame(self.seq, None, &[&self.code.to_be_bytes(), &self.payload])
+    }
+
+    /// Serialize request packet into one frame, or fragments if it does not fit
+    pub fn request_frames(&self) -> Result<fragment::Fragments, ProtocolError> {
+        fragment::Fragments::new(self.seq, &[&self.payload, &self.code.to_be_bytes()])
+    }
+
+    /// Serialize response packet into one frame, or fragments if it does not fit
+    pub fn response_frames(&self) -> Result<fragment::Fragments, ProtocolError> {
+        fragment::Fragments::new(self.seq, &[&self.code.to_be_bytes(), &self.payload])
+    }
+
+    /// Get the request code (if this is a request packet)
+    pub fn request_code(&self) -> Option<RequestCode> {
+        RequestCode::from_u16(self.code)
+    }
+
+    /// Get the response code (if this is a response packet)
+    pub fn response_code(&self) -> Option<ResponseCode> {
+        ResponseCode::from_u16(self.code)
+    }
+}
+
+/// A validated frame with its header fields decoded
+///
+/// The body is the packet content between the header and the CRC: payload and
+/// request code for requests, response code and payload for responses, or a
+/// slice of either when the frame is a fragment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    /// Sequence number (`None` for legacy framing)
+    pub seq: Option<u16>,
+    /// Fragment header, if this frame carries part of a larger packet
+    pub fragment: Option<fragment::FragmentHeader>,
+    /// Frame body
+    pub body: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+    /// Frame length declared by the length header at the start of `data`
+    ///
+    /// Returns `None` for fewer than two bytes or a zero length (idle padding), so
+    /// links that clock fixed-size transactions can trim a frame before parsing it.
+    pub fn declared_length(data: &[u8]) -> Option<usize> {
+        let length_header = u16::from_be_bytes([*data.first()?, *data.get(1)?]);
+        let length = (length_header & !(SEQUENCE_FLAG | fragment::FRAGMENT_FLAG)) as usize;
+        (length > 0).then_some(length)
+    }
+
+    /// Validate length header and CRC of a received frame and split its fields
+    pub fn parse(data: &'a [u8]) -> Result<Self, ProtocolError> {
+        if data.len() < 6 {
+            // Minimum: length(2) + code(2) + crc(2)
+            return Err(ProtocolError::InvalidLength);
+        }
+
+        // Parse length header
+        let length_header = u16::from_be_bytes([data[0], data[1]]);
+        let length = (length_header & !(SEQUENCE_FLAG | fragment::FRAGMENT_FLAG)) as usize;
+        if length != data.len() {
+            return Err(ProtocolError::InvalidLength);
+        }
+
+        // Extract CRC from last 2 bytes
+        let crc_offset = data.len() - 2;
+        let received_crc = u16::from_be_bytes([data[crc_offset], data[crc_offset + 1]]);
+
+        // Validate CRC over entire message except CRC field
+        if !validate_crc16(&data[..crc_offset], received_crc) {
+            return Err(ProtocolError::InvalidCrc);
+        }
+
+        let mut body = &data[2..crc_offset];
+
+        let seq = if length_header & SEQUENCE_FLAG != 0 {
+            if body.len() < SEQUENCE_SIZE {
+                return Err(ProtocolError::InvalidLength);
+            }
+            let seq = u16::from_be_bytes([body[0], body[1]]);
+            body = &body[SEQUENCE_SIZE..];
+            Some(seq)
+        } else {
+            None
+        };
+
+        let fragment = if length_header & fragment::FRAGMENT_FLAG != 0 {
+            let (header, size) = fragment::FragmentHeader::decode(body)?;
+            body = &body[size..];
+            Some(header)
+        } else {
+            None
+        };
+
+        Ok(Self { seq, fragment, body })
+    }
+}
+
+/// Build a frame from its header fields and body parts, appending the CRC
+fn encode_frame(
+    seq: Option<u16>,
+    fragment: Option<fragment::FragmentHeader>,
+    body: &[&[u8]],
+) -> Result<Vec<u8, MAX_PAYLOAD_SIZE>, ProtocolError> {
+    let body_len: usize = body.iter().map(|part| part.len()).sum();
+    let mut message = Vec::new();
+    serialization::write_slice(&mut message, &frame_header(seq, fragment, body_len)?)?;
+    for part in body {
+        serialization::write_slice(&mut message, part)?;
+    }
+
+    // Add CRC over everything written so far
+    let crc = calculate_crc16(&message);
+    serialization::write_u16(&mut message, crc)?;
+    Ok(message)
+}
+
+/// Complete a frame whose body was written in place
+///
+/// The body must already sit at `buffer[FRAME_HEADROOM..FRAME_HEADROOM + body_len]`.
+/// The header is written directly in front of it and the CRC right after, so the
+/// body never moves. Returns the range of the finished frame within `buffer`.
+pub fn seal_frame(
+    buffer: &mut [u8],
+    seq: Option<u16>,
+    fragment: Option<fragment::FragmentHeader>,
+    body_len: usize,
+) -> Result<Range<usize>, ProtocolError> {
+    let header = frame_header(seq, fragment, body_len)?;
+    let end = FRAME_HEADROOM + body_len;
+    if buffer.len() < end + 2 {
+        return Err(ProtocolError::BufferFull);
+    }
+
+    let start = FRAME_HEADROOM - header.len();
+    buffer[start..FRAME_HEADROOM].copy_from_slice(&header);
+
+    // CRC over the header and body
+    let crc = calculate_crc16(&buffer[start..end]);
+    buffer[end..end + 2].copy_from_slice(&crc.to_be_bytes());
+    Ok(start..end + 2)
+}
+
+/// Encode the header of a frame carrying `body_len` body bytes
+fn frame_header(
+    seq: Option<u16>,
+    fragment: Option<fragment::FragmentHeader>,
+    body_len: usize,
+) -> Result<Vec<u8, FRAME_HEADROOM>, ProtocolError> {
+    let mut header = Vec::new();
+
+    // Calculate total length (length header + [sequence] + [fragment header] + body + crc)
+    let seq_len = if seq.is_some() { SEQUENCE_SIZE } else { 0 };
+    let fragment_len = fragment.map_or(0, |header| header.encoded_len());
+    let total_length = 2 + seq_len + fragment_len + body_len + 2;
+    if total_length > MAX_PAYLOAD_SIZE {
+        return Err(ProtocolError::BufferFull);
+    }
+
+    // Add length header, flagged when a sequence number or fragment header follows
+    let mut length_header = total_length as u16;
+    if seq.is_some() {
+        length_header |= SEQUENCE_FLAG;
+    }
+    if fragment.is_some() {
+        length_header |= fragment::FRAGMENT_FLAG;
+    }
+    serialization::write_u16(&mut header, length_header)?;
+
+    if let Some(seq) = seq {
+        serialization::write_u16(&mut header, seq)?;
+    }
+    if let Some(fragment) = fragment {
+        fragment.encode(&mut header)?;
+    }
+    Ok(header)
+}
+
+/// Helper functions for big-endian serialization
+pub mod serialization {
+    use heapless::Vec;
+
+    use super::ProtocolError;
+
+    pub fn write_u8<const N: usize>(buffer: &mut Vec<u8, N>, value: u8) -> Result<(), ProtocolError> {
+        buffer.push(value).map_err(|_| ProtocolError::BufferFull)
+    }
+
+    pub fn write_u16<const N: usize>(buffer: &mut Vec<u8, N>, value: u16) -> Result<(), ProtocolError> {
+        let bytes = value.to_be_bytes();
+        buffer.extend_from_slice(&bytes).map_err(|_| ProtocolError::BufferFull)
+    }
+
+    pub fn write_u32<const N: usize>(buffer: &mut Vec<u8, N>, value: u32) -> Result<(), ProtocolError> {
+        let bytes = value.to_be_bytes();
+        buffer.extend_from_slice(&bytes).map_err(|_| ProtocolError::BufferFull)
+    }
+
+    pub fn write_u64<const N: usize>(buffer: &mut Vec<u8, N>, value: u64) -> Result<(), ProtocolError> {
+        let bytes = value.to_be_bytes();
+        buffer.extend_from_slice(&bytes).map_err(|_| ProtocolError::BufferFull)
+    }
+
+    pub fn write_slice<const N: usize>(buffer: &mut Vec<u8, N>, data: &[u8]) -> Result<(), ProtocolError> {
+        buffer.extend_from_slice(data).map_err(|_| ProtocolError::BufferFull)
+    }
+
+    pub fn read_u8(data: &[u8], offset: usize) -> Option<u8> {
+        data.get(offset).copied()
+    }
+
+    pub fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+        if data.len() < offset + 2 {
+            return None;
+        }
+        Some(u16::from_be_bytes([data[offset], data[offset + 1]]))
+    }
+
+    pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+        if data.len() < offset + 4 {
+            return None;
+        }
+        Some(u32::from_be_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ]))
+    }
+
+    /// Helper for reading from payload sequentially
+    pub struct PayloadReader<'a> {
+        data: &'a [u8],
+        offset: usize,
+    }
+
+    impl<'a> PayloadReader<'a> {
+        pub fn new(data: &'a [u8]) -> Self {
+            Self { data, offset: 0 }
+        }
+
+        pub fn read_u8(&mut self) -> Result<u8, ProtocolError> {
+            if self.offset >= self.data.len() {
+                return Err(ProtocolError::InvalidData);
+            }
+            let value = self.data[self.offset];
+            self.offset += 1;
+            Ok(value)
+        }
+
+        pub fn read_u16(&mut self) -> Result<u16, ProtocolError> {
+            if self.offset + 2 > self.data.len() {
+                return Err(ProtocolError::InvalidData);
+            }
+            let value = u16::from_be_bytes([self.data[self.offset], self.data[self.offset + 1]]);
+            self.offset += 2;
+            Ok(value)
+        }
+
+        pub fn read_u32(&mut self) -> Result<u32, ProtocolError> {
+            if self.offset + 4 > self.data.len() {
+                return Err(ProtocolError::InvalidData);
+            }
+            let value = u32::from_be_bytes([
+                self.data[self.offset],
+                self.data[self.offset + 1],
+                self.data[self.offset + 2],
+                self.data[self.offset + 3],
+            ]);
+            self.offset += 4;
+            Ok(value)
+        }
+
+        pub fn read_u64(&mut self) -> Result<u64, ProtocolError> {
+            let bytes = self.read_slice(8)?;
+            let mut value = [0u8; 8];
+            value.copy_from_slice(bytes);
+            Ok(u64::from_be_bytes(value))
+        }
+
+        pub fn read_slice(&mut self, len: usize) -> Result<&'a [u8], ProtocolError> {
+            if self.offset + len > self.data.len() {
+                return Err(ProtocolError::InvalidData);
+            }
+            let slice = &self.data[self.offset..self.offset + len];
+            self.offset += len;
+            Ok(slice)
+        }
+
+        pub fn offset(&self) -> usize {
+            self.offset
+        }
+
+        pub fn remaining(&self) -> usize {
+            self.data.len() - self.offset
+        }
+    }
+}
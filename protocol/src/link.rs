@@ -0,0 +1,309 @@
+//! Link Integrity
+//!
+//! Frames the modem cannot accept are answered with a `Nak` frame instead of
+//! being dropped silently, so the host can retransmit.
+//!
+//! NAK payload layout (big-endian):
+//! [Reason (1)] [Request Seq (2)]
+//!
+//! The request sequence number is only known for frames that passed the CRC
+//! check; it is `UNSOLICITED_SEQ` (0) otherwise. In sequenced framing the NAK
+//! frame itself carries the same sequence number, so a NAK for a corrupted
+//! frame reaches the host as unsolicited.
+//!
+//! Retransmissions reuse the original sequence number. The modem remembers the
+//! last [`DUPLICATE_WINDOW_SIZE`] sequenced requests and never runs a repeated
+//! one twice. Entries are matched on the request content too, so a restarted
+//! host reusing sequence numbers for different requests is not mistaken for a
+//! retransmission; HELLO clears the window.
+//!
+//! Each entry keeps the response frame sent for its request (single-frame
+//! responses up to [`CACHED_RESPONSE_SIZE`] bytes), and a retransmission is
+//! answered with that frame again, so a host that lost the response still gets
+//! the result. A retransmission of a request whose response is larger, or that
+//! is still running, gets a `Duplicate` NAK instead: the request was executed
+//! (or will be), and the host should keep waiting for its response rather than
+//! treat the NAK as a failure.
+//!
+//! GET_LINK_STATS response layout (big-endian, every counter 4 bytes):
+//! [Frames Received] [Frames Sent] [Invalid Length] [Invalid CRC] [Malformed]
+//! [Fragment Errors] [RX Overflows] [Duplicates] [RX Errors] [TX Errors]
+//! [NAKs Dropped] [TX Pool Exhausted] [TX Queue High Water]
+
+use heapless::Vec;
+
+use crate::serialization::{write_u16, write_u32, write_u8, PayloadReader};
+use crate::{calculate_crc16, Packet, ProtocolError, UNSOLICITED_SEQ};
+
+/// Size of an encoded NAK payload
+pub const NAK_SIZE: usize = 3;
+
+/// Size of an encoded [`LinkStats`]
+pub const LINK_STATS_SIZE: usize = 13 * 4;
+
+/// Number of recent sequenced requests checked for retransmissions
+pub const DUPLICATE_WINDOW_SIZE: usize = 8;
+
+/// Largest response frame the [`DuplicateWindow`] keeps for a retransmission
+pub const CACHED_RESPONSE_SIZE: usize = 64;
+
+/// Why a frame was rejected
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NakReason {
+    /// Length header did not match the received frame
+    InvalidLength = 0x01,
+    /// CRC check failed
+    InvalidCrc = 0x02,
+    /// Frame passed the CRC check but could not be decoded (COBS, sequence field, ...)
+    Malformed = 0x03,
+    /// Fragment out of order, or the packet was not completed in time
+    Fragment = 0x04,
+    /// The command queue was full, the request was dropped
+    Overflow = 0x05,
+    /// Retransmission of a request that was already received: it is not run
+    /// again and its response is either still to come or was lost
+    Duplicate = 0x06,
+}
+
+impl NakReason {
+    /// Convert from raw u8 value
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0x01 => Some(Self::InvalidLength),
+            0x02 => Some(Self::InvalidCrc),
+            0x03 => Some(Self::Malformed),
+            0x04 => Some(Self::Fragment),
+            0x05 => Some(Self::Overflow),
+            0x06 => Some(Self::Duplicate),
+            _ => None,
+        }
+    }
+
+    /// Reason for a frame rejected by [`Frame::parse`](crate::Frame::parse) or reassembly
+    pub fn from_error(err: ProtocolError) -> Self {
+        match err {
+            ProtocolError::InvalidLength => Self::InvalidLength,
+            ProtocolError::InvalidCrc => Self::InvalidCrc,
+            ProtocolError::Fragmented | ProtocolError::FragmentOutOfOrder | ProtocolError::ReassemblyTimeout => {
+                Self::Fragment
+            }
+            _ => Self::Malformed,
+        }
+    }
+
+    /// Check if the request was dropped before being run, so resending it is safe
+    pub fn is_retransmittable(self) -> bool {
+        self != Self::Duplicate
+    }
+}
+
+/// Decoded `ResponseCode::Nak` payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Nak {
+    pub reason: NakReason,
+    /// Sequence number of the rejected request (`UNSOLICITED_SEQ` if unknown)
+    pub seq: u16,
+}
+
+impl Nak {
+    /// Append the encoded NAK to a buffer
+    pub fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u8(buffer, self.reason as u8)?;
+        write_u16(buffer, self.seq)
+    }
+
+    /// Decode a NAK payload
+    pub fn decode(payload: &[u8]) -> Result<Self, ProtocolError> {
+        let mut reader = PayloadReader::new(payload);
+        let reason = NakReason::from_u8(reader.read_u8()?).ok_or(ProtocolError::InvalidData)?;
+        Ok(Self {
+            reason,
+            seq: reader.read_u16()?,
+        })
+    }
+
+    /// Request sequence number, if the rejected frame's was known
+    pub fn request_seq(&self) -> Option<u16> {
+        (self.seq != UNSOLICITED_SEQ).then_some(self.seq)
+    }
+}
+
+/// A request remembered by the [`DuplicateWindow`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SeenRequest {
+    seq: u16,
+    code: u16,
+    payload_crc: u16,
+}
+
+impl SeenRequest {
+    fn of(packet: &Packet) -> Option<Self> {
+        Some(Self {
+            seq: packet.seq?,
+            code: packet.code,
+            payload_crc: calculate_crc16(&packet.payload),
+        })
+    }
+}
+
+/// A remembered request and the response frame sent for it
+struct WindowEntry {
+    request: SeenRequest,
+    /// `None` until the response is recorded, or if it was too large to keep
+    response: Option<Vec<u8, CACHED_RESPONSE_SIZE>>,
+}
+
+/// Recently received sequenced requests, for detecting retransmissions
+pub struct DuplicateWindow {
+    seen: [Option<WindowEntry>; DUPLICATE_WINDOW_SIZE],
+    next: usize,
+}
+
+impl DuplicateWindow {
+    /// Create an empty window
+    pub const fn new() -> Self {
+        Self {
+            seen: [const { None }; DUPLICATE_WINDOW_SIZE],
+            next: 0,
+        }
+    }
+
+    /// Check if `packet` repeats a remembered request
+    ///
+    /// Legacy (unsequenced) requests are never duplicates.
+    pub fn is_duplicate(&self, packet: &Packet) -> bool {
+        self.entry(packet).is_some()
+    }
+
+    /// Response frame sent for the request `packet` repeats, if it was kept
+    pub fn cached_response(&self, packet: &Packet) -> Option<&[u8]> {
+        self.entry(packet)?.response.as_deref()
+    }
+
+    /// Remember a request accepted for processing, evicting the oldest one
+    ///
+    /// Only record requests that will actually run, so a dropped request can
+    /// still be retransmitted.
+    pub fn remember(&mut self, packet: &Packet) {
+        if let Some(request) = SeenRequest::of(packet) {
+            self.seen[self.next] = Some(WindowEntry {
+                request,
+                response: None,
+            });
+            self.next = (self.next + 1) % DUPLICATE_WINDOW_SIZE;
+        }
+    }
+
+    /// Keep the response frame sent for the latest request with sequence number `seq`
+    ///
+    /// Returns `false` if the request is not remembered or the frame is larger
+    /// than [`CACHED_RESPONSE_SIZE`]; a retransmission is then NAKed as `Duplicate`.
+    pub fn record_response(&mut self, seq: u16, frame: &[u8]) -> bool {
+        // Newest first: a restarted host may have reused `seq` for an older entry
+        let newest_first =
+            (1..=DUPLICATE_WINDOW_SIZE).map(|age| (self.next + DUPLICATE_WINDOW_SIZE - age) % DUPLICATE_WINDOW_SIZE);
+        for index in newest_first {
+            if let Some(entry) = self.seen[index].as_mut().filter(|entry| entry.request.seq == seq) {
+                entry.response = Vec::from_slice(frame).ok();
+                return entry.response.is_some();
+            }
+        }
+        false
+    }
+
+    /// Forget every remembered request
+    pub fn clear(&mut self) {
+        self.seen = [const { None }; DUPLICATE_WINDOW_SIZE];
+        self.next = 0;
+    }
+
+    fn entry(&self, packet: &Packet) -> Option<&WindowEntry> {
+        let request = SeenRequest::of(packet)?;
+        self.seen.iter().flatten().find(|entry| entry.request == request)
+    }
+}
+
+impl Default for DuplicateWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Link traffic and failure counters since boot or the last reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LinkStats {
+    /// Frames received from the host, valid or not
+    pub frames_received: u32,
+    /// Frames transmitted to the host
+    pub frames_sent: u32,
+    /// Frames whose length header did not match
+    pub invalid_length: u32,
+    /// Frames that failed the CRC check
+    pub invalid_crc: u32,
+    /// Frames that could not be decoded for other reasons
+    pub malformed: u32,
+    /// Fragmented requests dropped (out of order or timed out)
+    pub fragment_errors: u32,
+    /// Requests dropped because the command queue was full
+    pub rx_overflows: u32,
+    /// Retransmitted requests that were not run again
+    pub duplicates: u32,
+    /// Receive transfers the link driver reported as failed
+    pub rx_errors: u32,
+    /// Frames the link driver failed to transmit, plus packets dropped because the host was not ready
+    pub tx_errors: u32,
+    /// NAK frames that could not be queued for the host
+    pub naks_dropped: u32,
+    /// TX buffer allocations that failed because the pool was empty
+    pub tx_pool_exhausted: u32,
+    /// Most packets waiting in the TX queue at once
+    pub tx_queue_high_water: u32,
+}
+
+impl LinkStats {
+    /// Append the encoded statistics to a buffer
+    pub fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        for value in [
+            self.frames_received,
+            self.frames_sent,
+            self.invalid_length,
+            self.invalid_crc,
+            self.malformed,
+            self.fragment_errors,
+            self.rx_overflows,
+            self.duplicates,
+            self.rx_errors,
+            self.tx_errors,
+            self.naks_dropped,
+            self.tx_pool_exhausted,
+            self.tx_queue_high_water,
+        ] {
+            write_u32(buffer, value)?;
+        }
+        Ok(())
+    }
+
+    /// Decode statistics from a GET_LINK_STATS response payload
+    pub fn decode(payload: &[u8]) -> Result<Self, ProtocolError> {
+        let mut reader = PayloadReader::new(payload);
+        Ok(Self {
+            frames_received: reader.read_u32()?,
+            frames_sent: reader.read_u32()?,
+            invalid_length: reader.read_u32()?,
+            invalid_crc: reader.read_u32()?,
+            malformed: reader.read_u32()?,
+            fragment_errors: reader.read_u32()?,
+            rx_overflows: reader.read_u32()?,
+            duplicates: reader.read_u32()?,
+            rx_errors: reader.read_u32()?,
+            tx_errors: reader.read_u32()?,
+            naks_dropped: reader.read_u32()?,
+            tx_pool_exhausted: reader.read_u32()?,
+            tx_queue_high_water: reader.read_u32()?,
+        })
+    }
+}
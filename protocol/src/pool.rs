@@ -0,0 +1,58 @@
+//! TX Buffer Pool Statistics
+//!
+//! Responses and events are built in a fixed pool of TX buffers, one per frame.
+//! GET_POOL_STATS reports its live usage; a `SystemEvent::PoolLow` event warns
+//! the host when it is nearly exhausted.
+//!
+//! GET_POOL_STATS request layout: [Reset (1)]
+//!
+//! GET_POOL_STATS response layout (big-endian):
+//! [Pool Size (1)] [Allocated (1)] [Peak (1)] [Allocation Failures (4)]
+
+use heapless::Vec;
+
+use crate::serialization::{write_u32, write_u8, PayloadReader};
+use crate::ProtocolError;
+
+/// Size of an encoded [`PoolStats`]
+pub const POOL_STATS_SIZE: usize = 7;
+
+/// TX buffer pool usage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PoolStats {
+    /// Number of buffers in the pool
+    pub size: u8,
+    /// Buffers currently in use
+    pub allocated: u8,
+    /// Most buffers in use at once since boot
+    pub peak: u8,
+    /// Allocations that failed because every buffer was in use
+    pub alloc_failures: u32,
+}
+
+impl PoolStats {
+    /// Buffers currently free
+    pub fn available(&self) -> u8 {
+        self.size.saturating_sub(self.allocated)
+    }
+
+    /// Append the encoded statistics to a buffer
+    pub fn encode<const N: usize>(&self, buffer: &mut Vec<u8, N>) -> Result<(), ProtocolError> {
+        write_u8(buffer, self.size)?;
+        write_u8(buffer, self.allocated)?;
+        write_u8(buffer, self.peak)?;
+        write_u32(buffer, self.alloc_failures)
+    }
+
+    /// Decode statistics from a GET_POOL_STATS response payload
+    pub fn decode(payload: &[u8]) -> Result<Self, ProtocolError> {
+        let mut reader = PayloadReader::new(payload);
+        Ok(Self {
+            size: reader.read_u8()?,
+            allocated: reader.read_u8()?,
+            peak: reader.read_u8()?,
+            alloc_failures: reader.read_u32()?,
+        })
+    }
+}
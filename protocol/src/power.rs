@@ -0,0 +1,145 @@
+//! Power Management
+//!
+//! SHUTDOWN puts the modem in System OFF. Only a level change on the selected
+//! wake pin ends it, and waking resets the chip, so the host should expect a
+//! fresh boot afterwards. Both request fields are optional and default to the
+//! host link's select line ([`WAKE_PIN_LINK`]) going low.
+//!
+//! SHUTDOWN request layout: [Wake Pin (1)] [Wake Polarity (1)]
+//!
+//! REBOOT resets the modem once its ACK has been sent, into the application or
+//! the bootloader. After every reset the modem sends a `SystemEvent::Boot`
+//! event with the [`ResetReason`] so the host can tell a crash from a request.
+//!
+//! REBOOT request layout: [Mode (1)] (optional, see [`RebootMode`])
+//!
+//! FACTORY_RESET wipes bonds, the GATT schema and the host-set configuration,
+//! then resets like REBOOT. It only runs if the request carries
+//! [`FACTORY_RESET_TOKEN`], so a corrupted or misrouted request cannot erase
+//! a modem.
+//!
+//! FACTORY_RESET request layout: [Token (4)]
+
+/// Wake pin value selecting the host link's select line (UART RX with the UART link)
+pub const WAKE_PIN_LINK: u8 = 0xFF;
+
+/// Highest GPIO (P0.n) accepted as a wake pin
+pub const WAKE_PIN_MAX: u8 = 31;
+
+/// Wake pin level that ends System OFF
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum WakePolarity {
+    /// Wake when the pin is driven low (pulled up while off)
+    #[default]
+    Low = 0,
+    /// Wake when the pin is driven high (pulled down while off)
+    High = 1,
+}
+
+impl WakePolarity {
+    /// Polarity from its wire value
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Low),
+            1 => Some(Self::High),
+            _ => None,
+        }
+    }
+}
+
+/// Confirmation token FACTORY_RESET must carry ("FRST")
+pub const FACTORY_RESET_TOKEN: u32 = 0x4652_5354;
+
+/// Where REBOOT restarts the modem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum RebootMode {
+    /// Restart the application
+    #[default]
+    Application = 0,
+    /// Stay in the bootloader (DFU) after the reset
+    Bootloader = 1,
+}
+
+impl RebootMode {
+    /// Mode from its wire value
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Application),
+            1 => Some(Self::Bootloader),
+            _ => None,
+        }
+    }
+}
+
+/// Cause of the last reset, reported by `SystemEvent::Boot`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ResetReason {
+    /// Power-on or brown-out reset
+    PowerOn,
+    /// Reset pin
+    Pin,
+    /// Watchdog timeout
+    Watchdog,
+    /// Host sent REBOOT
+    HostRequested,
+    /// Firmware panicked
+    Panic,
+    /// Software reset not requested by the host (e.g. a SoftDevice assert)
+    SoftReset,
+    /// CPU lockup
+    Lockup,
+    /// Woken from System OFF by the SHUTDOWN wake pin
+    WakeFromOff,
+    /// Debug interface
+    Debugger,
+    /// Host sent FACTORY_RESET
+    FactoryReset,
+    /// Reason ID not known to this protocol version
+    Unknown(u8),
+}
+
+impl ResetReason {
+    /// Reason from its wire value
+    pub const fn from_id(id: u8) -> Self {
+        match id {
+            0 => Self::PowerOn,
+            1 => Self::Pin,
+            2 => Self::Watchdog,
+            3 => Self::HostRequested,
+            4 => Self::Panic,
+            5 => Self::SoftReset,
+            6 => Self::Lockup,
+            7 => Self::WakeFromOff,
+            8 => Self::Debugger,
+            9 => Self::FactoryReset,
+            other => Self::Unknown(other),
+        }
+    }
+
+    /// Wire value
+    pub const fn id(self) -> u8 {
+        match self {
+            Self::PowerOn => 0,
+            Self::Pin => 1,
+            Self::Watchdog => 2,
+            Self::HostRequested => 3,
+            Self::Panic => 4,
+            Self::SoftReset => 5,
+            Self::Lockup => 6,
+            Self::WakeFromOff => 7,
+            Self::Debugger => 8,
+            Self::FactoryReset => 9,
+            Self::Unknown(id) => id,
+        }
+    }
+
+    /// Check if the reset was not asked for by the host or a person
+    pub const fn is_fault(self) -> bool {
+        matches!(self, Self::Watchdog | Self::Panic | Self::SoftReset | Self::Lockup)
+    }
+}
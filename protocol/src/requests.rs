@@ -0,0 +1,223 @@
+//! Protocol Table
+//!
+//! Single definition of every request: its code and, where modelled, the typed
+//! payload struct with its field encodings. The table generates [`RequestCode`],
+//! `RequestCode::from_u16`, the request structs and their minimum-length checks,
+//! so the firmware dispatcher and the host client cannot drift apart.
+//!
+//! Fields are big-endian unless marked `as Le`; see [`crate::codec`].
+
+use crate::codec::{protocol_table, AdvData, AdvParams, Uuid};
+
+protocol_table! {
+    // System Commands
+    GetInfo = 0x0001 => GetInfoRequest {},
+    /// Enter System OFF, see [`crate::power`]
+    Shutdown = 0x0002 => ShutdownRequest {
+        /// P0 pin that wakes the modem, or [`WAKE_PIN_LINK`](crate::power::WAKE_PIN_LINK)
+        wake_pin: Option<u8> as Opt,
+        /// [`WakePolarity`](crate::power::WakePolarity) of the wake pin
+        wake_polarity: Option<u8> as Opt,
+    },
+    Echo = 0x0003 => EchoRequest<'a> {
+        data: &'a [u8] as Rest,
+    },
+    /// Reset the modem, see [`crate::power`]
+    Reboot = 0x00F0 => RebootRequest {
+        /// [`RebootMode`](crate::power::RebootMode), application when omitted
+        mode: Option<u8> as Opt,
+    },
+
+    // Capability Negotiation
+    Hello = 0x0006 => HelloRequest {
+        /// Host protocol version (optional, logged only)
+        host_version: Option<u16> as Opt,
+    },
+
+    // Event Subscription (0x0004 / 0x0005 were the retired callback registration commands)
+    SetEventMask = 0x0007 => SetEventMaskRequest {
+        /// [`EventMask`](crate::events::EventMask) bits; unknown bits are ignored
+        mask: u32,
+    },
+    GetEventMask = 0x0008 => GetEventMaskRequest {},
+    SetCharEventFilter = 0x0009 => SetCharEventFilterRequest {
+        /// Characteristic value handle
+        char_handle: u16,
+        /// Forward events of this characteristic (false mutes it)
+        enabled: bool,
+    },
+    ClearCharEventFilters = 0x000A => ClearCharEventFiltersRequest {},
+    /// Device uptime, for aligning event timestamps with the host clock
+    GetClock = 0x000B => GetClockRequest {},
+
+    // Batching
+    /// Several sub-commands run in order, see [`crate::batch`]
+    Batch = 0x000C => BatchRequest<'a> {
+        /// [`BATCH_STOP_ON_ERROR`](crate::batch::BATCH_STOP_ON_ERROR)
+        flags: u8,
+        /// Sub-command entries
+        commands: &'a [u8] as Rest,
+    },
+
+    // Diagnostics
+    /// Link traffic and failure counters, see [`crate::link::LinkStats`]
+    GetLinkStats = 0x000D => GetLinkStatsRequest {
+        /// Zero the counters after reading them
+        reset: bool,
+    },
+    /// TX buffer pool usage, see [`crate::pool::PoolStats`]
+    GetPoolStats = 0x000E => GetPoolStatsRequest {
+        /// Restart peak tracking and zero the failure count after reading
+        reset: bool,
+    },
+
+    // Maintenance
+    /// Wipe bonds, GATT schema and configuration and reset, see [`crate::power`]
+    FactoryReset = 0x000F => FactoryResetRequest {
+        /// Must be [`FACTORY_RESET_TOKEN`](crate::power::FACTORY_RESET_TOKEN)
+        token: u32,
+    },
+
+    // UUID Management
+    RegisterUuidGroup = 0x0010 => RegisterUuidGroupRequest {
+        uuid_base: [u8; 16],
+    },
+
+    // GAP Operations - Address Management
+    GapGetAddr = 0x0011 => GapGetAddrRequest {},
+    GapSetAddr = 0x0012 => GapSetAddrRequest {
+        /// 0=Public, 1=RandomStatic, 2=RandomPrivateResolvable, 3=RandomPrivateNonResolvable
+        addr_type: u8,
+        addr: [u8; 6],
+    },
+
+    // GAP Operations - Advertising Control
+    GapAdvStart = 0x0020 => GapAdvStartRequest {
+        adv_handle: u8,
+        conn_cfg_tag: u8,
+    },
+    GapAdvStop = 0x0021 => GapAdvStopRequest {
+        adv_handle: u8,
+    },
+    GapAdvSetConfigure = 0x0022 => GapAdvSetConfigureRequest<'a> {
+        adv_handle: u8,
+        data_present: bool,
+        data: Option<AdvData<'a>> as Opt,
+        /// Interval, timeout and filter policy; `data` must be present (empty
+        /// if `data_present` is false) to send them
+        params: Option<AdvParams> as Opt,
+    },
+
+    // GAP Operations - Device Configuration
+    GapGetName = 0x0023 => GapGetNameRequest {
+        /// Return the name itself, not only its length
+        present: bool,
+    },
+    GapSetName = 0x0024 => GapSetNameRequest<'a> {
+        /// Security mode `sm` field
+        sec_mode: u8,
+        /// Security mode `lv` field
+        sec_level: u8,
+        name: &'a [u8] as Rest,
+    },
+    GapConnParamsGet = 0x0025 => GapConnParamsGetRequest {},
+    GapConnParamsSet = 0x0026 => GapConnParamsSetRequest {
+        min_conn_interval: u16,
+        max_conn_interval: u16,
+        slave_latency: u16,
+        conn_sup_timeout: u16,
+    },
+
+    // GAP Operations - Connection Management
+    GapConnParamUpdate = 0x0027 => GapConnParamUpdateRequest {
+        conn_handle: u16 as Le,
+        /// 1.25ms units
+        min_conn_interval: u16 as Le,
+        /// 1.25ms units
+        max_conn_interval: u16 as Le,
+        slave_latency: u16 as Le,
+        /// 10ms units
+        conn_sup_timeout: u16 as Le,
+    },
+    GapDataLengthUpdate = 0x0028 => GapDataLengthUpdateRequest {
+        conn_handle: u16 as Le,
+        tx_octets: u16 as Le,
+        tx_time_us: u16 as Le,
+    },
+    GapPhyUpdate = 0x0029 => GapPhyUpdateRequest {
+        conn_handle: u16 as Le,
+        /// Bitmask: 0x01=1M, 0x02=2M, 0x04=Coded
+        tx_phys: u8,
+        /// Bitmask: 0x01=1M, 0x02=2M, 0x04=Coded
+        rx_phys: u8,
+        /// 0x0000=No preference, 0x0001=S2, 0x0002=S8 (not used by the SoftDevice API)
+        coded_phy: u16 as Le,
+    },
+    /// Central mode only
+    GapConnect = 0x002A,
+    /// Central mode only
+    GapConnectCancel = 0x002B,
+    GapDisconnect = 0x002C => GapDisconnectRequest {
+        conn_handle: u16 as Le,
+        /// HCI disconnect reason code
+        reason: u8,
+    },
+
+    // GAP Operations - Power & RSSI
+    GapSetTxPower = 0x002D => GapSetTxPowerRequest {
+        /// 0x01=Advertising, 0x02=Scanning, 0x03=Connection
+        role: u8,
+        /// Only used for the connection role
+        conn_handle: u16 as Le,
+        /// dBm (-40 to +8)
+        tx_power: i8,
+    },
+    GapStartRssiReporting = 0x002E => GapStartRssiReportingRequest {},
+    GapStopRssiReporting = 0x002F => GapStopRssiReportingRequest {},
+
+    // GAP Operations - Scanning (Central mode only)
+    GapScanStart = 0x0030,
+    GapScanStop = 0x0031,
+
+    // GATT Server Operations
+    GattsServiceAdd = 0x0080 => GattsServiceAddRequest {
+        uuid: Uuid,
+        /// 1=Primary, 2=Secondary
+        service_type: u8,
+    },
+    GattsCharacteristicAdd = 0x0081 => GattsCharacteristicAddRequest<'a> {
+        service_handle: u16,
+        uuid: Uuid,
+        /// BLE characteristic properties bitmask
+        properties: u8,
+        max_length: u16,
+        initial_value: &'a [u8] as Len8,
+        /// Defaults to open permissions when omitted
+        permissions: Option<u8> as Opt,
+    },
+    GattsMtuReply = 0x0082 => GattsMtuReplyRequest {
+        conn_handle: u16,
+        mtu: u16,
+    },
+    GattsHvx = 0x0083 => GattsHvxRequest<'a> {
+        conn_handle: u16,
+        char_handle: u16,
+        /// 0x01=Notification, 0x02=Indication
+        hvx_type: u8,
+        data: &'a [u8] as Len16,
+    },
+    /// Not implemented in original
+    GattsSysAttrGet = 0x0084,
+    GattsSysAttrSet = 0x0085 => GattsSysAttrSetRequest<'a> {
+        conn_handle: u16,
+        sys_attr: &'a [u8] as Len16,
+    },
+
+    // GATT Client Operations (Central mode only)
+    GattcMtuRequest = 0x00A0,
+    GattcServiceDiscover = 0x00A1,
+    GattcCharacteristicsDiscover = 0x00A2,
+    GattcDescriptorsDiscover = 0x00A3,
+    GattcRead = 0x00A4,
+    GattcWrite = 0x00A5,
+}
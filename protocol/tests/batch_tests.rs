@@ -0,0 +1,80 @@
+//! Batch entry encoding tests
+
+use ble_modem_protocol::batch::{BatchEntries, BatchEntry, BatchResponse, BATCH_STOP_ON_ERROR};
+use ble_modem_protocol::codec::Request;
+use ble_modem_protocol::requests::BatchRequest;
+use ble_modem_protocol::{ProtocolError, RequestCode, ResponseCode};
+use heapless::Vec;
+
+#[test]
+fn test_batch_request_roundtrip() {
+    let mut commands: Vec<u8, 64> = Vec::new();
+    BatchEntry {
+        code: RequestCode::Echo as u16,
+        payload: b"hi",
+    }
+    .encode(&mut commands)
+    .unwrap();
+    BatchEntry {
+        code: RequestCode::GapGetAddr as u16,
+        payload: &[],
+    }
+    .encode(&mut commands)
+    .unwrap();
+    assert_eq!(
+        commands.as_slice(),
+        &[0x00, 0x03, 0x00, 0x02, b'h', b'i', 0x00, 0x11, 0x00, 0x00]
+    );
+
+    let request = BatchRequest {
+        flags: BATCH_STOP_ON_ERROR,
+        commands: &commands,
+    };
+    let mut payload: Vec<u8, 64> = Vec::new();
+    request.encode(&mut payload).unwrap();
+    let decoded = BatchRequest::decode(&payload).unwrap();
+    assert_eq!(decoded.flags, BATCH_STOP_ON_ERROR);
+
+    let entries: std::vec::Vec<_> = BatchEntries::new(decoded.commands).map(Result::unwrap).collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].code, RequestCode::Echo as u16);
+    assert_eq!(entries[0].payload, b"hi");
+    assert_eq!(entries[1].code, RequestCode::GapGetAddr as u16);
+    assert!(entries[1].payload.is_empty());
+}
+
+#[test]
+fn test_truncated_entry_stops_iteration() {
+    // Entry claims 4 payload bytes but only 1 follows
+    let data = [0x00, 0x03, 0x00, 0x04, 0xAA];
+    let mut entries = BatchEntries::new(&data);
+    assert_eq!(entries.next(), Some(Err(ProtocolError::InvalidData)));
+    assert_eq!(entries.next(), None);
+
+    assert!(BatchEntries::new(&[]).next().is_none());
+}
+
+#[test]
+fn test_batch_response_decode() {
+    let mut payload: Vec<u8, 64> = Vec::new();
+    payload.push(2).unwrap();
+    BatchEntry {
+        code: ResponseCode::Ack.to_u16(),
+        payload: &[0x00, 0x01],
+    }
+    .encode(&mut payload)
+    .unwrap();
+    BatchEntry {
+        code: ResponseCode::Error.to_u16(),
+        payload: &[0x00, 0x02],
+    }
+    .encode(&mut payload)
+    .unwrap();
+
+    let response = BatchResponse::decode(&payload).unwrap();
+    assert_eq!(response.executed, 2);
+    let codes: std::vec::Vec<_> = response.results().map(|entry| entry.unwrap().code).collect();
+    assert_eq!(codes, [ResponseCode::Ack.to_u16(), ResponseCode::Error.to_u16()]);
+
+    assert!(BatchResponse::decode(&[]).is_err());
+}
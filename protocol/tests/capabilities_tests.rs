@@ -0,0 +1,53 @@
+//! Host-run tests for the HELLO capabilities record
+
+use ble_modem_protocol::capabilities::{Capabilities, CommandBitmap, SoftDeviceVersion};
+use ble_modem_protocol::{ProtocolError, RequestCode, PROTOCOL_VERSION};
+
+fn sample() -> Capabilities {
+    let mut commands = CommandBitmap::new();
+    commands.insert(RequestCode::Hello);
+    commands.insert(RequestCode::Reboot);
+    commands.insert(RequestCode::GattsHvx);
+
+    Capabilities {
+        protocol_version: PROTOCOL_VERSION,
+        firmware_version: 0x0001,
+        max_message_size: 528,
+        max_services: 8,
+        max_characteristics: 32,
+        tx_pool_size: 8,
+        max_mtu: 128,
+        max_connections: 2,
+        softdevice: SoftDeviceVersion {
+            fwid: 0x0123,
+            ll_version: 0x0C,
+            company_id: 0x0059,
+        },
+        commands,
+        build_id: "1a2b3c4d".try_into().unwrap(),
+    }
+}
+
+#[test]
+fn test_capabilities_roundtrip() {
+    let capabilities = sample();
+    let mut payload: heapless::Vec<u8, 128> = heapless::Vec::new();
+    capabilities.encode(&mut payload).unwrap();
+
+    let decoded = Capabilities::decode(&payload).unwrap();
+    assert_eq!(decoded, capabilities);
+    assert!(decoded.commands.contains(RequestCode::Hello));
+    assert!(decoded.commands.contains(RequestCode::Reboot));
+    assert!(!decoded.commands.contains(RequestCode::GapScanStart));
+}
+
+#[test]
+fn test_capabilities_truncated() {
+    let mut payload: heapless::Vec<u8, 128> = heapless::Vec::new();
+    sample().encode(&mut payload).unwrap();
+
+    assert_eq!(
+        Capabilities::decode(&payload[..payload.len() - 1]).unwrap_err(),
+        ProtocolError::InvalidData
+    );
+}
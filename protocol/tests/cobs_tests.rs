@@ -0,0 +1,125 @@
+//! Host-run tests for COBS framing
+
+use ble_modem_protocol::cobs::{self, CobsDecoder, DELIMITER};
+use ble_modem_protocol::{Packet, ProtocolError, RequestCode, MAX_PAYLOAD_SIZE};
+
+fn roundtrip(data: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![0u8; cobs::max_encoded_len(data.len())];
+    let len = cobs::encode(data, &mut encoded).unwrap();
+    assert!(!encoded[..len].contains(&DELIMITER));
+
+    let mut decoded = vec![0u8; data.len()];
+    let decoded_len = cobs::decode(&encoded[..len], &mut decoded).unwrap();
+    assert_eq!(&decoded[..decoded_len], data);
+
+    let mut in_place = encoded[..len].to_vec();
+    let in_place_len = cobs::decode_in_place(&mut in_place).unwrap();
+    assert_eq!(&in_place[..in_place_len], data);
+
+    encoded.truncate(len);
+    encoded
+}
+
+#[test]
+fn test_cobs_known_vectors() {
+    assert_eq!(roundtrip(&[]), [0x01]);
+    assert_eq!(roundtrip(&[0x00]), [0x01, 0x01]);
+    assert_eq!(roundtrip(&[0x00, 0x00]), [0x01, 0x01, 0x01]);
+    assert_eq!(roundtrip(&[0x11, 0x22, 0x00, 0x33]), [0x03, 0x11, 0x22, 0x02, 0x33]);
+    assert_eq!(roundtrip(&[0x11, 0x00, 0x00, 0x00]), [0x02, 0x11, 0x01, 0x01, 0x01]);
+}
+
+#[test]
+fn test_cobs_long_runs() {
+    // 254 non-zero bytes fill exactly one block
+    let data: Vec<u8> = (1..=254).collect();
+    let encoded = roundtrip(&data);
+    assert_eq!(encoded.len(), 256);
+    assert_eq!(encoded[0], 0xFF);
+
+    let data: Vec<u8> = (0..600).map(|i| (i % 7) as u8).collect();
+    roundtrip(&data);
+
+    let data = vec![0xAA; MAX_PAYLOAD_SIZE];
+    assert!(roundtrip(&data).len() <= cobs::max_encoded_len(MAX_PAYLOAD_SIZE));
+}
+
+#[test]
+fn test_cobs_encode_buffer_too_small() {
+    let mut encoded = [0u8; 3];
+    assert_eq!(cobs::encode(&[1, 2, 3], &mut encoded), Err(ProtocolError::BufferFull));
+}
+
+#[test]
+fn test_cobs_decode_rejects_invalid_input() {
+    let mut decoded = [0u8; 16];
+    // Embedded zero byte
+    assert_eq!(
+        cobs::decode(&[0x02, 0x00], &mut decoded),
+        Err(ProtocolError::InvalidData)
+    );
+    // Block runs past the end of the input
+    assert_eq!(
+        cobs::decode(&[0x05, 0x11], &mut decoded),
+        Err(ProtocolError::InvalidData)
+    );
+    assert_eq!(
+        cobs::decode_in_place(&mut [0x05, 0x11]),
+        Err(ProtocolError::InvalidData)
+    );
+}
+
+#[test]
+fn test_cobs_decoder_splits_stream() {
+    let first = Packet::new_request_for_sending(RequestCode::Echo, &[0x00, 0x01, 0x00])
+        .unwrap()
+        .with_seq(Some(7))
+        .serialize_request()
+        .unwrap();
+    let second = Packet::new_request_for_sending(RequestCode::GetInfo, &[])
+        .unwrap()
+        .serialize_request()
+        .unwrap();
+
+    let mut stream = vec![DELIMITER];
+    for frame in [&first[..], &second[..]] {
+        let mut encoded = [0u8; cobs::max_encoded_len(MAX_PAYLOAD_SIZE)];
+        let len = cobs::encode(frame, &mut encoded).unwrap();
+        stream.extend_from_slice(&encoded[..len]);
+        stream.push(DELIMITER);
+    }
+
+    let mut decoder = CobsDecoder::<{ cobs::max_encoded_len(MAX_PAYLOAD_SIZE) }>::new();
+    let mut frames = Vec::new();
+    for byte in stream {
+        if let Some(frame) = decoder.push(byte).unwrap() {
+            frames.push(frame.to_vec());
+        }
+    }
+
+    assert_eq!(frames, [first.to_vec(), second.to_vec()]);
+    let packet = Packet::new_request(&frames[0]).unwrap();
+    assert_eq!(packet.seq, Some(7));
+    assert_eq!(&packet.payload[..], &[0x00, 0x01, 0x00]);
+}
+
+#[test]
+fn test_cobs_decoder_resynchronizes() {
+    let mut decoder = CobsDecoder::<8>::new();
+
+    // Oversized frame is reported once at its delimiter
+    for byte in [0x01; 12] {
+        assert_eq!(decoder.push(byte), Ok(None));
+    }
+    assert_eq!(decoder.push(DELIMITER), Err(ProtocolError::BufferFull));
+
+    // Corrupt frame
+    decoder.push(0x05).unwrap();
+    assert_eq!(decoder.push(DELIMITER), Err(ProtocolError::InvalidData));
+
+    // Next frame decodes normally
+    for byte in [0x03, 0x11, 0x22, 0x02, 0x33] {
+        assert_eq!(decoder.push(byte), Ok(None));
+    }
+    assert_eq!(decoder.push(DELIMITER), Ok(Some(&[0x11, 0x22, 0x00, 0x33][..])));
+}
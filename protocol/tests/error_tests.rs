@@ -0,0 +1,31 @@
+//! Host-run tests for structured error responses
+
+use ble_modem_protocol::error::{ErrorCategory, ErrorResponse, ERROR_RESPONSE_SIZE};
+use ble_modem_protocol::RequestCode;
+
+#[test]
+fn test_error_response_roundtrip() {
+    let error = ErrorResponse {
+        category: ErrorCategory::SoftDevice as u16,
+        request_code: RequestCode::GapDisconnect as u16,
+        detail: 0,
+        nrf_error: 0x3002,
+    };
+
+    let mut payload: heapless::Vec<u8, 16> = heapless::Vec::new();
+    error.encode(&mut payload).unwrap();
+    assert_eq!(payload.len(), ERROR_RESPONSE_SIZE);
+    assert_eq!(&payload[..2], &[0x00, 0x06]);
+
+    let decoded = ErrorResponse::decode(&payload).unwrap();
+    assert_eq!(decoded, error);
+    assert_eq!(decoded.category(), Some(ErrorCategory::SoftDevice));
+}
+
+#[test]
+fn test_legacy_error_code_decodes() {
+    let decoded = ErrorResponse::decode(&[0x00, 0x02]).unwrap();
+    assert_eq!(decoded.category(), Some(ErrorCategory::InvalidPayload));
+    assert_eq!(decoded.request_code, 0);
+    assert_eq!(decoded.nrf_error, 0);
+}
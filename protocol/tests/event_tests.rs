@@ -0,0 +1,132 @@
+//! Host-run tests for event headers and subscription encoding
+
+use ble_modem_protocol::events::{
+    EventHeader, EventMask, EventSubscription, SocEvent, SystemEvent, EVENT_HEADER_SIZE, MAX_CHAR_EVENT_FILTERS,
+};
+use ble_modem_protocol::power::ResetReason;
+use ble_modem_protocol::{ProtocolError, MAX_PAYLOAD_SIZE};
+use heapless::Vec;
+
+#[test]
+fn test_event_mask_bits() {
+    let mask = EventMask::GAP_CONNECTION.union(EventMask::CCCD);
+    assert_eq!(mask.bits(), 0b1001);
+    assert!(mask.contains(EventMask::CCCD));
+    assert!(!mask.contains(EventMask::GATTS_WRITE));
+    assert!(EventMask::ALL.contains(mask));
+    assert_eq!(
+        EventMask::ALL
+            .difference(EventMask::SOC)
+            .difference(EventMask::SYSTEM)
+            .bits(),
+        0x3F
+    );
+    assert_eq!(EventMask::default(), EventMask::ALL);
+
+    // Bits from a newer protocol version are dropped
+    assert_eq!(EventMask::from_bits_truncate(0xFFFF_FFFF), EventMask::ALL);
+}
+
+#[test]
+fn test_event_subscription_roundtrip() {
+    let mut muted_handles = Vec::new();
+    muted_handles.push(0x000E).unwrap();
+    muted_handles.push(0x0012).unwrap();
+    let subscription = EventSubscription {
+        mask: EventMask::GATTS_WRITE,
+        muted_handles,
+    };
+
+    let mut encoded: Vec<u8, MAX_PAYLOAD_SIZE> = Vec::new();
+    subscription.encode(&mut encoded).unwrap();
+    assert_eq!(&encoded[..], &[0, 0, 0, 0x02, 2, 0x00, 0x0E, 0x00, 0x12]);
+    assert_eq!(EventSubscription::decode(&encoded), Ok(subscription));
+}
+
+#[test]
+fn test_event_subscription_rejects_bad_count() {
+    // Count past the end of the payload
+    assert_eq!(
+        EventSubscription::decode(&[0, 0, 0, 1, 1, 0x00]),
+        Err(ProtocolError::InvalidData)
+    );
+
+    // More handles than the filter table holds
+    let mut payload = [0u8; 5 + 2 * (MAX_CHAR_EVENT_FILTERS + 1)];
+    payload[4] = MAX_CHAR_EVENT_FILTERS as u8 + 1;
+    assert_eq!(EventSubscription::decode(&payload), Err(ProtocolError::InvalidData));
+}
+
+#[test]
+fn test_event_header_roundtrip() {
+    let header = EventHeader {
+        seq: 0x0102_0304,
+        timestamp_us: 0x0000_0001_0000_0002,
+    };
+
+    let mut payload: Vec<u8, MAX_PAYLOAD_SIZE> = Vec::new();
+    header.encode(&mut payload).unwrap();
+    assert_eq!(payload.len(), EVENT_HEADER_SIZE);
+    payload.extend_from_slice(&[0x12, 0x00, 0x01, 0x00, 0x13]).unwrap();
+
+    let (decoded, body) = EventHeader::decode(&payload).unwrap();
+    assert_eq!(decoded, header);
+    assert_eq!(body, &[0x12, 0x00, 0x01, 0x00, 0x13]);
+
+    assert_eq!(
+        EventHeader::decode(&payload[..EVENT_HEADER_SIZE - 1]),
+        Err(ProtocolError::InvalidData)
+    );
+}
+
+#[test]
+fn test_soc_event_ids() {
+    for id in 0..=u8::MAX {
+        assert_eq!(SocEvent::from_id(id).id(), id);
+    }
+    assert_eq!(SocEvent::from_id(1), SocEvent::PowerFailureWarning);
+    assert_eq!(SocEvent::from_id(42), SocEvent::Unknown(42));
+
+    let mut body: Vec<u8, 4> = Vec::new();
+    SocEvent::FlashOperationError.encode(&mut body).unwrap();
+    assert_eq!(&body[..], &[3]);
+    assert_eq!(SocEvent::decode(&body), Ok(SocEvent::FlashOperationError));
+    assert_eq!(SocEvent::decode(&[]), Err(ProtocolError::InvalidData));
+}
+
+#[test]
+fn test_system_event_roundtrip() {
+    let event = SystemEvent::PoolLow { allocated: 7, size: 8 };
+    let mut encoded: Vec<u8, 8> = Vec::new();
+    event.encode(&mut encoded).unwrap();
+    assert_eq!(&encoded[..], &[0x01, 7, 8]);
+    assert_eq!(SystemEvent::decode(&encoded), Ok(event));
+
+    // Events from a newer protocol version are reported, not rejected
+    assert_eq!(SystemEvent::decode(&[0x42, 1, 2]), Ok(SystemEvent::Unknown(0x42)));
+    assert_eq!(SystemEvent::decode(&[0x01, 7]), Err(ProtocolError::InvalidData));
+}
+
+#[test]
+fn test_boot_event_roundtrip() {
+    let event = SystemEvent::Boot {
+        reason: ResetReason::Panic,
+        resetreas: 0x0000_0004,
+    };
+    let mut encoded: Vec<u8, 8> = Vec::new();
+    event.encode(&mut encoded).unwrap();
+    assert_eq!(&encoded[..], &[0x02, 4, 0, 0, 0, 4]);
+    assert_eq!(SystemEvent::decode(&encoded), Ok(event));
+    assert!(ResetReason::Panic.is_fault());
+    assert!(!ResetReason::HostRequested.is_fault());
+
+    // Reasons from a newer protocol version are kept
+    assert_eq!(
+        SystemEvent::decode(&[0x02, 0x30, 0, 0, 0, 0]),
+        Ok(SystemEvent::Boot {
+            reason: ResetReason::Unknown(0x30),
+            resetreas: 0,
+        })
+    );
+    assert_eq!(SystemEvent::decode(&[0x02, 4, 0, 0]), Err(ProtocolError::InvalidData));
+}
@@ -0,0 +1,140 @@
+//! Host-run tests for fragmentation and reassembly
+
+use ble_modem_protocol::fragment::{FragmentHeader, Reassembler, MAX_FRAGMENTS, MAX_REASSEMBLED_SIZE};
+use ble_modem_protocol::{Frame, Packet, ProtocolError, RequestCode, ResponseCode, MAX_PAYLOAD_SIZE};
+
+const TIMEOUT_MS: u64 = 100;
+
+fn large_payload() -> Vec<u8> {
+    (0..512u16).map(|i| i as u8).collect()
+}
+
+fn request_frames(seq: Option<u16>) -> Vec<heapless::Vec<u8, MAX_PAYLOAD_SIZE>> {
+    Packet::new_request_for_sending(RequestCode::GattsHvx, &large_payload())
+        .unwrap()
+        .with_seq(seq)
+        .request_frames()
+        .unwrap()
+        .collect()
+}
+
+#[test]
+fn test_small_packet_is_not_fragmented() {
+    let packet = Packet::new_response(ResponseCode::Ack, &[1, 2, 3]).unwrap();
+    let frames: Vec<_> = packet.response_frames().unwrap().collect();
+
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0], packet.serialize().unwrap());
+}
+
+#[test]
+fn test_large_request_roundtrip() {
+    let frames = request_frames(Some(42));
+    assert!(frames.len() > 1 && frames.len() <= MAX_FRAGMENTS);
+
+    let mut reassembler = Reassembler::<MAX_REASSEMBLED_SIZE>::new(TIMEOUT_MS);
+    let mut packet = None;
+    for (i, wire) in frames.iter().enumerate() {
+        assert!(wire.len() <= MAX_PAYLOAD_SIZE);
+
+        let frame = Frame::parse(wire).unwrap();
+        assert_eq!(frame.seq, Some(42));
+        assert_eq!(frame.fragment.unwrap().index(), i as u8);
+        assert_eq!(Packet::new_request(wire).unwrap_err(), ProtocolError::Fragmented);
+
+        if let Some(complete) = reassembler.push(&frame, 0).unwrap() {
+            packet = Some(Packet::from_request_frame(&complete).unwrap());
+        }
+    }
+
+    let packet = packet.unwrap();
+    assert_eq!(packet.seq, Some(42));
+    assert_eq!(packet.request_code(), Some(RequestCode::GattsHvx));
+    assert_eq!(packet.payload.as_slice(), large_payload().as_slice());
+    assert!(!reassembler.is_in_progress());
+}
+
+#[test]
+fn test_large_response_roundtrip() {
+    let frames: Vec<_> = Packet::new_response(ResponseCode::Ack, &large_payload())
+        .unwrap()
+        .response_frames()
+        .unwrap()
+        .collect();
+
+    let mut reassembler = Reassembler::<MAX_REASSEMBLED_SIZE>::new(TIMEOUT_MS);
+    let mut complete = None;
+    for wire in &frames {
+        if let Some(frame) = reassembler.push(&Frame::parse(wire).unwrap(), 0).unwrap() {
+            complete = Some(Packet::from_response_frame(&frame).unwrap());
+        }
+    }
+
+    let packet = complete.unwrap();
+    assert_eq!(packet.seq, None);
+    assert_eq!(packet.response_code(), Some(ResponseCode::Ack));
+    assert_eq!(packet.payload.as_slice(), large_payload().as_slice());
+}
+
+#[test]
+fn test_out_of_order_fragment_is_rejected() {
+    let frames = request_frames(Some(1));
+    assert!(frames.len() >= 3);
+
+    let mut reassembler = Reassembler::<MAX_REASSEMBLED_SIZE>::new(TIMEOUT_MS);
+    assert_eq!(reassembler.push(&Frame::parse(&frames[0]).unwrap(), 0), Ok(None));
+    assert_eq!(
+        reassembler.push(&Frame::parse(&frames[2]).unwrap(), 0),
+        Err(ProtocolError::FragmentOutOfOrder)
+    );
+    assert!(!reassembler.is_in_progress());
+
+    // Continuation without a first fragment
+    assert_eq!(
+        reassembler.push(&Frame::parse(&frames[1]).unwrap(), 0),
+        Err(ProtocolError::FragmentOutOfOrder)
+    );
+}
+
+#[test]
+fn test_missing_fragment_times_out() {
+    let frames = request_frames(None);
+
+    let mut reassembler = Reassembler::<MAX_REASSEMBLED_SIZE>::new(TIMEOUT_MS);
+    assert_eq!(reassembler.push(&Frame::parse(&frames[0]).unwrap(), 0), Ok(None));
+    assert!(!reassembler.expire(TIMEOUT_MS));
+    assert!(reassembler.is_in_progress());
+
+    assert_eq!(
+        reassembler.push(&Frame::parse(&frames[1]).unwrap(), TIMEOUT_MS + 1),
+        Err(ProtocolError::ReassemblyTimeout)
+    );
+    assert!(!reassembler.is_in_progress());
+}
+
+#[test]
+fn test_new_first_fragment_restarts_reassembly() {
+    let stale = request_frames(Some(1));
+    let fresh = request_frames(Some(2));
+
+    let mut reassembler = Reassembler::<MAX_REASSEMBLED_SIZE>::new(TIMEOUT_MS);
+    reassembler.push(&Frame::parse(&stale[0]).unwrap(), 0).unwrap();
+
+    let mut packet = None;
+    for wire in &fresh {
+        if let Some(frame) = reassembler.push(&Frame::parse(wire).unwrap(), 0).unwrap() {
+            packet = Some(Packet::from_request_frame(&frame).unwrap());
+        }
+    }
+    assert_eq!(packet.unwrap().seq, Some(2));
+}
+
+#[test]
+fn test_oversized_packet_is_rejected() {
+    let frames = request_frames(None);
+    let frame = Frame::parse(&frames[0]).unwrap();
+    assert!(matches!(frame.fragment, Some(FragmentHeader::First { .. })));
+
+    let mut reassembler = Reassembler::<64>::new(TIMEOUT_MS);
+    assert_eq!(reassembler.push(&frame, 0), Err(ProtocolError::BufferFull));
+}
@@ -0,0 +1,40 @@
+//! Device identity encoding tests
+
+use ble_modem_protocol::capabilities::SoftDeviceVersion;
+use ble_modem_protocol::identity::DeviceIdentity;
+use ble_modem_protocol::{ProtocolError, MAX_PAYLOAD_SIZE};
+use heapless::Vec;
+
+fn identity() -> DeviceIdentity {
+    DeviceIdentity {
+        firmware_version: 0x0001,
+        device_id: 0x1122_3344_5566_7788,
+        addr_type: 1,
+        factory_address: [0x01, 0x02, 0x03, 0x04, 0x05, 0xC6],
+        softdevice: SoftDeviceVersion {
+            fwid: 0x0123,
+            ll_version: 12,
+            company_id: 0x0059,
+        },
+        crate_version: "0.1.0".try_into().unwrap(),
+        build_id: "c5e1013a".try_into().unwrap(),
+    }
+}
+
+#[test]
+fn test_identity_roundtrip() {
+    let mut encoded: Vec<u8, MAX_PAYLOAD_SIZE> = Vec::new();
+    identity().encode(&mut encoded).unwrap();
+
+    // Firmware version leads, so hosts reading only GET_INFO's version still work
+    assert_eq!(&encoded[..4], &[0, 0, 0, 1]);
+    assert_eq!(&encoded[4..12], &[0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88]);
+    assert_eq!(encoded.len(), 4 + 8 + 1 + 6 + 5 + 1 + 5 + 1 + 8);
+    assert_eq!(DeviceIdentity::decode(&encoded), Ok(identity()));
+}
+
+#[test]
+fn test_identity_rejects_legacy_response() {
+    // Firmware before device identity only sent the version
+    assert_eq!(DeviceIdentity::decode(&[0, 0, 0, 1]), Err(ProtocolError::InvalidData));
+}
@@ -0,0 +1,125 @@
+//! NAK encoding and duplicate window tests
+
+use ble_modem_protocol::link::{
+    DuplicateWindow, LinkStats, Nak, NakReason, CACHED_RESPONSE_SIZE, DUPLICATE_WINDOW_SIZE, LINK_STATS_SIZE, NAK_SIZE,
+};
+use ble_modem_protocol::{Packet, ProtocolError, RequestCode, ResponseCode};
+use heapless::Vec;
+
+fn request(seq: Option<u16>, payload: &[u8]) -> Packet {
+    Packet::new_request_for_sending(RequestCode::Echo, payload)
+        .unwrap()
+        .with_seq(seq)
+}
+
+#[test]
+fn test_nak_roundtrip() {
+    let nak = Nak {
+        reason: NakReason::Overflow,
+        seq: 0x1234,
+    };
+    let mut buffer: Vec<u8, 8> = Vec::new();
+    nak.encode(&mut buffer).unwrap();
+    assert_eq!(buffer.as_slice(), &[0x05, 0x12, 0x34]);
+    assert_eq!(buffer.len(), NAK_SIZE);
+
+    let decoded = Nak::decode(&buffer).unwrap();
+    assert_eq!(decoded, nak);
+    assert_eq!(decoded.request_seq(), Some(0x1234));
+
+    assert_eq!(Nak::decode(&[0x02, 0x00, 0x00]).unwrap().request_seq(), None);
+    assert_eq!(Nak::decode(&[0x7F, 0x00, 0x00]), Err(ProtocolError::InvalidData));
+    assert_eq!(ResponseCode::from_u16(0xAC52), Some(ResponseCode::Nak));
+}
+
+#[test]
+fn test_nak_reason_from_error() {
+    assert_eq!(NakReason::from_error(ProtocolError::InvalidCrc), NakReason::InvalidCrc);
+    assert_eq!(
+        NakReason::from_error(ProtocolError::InvalidLength),
+        NakReason::InvalidLength
+    );
+    assert_eq!(
+        NakReason::from_error(ProtocolError::ReassemblyTimeout),
+        NakReason::Fragment
+    );
+    assert_eq!(NakReason::from_error(ProtocolError::InvalidData), NakReason::Malformed);
+    assert!(NakReason::InvalidCrc.is_retransmittable());
+    assert!(!NakReason::Duplicate.is_retransmittable());
+}
+
+#[test]
+fn test_duplicate_window() {
+    let mut window = DuplicateWindow::new();
+
+    let first = request(Some(1), b"a");
+    assert!(!window.is_duplicate(&first));
+    window.remember(&first);
+    assert!(window.is_duplicate(&request(Some(1), b"a")));
+    // Same sequence number, different request: a restarted host, not a retransmission
+    assert!(!window.is_duplicate(&request(Some(1), b"b")));
+    // Legacy requests are never tracked
+    window.remember(&request(None, b"a"));
+    assert!(!window.is_duplicate(&request(None, b"a")));
+
+    window.clear();
+    assert!(!window.is_duplicate(&first));
+
+    // Old entries fall out of the window
+    window.remember(&first);
+    for seq in 2..(2 + DUPLICATE_WINDOW_SIZE as u16) {
+        window.remember(&request(Some(seq), b"a"));
+    }
+    assert!(!window.is_duplicate(&first));
+    assert!(window.is_duplicate(&request(Some(2 + DUPLICATE_WINDOW_SIZE as u16 - 1), b"a")));
+}
+
+#[test]
+fn test_duplicate_window_replays_response() {
+    let mut window = DuplicateWindow::new();
+    let first = request(Some(1), b"a");
+
+    // Still running: no response to replay yet
+    window.remember(&first);
+    assert_eq!(window.cached_response(&first), None);
+
+    assert!(window.record_response(1, b"response"));
+    assert_eq!(window.cached_response(&request(Some(1), b"a")), Some(&b"response"[..]));
+    assert_eq!(window.cached_response(&request(Some(1), b"b")), None);
+    assert!(!window.record_response(2, b"unknown"));
+
+    // A restarted host reused seq 1: the response belongs to the newer request
+    let reused = request(Some(1), b"b");
+    window.remember(&reused);
+    assert!(window.record_response(1, b"second"));
+    assert_eq!(window.cached_response(&reused), Some(&b"second"[..]));
+    assert_eq!(window.cached_response(&first), Some(&b"response"[..]));
+
+    // Too large to keep: still a duplicate, but only NAKed
+    let large = request(Some(3), b"c");
+    window.remember(&large);
+    assert!(!window.record_response(3, &[0; CACHED_RESPONSE_SIZE + 1]));
+    assert!(window.is_duplicate(&large));
+    assert_eq!(window.cached_response(&large), None);
+
+    window.clear();
+    assert_eq!(window.cached_response(&reused), None);
+}
+
+#[test]
+fn test_link_stats_roundtrip() {
+    let stats = LinkStats {
+        frames_received: 100,
+        frames_sent: 90,
+        invalid_crc: 3,
+        rx_overflows: 1,
+        tx_queue_high_water: 5,
+        ..Default::default()
+    };
+    let mut buffer: Vec<u8, 64> = Vec::new();
+    stats.encode(&mut buffer).unwrap();
+    assert_eq!(buffer.len(), LINK_STATS_SIZE);
+    assert_eq!(&buffer[..8], &[0, 0, 0, 100, 0, 0, 0, 90]);
+    assert_eq!(LinkStats::decode(&buffer).unwrap(), stats);
+    assert!(LinkStats::decode(&buffer[..LINK_STATS_SIZE - 1]).is_err());
+}
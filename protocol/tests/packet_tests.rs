@@ -0,0 +1,146 @@
+//! Host-run tests for packet framing
+
+use ble_modem_protocol::fragment::FragmentHeader;
+use ble_modem_protocol::{
+    seal_frame, Frame, Packet, ProtocolError, RequestCode, ResponseCode, FRAME_HEADROOM, MAX_PAYLOAD_SIZE,
+    SEQUENCE_FLAG,
+};
+
+#[test]
+fn test_request_roundtrip() {
+    let packet = Packet::new_request_for_sending(RequestCode::GattsHvx, &[1, 2, 3, 4]).unwrap();
+    let wire = packet.serialize_request().unwrap();
+
+    let parsed = Packet::new_request(&wire).unwrap();
+    assert_eq!(parsed.request_code(), Some(RequestCode::GattsHvx));
+    assert_eq!(parsed.payload.as_slice(), &[1, 2, 3, 4]);
+}
+
+#[test]
+fn test_response_roundtrip() {
+    for code in [
+        ResponseCode::Ack,
+        ResponseCode::Error,
+        ResponseCode::BleEvent,
+        ResponseCode::SocEvent,
+    ] {
+        let wire = Packet::new_response(code, b"payload").unwrap().serialize().unwrap();
+
+        let parsed = Packet::parse_response(&wire).unwrap();
+        assert_eq!(parsed.response_code(), Some(code));
+        assert_eq!(parsed.payload.as_slice(), b"payload");
+    }
+}
+
+#[test]
+fn test_response_rejects_bad_crc_and_length() {
+    let mut wire = Packet::new_response(ResponseCode::Ack, &[0xAA])
+        .unwrap()
+        .serialize()
+        .unwrap();
+
+    assert_eq!(
+        Packet::parse_response(&wire[..wire.len() - 1]).unwrap_err(),
+        ProtocolError::InvalidLength
+    );
+
+    let last = wire.len() - 1;
+    wire[last] ^= 0xFF;
+    assert_eq!(Packet::parse_response(&wire).unwrap_err(), ProtocolError::InvalidCrc);
+}
+
+#[test]
+fn test_sequenced_request_roundtrip() {
+    let packet = Packet::new_request_for_sending(RequestCode::Echo, &[9, 8, 7])
+        .unwrap()
+        .with_seq(Some(0x1234));
+    let wire = packet.serialize_request().unwrap();
+    assert_eq!(u16::from_be_bytes([wire[0], wire[1]]) & SEQUENCE_FLAG, SEQUENCE_FLAG);
+    assert_eq!(&wire[2..4], &[0x12, 0x34]);
+
+    let parsed = Packet::new_request(&wire).unwrap();
+    assert_eq!(parsed.seq, Some(0x1234));
+    assert_eq!(parsed.request_code(), Some(RequestCode::Echo));
+    assert_eq!(parsed.payload.as_slice(), &[9, 8, 7]);
+}
+
+#[test]
+fn test_sequenced_response_roundtrip() {
+    let wire = Packet::new_response(ResponseCode::Ack, b"ok")
+        .unwrap()
+        .with_seq(Some(7))
+        .serialize()
+        .unwrap();
+
+    let parsed = Packet::parse_response(&wire).unwrap();
+    assert_eq!(parsed.seq, Some(7));
+    assert_eq!(parsed.response_code(), Some(ResponseCode::Ack));
+    assert_eq!(parsed.payload.as_slice(), b"ok");
+}
+
+#[test]
+fn test_legacy_framing_has_no_sequence() {
+    let wire = Packet::new_request_for_sending(RequestCode::Echo, &[1])
+        .unwrap()
+        .serialize_request()
+        .unwrap();
+    assert_eq!(wire.len(), 7);
+    assert_eq!(Packet::new_request(&wire).unwrap().seq, None);
+}
+
+#[test]
+fn test_declared_length_trims_padding() {
+    let packet = Packet::new_response(ResponseCode::Ack, &[1, 2, 3])
+        .unwrap()
+        .with_seq(Some(7));
+    let wire = packet.serialize().unwrap();
+
+    let mut padded = wire.to_vec();
+    padded.resize(wire.len() + 16, 0);
+    assert_eq!(Frame::declared_length(&padded), Some(wire.len()));
+    assert!(Frame::parse(&padded[..wire.len()]).is_ok());
+
+    // Idle transactions carry no frame
+    assert_eq!(Frame::declared_length(&[0; 8]), None);
+    assert_eq!(Frame::declared_length(&[0x00]), None);
+}
+
+#[test]
+fn test_seal_frame_matches_serialized_frame() {
+    let payload = [0x10, 0x20, 0x30];
+    for seq in [None, Some(0x1234)] {
+        let expected = Packet::new_response(ResponseCode::Ack, &payload)
+            .unwrap()
+            .with_seq(seq)
+            .serialize()
+            .unwrap();
+
+        // Body written in place behind the headroom: response code, then payload
+        let mut buffer = [0u8; FRAME_HEADROOM + MAX_PAYLOAD_SIZE];
+        buffer[FRAME_HEADROOM..FRAME_HEADROOM + 2].copy_from_slice(&ResponseCode::Ack.to_u16().to_be_bytes());
+        buffer[FRAME_HEADROOM + 2..FRAME_HEADROOM + 5].copy_from_slice(&payload);
+
+        let range = seal_frame(&mut buffer, seq, None, 5).unwrap();
+        assert_eq!(&buffer[range], &expected[..]);
+    }
+}
+
+#[test]
+fn test_seal_fragment_frame() {
+    let mut buffer = [0u8; FRAME_HEADROOM + MAX_PAYLOAD_SIZE];
+    buffer[FRAME_HEADROOM..FRAME_HEADROOM + 4].copy_from_slice(&[1, 2, 3, 4]);
+
+    let range = seal_frame(&mut buffer, Some(9), Some(FragmentHeader::Last { index: 2 }), 4).unwrap();
+    assert_eq!(range.start, FRAME_HEADROOM - 6);
+
+    let frame = Frame::parse(&buffer[range]).unwrap();
+    assert_eq!(frame.seq, Some(9));
+    assert_eq!(frame.fragment, Some(FragmentHeader::Last { index: 2 }));
+    assert_eq!(frame.body, &[1, 2, 3, 4]);
+
+    // Bodies that cannot fit in one frame are refused
+    assert_eq!(
+        seal_frame(&mut buffer, None, None, MAX_PAYLOAD_SIZE),
+        Err(ProtocolError::BufferFull)
+    );
+}
@@ -0,0 +1,21 @@
+//! TX pool statistics encoding tests
+
+use ble_modem_protocol::pool::{PoolStats, POOL_STATS_SIZE};
+use heapless::Vec;
+
+#[test]
+fn test_pool_stats_roundtrip() {
+    let stats = PoolStats {
+        size: 8,
+        allocated: 3,
+        peak: 7,
+        alloc_failures: 2,
+    };
+    assert_eq!(stats.available(), 5);
+
+    let mut encoded: Vec<u8, POOL_STATS_SIZE> = Vec::new();
+    stats.encode(&mut encoded).unwrap();
+    assert_eq!(&encoded[..], &[8, 3, 7, 0, 0, 0, 2]);
+    assert_eq!(PoolStats::decode(&encoded), Ok(stats));
+    assert!(PoolStats::decode(&encoded[..6]).is_err());
+}
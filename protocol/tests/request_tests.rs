@@ -0,0 +1,222 @@
+//! Host-run tests for the protocol table and typed request codecs
+
+use ble_modem_protocol::codec::{AdvData, AdvParams, Request, Uuid};
+use ble_modem_protocol::power::{RebootMode, WakePolarity, FACTORY_RESET_TOKEN};
+use ble_modem_protocol::requests::*;
+use ble_modem_protocol::{ProtocolError, MAX_MESSAGE_SIZE};
+use heapless::Vec;
+
+fn encode<'a, R: Request<'a>>(request: &R) -> Vec<u8, MAX_MESSAGE_SIZE> {
+    let mut payload = Vec::new();
+    request.encode(&mut payload).unwrap();
+    payload
+}
+
+#[test]
+fn test_request_code_table_roundtrip() {
+    for &code in RequestCode::ALL {
+        assert_eq!(RequestCode::from_u16(code as u16), Some(code));
+    }
+    assert_eq!(RequestCode::from_u16(0x0007), Some(RequestCode::SetEventMask));
+    // Retired callback registration commands
+    assert_eq!(RequestCode::from_u16(0x0004), None);
+    assert_eq!(RequestCode::from_u16(0x0005), None);
+    assert_eq!(RequestCode::from_u16(0x00F0), Some(RequestCode::Reboot));
+    assert_eq!(RequestCode::from_u16(0xFFFF), None);
+}
+
+#[test]
+fn test_shutdown_wake_fields_are_optional() {
+    let request = ShutdownRequest::decode(&[]).unwrap();
+    assert_eq!(request.wake_pin, None);
+    assert_eq!(request.wake_polarity, None);
+
+    let request = ShutdownRequest {
+        wake_pin: Some(7),
+        wake_polarity: Some(WakePolarity::High as u8),
+    };
+    assert_eq!(&encode(&request)[..], &[7, 1]);
+    assert_eq!(ShutdownRequest::decode(&[7, 1]), Ok(request));
+    assert_eq!(WakePolarity::from_u8(1), Some(WakePolarity::High));
+    assert_eq!(WakePolarity::from_u8(2), None);
+}
+
+#[test]
+fn test_reboot_mode_is_optional() {
+    assert_eq!(RebootRequest::decode(&[]), Ok(RebootRequest { mode: None }));
+    let request = RebootRequest {
+        mode: Some(RebootMode::Bootloader as u8),
+    };
+    assert_eq!(&encode(&request)[..], &[1]);
+    assert_eq!(RebootMode::from_u8(1), Some(RebootMode::Bootloader));
+    assert_eq!(RebootMode::from_u8(2), None);
+}
+
+#[test]
+fn test_factory_reset_needs_token() {
+    assert_eq!(RequestCode::from_u16(0x000F), Some(RequestCode::FactoryReset));
+    assert_eq!(FactoryResetRequest::decode(&[]), Err(ProtocolError::InvalidLength));
+
+    let request = FactoryResetRequest {
+        token: FACTORY_RESET_TOKEN,
+    };
+    assert_eq!(&encode(&request)[..], b"FRST");
+    assert_eq!(FactoryResetRequest::decode(b"FRST"), Ok(request));
+}
+
+#[test]
+fn test_min_payload_len() {
+    assert_eq!(RequestCode::GetInfo.min_payload_len(), 0);
+    assert_eq!(RequestCode::GapSetAddr.min_payload_len(), 7);
+    assert_eq!(RequestCode::GapConnParamUpdate.min_payload_len(), 10);
+    assert_eq!(RequestCode::GattsServiceAdd.min_payload_len(), 4);
+    assert_eq!(RequestCode::GattsHvx.min_payload_len(), 7);
+    assert_eq!(RequestCode::GapConnect.min_payload_len(), 0);
+    assert_eq!(GapSetAddrRequest::MIN_LEN, 7);
+
+    assert_eq!(
+        GapSetAddrRequest::decode(&[0, 1, 2, 3, 4, 5]),
+        Err(ProtocolError::InvalidLength)
+    );
+}
+
+#[test]
+fn test_fixed_fields_roundtrip() {
+    let request = GapSetAddrRequest {
+        addr_type: 1,
+        addr: [1, 2, 3, 4, 5, 6],
+    };
+    let payload = encode(&request);
+    assert_eq!(&payload[..], &[1, 1, 2, 3, 4, 5, 6]);
+    assert_eq!(GapSetAddrRequest::decode(&payload), Ok(request));
+}
+
+#[test]
+fn test_little_endian_fields() {
+    let request = GapDisconnectRequest {
+        conn_handle: 0x0102,
+        reason: 0x13,
+    };
+    let payload = encode(&request);
+    assert_eq!(&payload[..], &[0x02, 0x01, 0x13]);
+    assert_eq!(GapDisconnectRequest::decode(&payload), Ok(request));
+
+    let request = GapSetTxPowerRequest {
+        role: 0x03,
+        conn_handle: 1,
+        tx_power: -8,
+    };
+    assert_eq!(&encode(&request)[..], &[0x03, 0x01, 0x00, 0xF8]);
+}
+
+#[test]
+fn test_length_prefixed_and_optional_fields() {
+    let request = GattsCharacteristicAddRequest {
+        service_handle: 0x000C,
+        uuid: Uuid::VendorSpecific {
+            base_id: 0,
+            offset: 0x1234,
+        },
+        properties: 0x12,
+        max_length: 20,
+        initial_value: &[0xAA, 0xBB],
+        permissions: Some(0x01),
+    };
+    let payload = encode(&request);
+    assert_eq!(
+        &payload[..],
+        &[0x00, 0x0C, 2, 0, 0x34, 0x12, 0x12, 0x00, 20, 2, 0xAA, 0xBB, 0x01]
+    );
+    assert_eq!(GattsCharacteristicAddRequest::decode(&payload), Ok(request));
+
+    // Permissions may be omitted
+    let decoded = GattsCharacteristicAddRequest::decode(&payload[..payload.len() - 1]).unwrap();
+    assert_eq!(decoded.permissions, None);
+
+    // Length prefix past the end of the payload
+    let request = GattsHvxRequest {
+        conn_handle: 1,
+        char_handle: 2,
+        hvx_type: 1,
+        data: &[1, 2, 3],
+    };
+    let payload = encode(&request);
+    assert_eq!(GattsHvxRequest::decode(&payload), Ok(request));
+    assert_eq!(
+        GattsHvxRequest::decode(&payload[..payload.len() - 1]),
+        Err(ProtocolError::InvalidData)
+    );
+}
+
+#[test]
+fn test_adv_configure_roundtrip() {
+    let request = GapAdvSetConfigureRequest {
+        adv_handle: 1,
+        data_present: true,
+        data: Some(AdvData {
+            adv_data: &[0x02, 0x01, 0x06],
+            scan_data: &[],
+        }),
+        params: None,
+    };
+    let payload = encode(&request);
+    assert_eq!(&payload[..], &[1, 1, 0, 3, 0, 0, 0x02, 0x01, 0x06]);
+    assert_eq!(GapAdvSetConfigureRequest::decode(&payload), Ok(request));
+
+    let decoded = GapAdvSetConfigureRequest::decode(&[1, 0]).unwrap();
+    assert!(!decoded.data_present);
+    assert_eq!(decoded.data, None);
+    assert_eq!(decoded.params, None);
+}
+
+#[test]
+fn test_adv_configure_params() {
+    let params = AdvParams {
+        interval: 0x00A0,
+        timeout: 3000,
+        filter_policy: 0,
+    };
+    let request = GapAdvSetConfigureRequest {
+        adv_handle: 0,
+        data_present: false,
+        data: Some(AdvData {
+            adv_data: &[],
+            scan_data: &[],
+        }),
+        params: Some(params),
+    };
+    let payload = encode(&request);
+    assert_eq!(&payload[..], &[0, 0, 0, 0, 0, 0, 0x00, 0xA0, 0x0B, 0xB8, 0]);
+    assert_eq!(GapAdvSetConfigureRequest::decode(&payload), Ok(request));
+    assert_eq!(
+        GapAdvSetConfigureRequest::decode(&payload[..payload.len() - 1]),
+        Err(ProtocolError::InvalidData)
+    );
+
+    assert!(params.is_valid());
+    assert!(!AdvParams {
+        interval: 0x001F,
+        ..params
+    }
+    .is_valid());
+    assert!(!AdvParams {
+        interval: 0x4001,
+        ..params
+    }
+    .is_valid());
+    assert!(!AdvParams {
+        filter_policy: 4,
+        ..params
+    }
+    .is_valid());
+}
+
+#[test]
+fn test_uuid_rejects_unknown_type() {
+    assert_eq!(
+        GattsServiceAddRequest::decode(&[7, 0, 0, 1]),
+        Err(ProtocolError::InvalidData)
+    );
+    let request = GattsServiceAddRequest::decode(&[0, 0x0F, 0x18, 1]).unwrap();
+    assert_eq!(request.uuid, Uuid::Uuid16(0x180F));
+}
@@ -3,8 +3,14 @@
 //! Bridges between protocol GAP commands and nrf-softdevice high-level APIs.
 //! Provides coordinated advertising management that can be controlled via
 //! individual commands while leveraging the robust high-level abstractions.
+//!
+//! The advertisement is built from the data and parameters set with
+//! GAP_ADV_SET_CONFIGURE. A command received while advertising stops the
+//! running advertisement; it is restarted with the new configuration if
+//! advertising is still requested.
 
 use defmt::{debug, info};
+use embassy_futures::select::{select, Either};
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
 use embassy_sync::channel::Channel;
 use embassy_sync::mutex::Mutex;
@@ -17,6 +23,7 @@ use nrf_softdevice::Softdevice;
 use crate::ble::connection;
 use crate::ble::gap_state::{self, AdvState, MAX_ADV_DATA_LEN};
 use crate::ble::services::Server;
+use crate::core::protocol::codec::AdvParams;
 
 /// Maximum advertising data length for static buffers
 const MAX_COMBINED_ADV_DATA: usize = MAX_ADV_DATA_LEN * 2; // adv + scan response
@@ -26,7 +33,11 @@ const MAX_COMBINED_ADV_DATA: usize = MAX_ADV_DATA_LEN * 2; // adv + scan respons
 pub enum AdvCommand {
     Start { handle: u8, conn_cfg_tag: u8 },
     Stop { handle: u8 },
-    Configure { handle: u8, data_present: bool },
+    Configure {
+        handle: u8,
+        data_present: bool,
+        params: Option<AdvParams>,
+    },
 }
 
 /// Advertising controller state
@@ -39,6 +50,8 @@ pub struct AdvController {
     adv_data_len: usize,
     /// Whether advertising is currently requested
     advertising_requested: bool,
+    /// Set before System OFF; advertising is never restarted
+    halted: bool,
     /// Current advertising handle
     handle: u8,
 }
@@ -61,6 +74,7 @@ impl AdvController {
             combined_data: Vec::new(),
             adv_data_len: 0,
             advertising_requested: false,
+            halted: false,
             handle: 0,
         }
     }
@@ -97,6 +111,10 @@ impl AdvController {
 
     /// Request advertising start
     pub fn start_advertising(&mut self, handle: u8, _conn_cfg_tag: u8) {
+        if self.halted {
+            debug!("Advertising halted, ignoring start for handle {}", handle);
+            return;
+        }
         self.advertising_requested = true;
         self.handle = handle;
         debug!("Advertising start requested for handle {}", handle);
@@ -124,6 +142,18 @@ impl AdvController {
     pub fn update_config(&mut self, config: PeripheralConfig) {
         self.config = config;
     }
+
+    /// Apply host-supplied interval, timeout and filter policy
+    pub fn set_params(&mut self, params: AdvParams) {
+        self.config.interval = params.interval as u32;
+        self.config.timeout = (params.timeout != 0).then_some(params.timeout);
+        self.config.filter_policy = match params.filter_policy {
+            0 => FilterPolicy::Any,
+            1 => FilterPolicy::ScanRequests,
+            2 => FilterPolicy::ConnectionRequests,
+            _ => FilterPolicy::All,
+        };
+    }
 }
 
 /// Global advertising controller instance
@@ -137,6 +167,27 @@ pub async fn controller() -> embassy_sync::mutex::MutexGuard<'static, CriticalSe
     ADV_CONTROLLER.lock().await
 }
 
+/// Stop advertising now and keep it stopped (before System OFF)
+///
+/// Advertising runs inside `advertise_connectable`, so the set is stopped in the
+/// SoftDevice directly rather than through the command channel.
+pub async fn halt() {
+    {
+        let mut controller = ADV_CONTROLLER.lock().await;
+        controller.halted = true;
+        controller.advertising_requested = false;
+    }
+
+    // S140 has a single advertising set, configured as handle 0
+    let ret = unsafe { nrf_softdevice::raw::sd_ble_gap_adv_stop(0) };
+    if ret == nrf_softdevice::raw::NRF_SUCCESS {
+        info!("Advertising halted");
+    } else {
+        // NRF_ERROR_INVALID_STATE: not advertising
+        debug!("Advertising not running ({})", ret);
+    }
+}
+
 /// Send advertising command (non-blocking)
 pub fn send_command(cmd: AdvCommand) -> Result<(), AdvCommand> {
     ADV_COMMAND_CHANNEL.try_send(cmd).map_err(|e| match e {
@@ -144,74 +195,83 @@ pub fn send_command(cmd: AdvCommand) -> Result<(), AdvCommand> {
     })
 }
 
+/// Advertising data used until the host configures its own
+static DEFAULT_ADV_DATA: LegacyAdvertisementPayload = LegacyAdvertisementBuilder::new()
+    .flags(&[Flag::GeneralDiscovery, Flag::LE_Only])
+    .full_name("BLE_Modem")
+    .build();
+
+/// Apply an advertising command to the controller and the GAP state
+async fn apply_command(cmd: AdvCommand) {
+    info!("Advertising task: Received command");
+    let mut controller = ADV_CONTROLLER.lock().await;
+
+    match cmd {
+        AdvCommand::Start { handle, conn_cfg_tag } => {
+            controller.start_advertising(handle, conn_cfg_tag);
+
+            // Update gap state
+            let mut gap_state = gap_state::gap_state().lock().await;
+            gap_state.set_adv_state(AdvState::Starting);
+            gap_state.adv_handle = handle;
+        }
+        AdvCommand::Stop { handle } => {
+            controller.stop_advertising(handle);
+
+            // Update gap state
+            let mut gap_state = gap_state::gap_state().lock().await;
+            gap_state.set_adv_state(AdvState::Stopping);
+        }
+        AdvCommand::Configure {
+            handle,
+            data_present,
+            params,
+        } => {
+            if data_present {
+                // Get advertising data from gap state
+                let gap_state = gap_state::gap_state().lock().await;
+                let adv_data = gap_state.adv_data();
+                let scan_data = gap_state.scan_response();
+
+                if controller.configure_data(adv_data, scan_data).is_ok() {
+                    debug!("Advertising data configured for handle {}", handle);
+                }
+            }
+            if let Some(params) = params {
+                controller.set_params(params);
+                debug!("Advertising parameters configured for handle {}: {:?}", handle, params);
+            }
+            controller.handle = handle;
+        }
+    }
+}
+
 /// Enhanced BLE advertising task that coordinates with protocol commands
 #[embassy_executor::task]
 pub async fn advertising_task(sd: &'static Softdevice, bt_server: Server) {
     info!("Starting coordinated advertising task...");
 
-    // Default advertising data - can be overridden by ADV_CONFIGURE commands
-    // Use LegacyAdvertisementBuilder like the working test_connection.rs
-    static ADV_DATA: LegacyAdvertisementPayload = LegacyAdvertisementBuilder::new()
-        .flags(&[Flag::GeneralDiscovery, Flag::LE_Only])
-        .full_name("BLE_Modem")
-        .build();
-
-    static SCAN_DATA: LegacyAdvertisementPayload = LegacyAdvertisementBuilder::new().build();
-
     loop {
-        // Small yield to prevent tight loop spam
-        embassy_futures::yield_now().await;
-        
-        // Check for advertising commands
-        if let Ok(cmd) = ADV_COMMAND_CHANNEL.try_receive() {
-            info!("Advertising task: Received command");
-            let mut controller = ADV_CONTROLLER.lock().await;
-
-            match cmd {
-                AdvCommand::Start { handle, conn_cfg_tag } => {
-                    controller.start_advertising(handle, conn_cfg_tag);
-
-                    // Update gap state
-                    let mut gap_state = gap_state::gap_state().lock().await;
-                    gap_state.set_adv_state(AdvState::Starting);
-                    gap_state.adv_handle = handle;
-                }
-                AdvCommand::Stop { handle } => {
-                    controller.stop_advertising(handle);
-
-                    // Update gap state
-                    let mut gap_state = gap_state::gap_state().lock().await;
-                    gap_state.set_adv_state(AdvState::Stopping);
-                }
-                AdvCommand::Configure { handle, data_present } => {
-                    if data_present {
-                        // Get advertising data from gap state
-                        let gap_state = gap_state::gap_state().lock().await;
-                        let adv_data = gap_state.adv_data();
-                        let scan_data = gap_state.scan_response();
-
-                        if controller.configure_data(adv_data, scan_data).is_ok() {
-                            debug!("Advertising data configured for handle {}", handle);
-                        }
-                    }
-                    controller.handle = handle;
-                }
-            }
+        // Apply commands queued while advertising was stopped or connected
+        while let Ok(cmd) = ADV_COMMAND_CHANNEL.try_receive() {
+            apply_command(cmd).await;
         }
 
         // Check if advertising is requested
         let should_advertise = {
             let controller = ADV_CONTROLLER.lock().await;
-            let requested = controller.is_advertising_requested();
-            // debug!("Advertising task: should_advertise = {}", requested);
-            requested
+            controller.is_advertising_requested()
         };
 
         if should_advertise {
             info!("Advertising task: Starting advertising...");
-            let config = {
+            let (configured_adv_data, scan_data, config) = {
                 let controller = ADV_CONTROLLER.lock().await;
-                *controller.config()
+                (
+                    Vec::<u8, MAX_ADV_DATA_LEN>::from_slice(controller.adv_data()).unwrap_or_default(),
+                    Vec::<u8, MAX_ADV_DATA_LEN>::from_slice(controller.scan_data()).unwrap_or_default(),
+                    *controller.config(),
+                )
             };
 
             // Update gap state to active
@@ -220,16 +280,26 @@ pub async fn advertising_task(sd: &'static Softdevice, bt_server: Server) {
                 gap_state.set_adv_state(AdvState::Active);
             }
 
-            // Create advertising configuration using static payloads (like working test)
+            let adv_data: &[u8] = if configured_adv_data.is_empty() {
+                &DEFAULT_ADV_DATA
+            } else {
+                &configured_adv_data
+            };
             let advertisement = ConnectableAdvertisement::ScannableUndirected {
-                adv_data: &ADV_DATA,
-                scan_data: &SCAN_DATA,
+                adv_data,
+                scan_data: &scan_data,
             };
 
-            // Start advertising and wait for connection
-            debug!("Starting advertising...");
-            match peripheral::advertise_connectable(sd, advertisement, &config).await {
-                Ok(conn) => {
+            // Start advertising and wait for connection or the next command
+            debug!(
+                "Starting advertising: {} bytes adv, {} bytes scan, interval {}",
+                adv_data.len(),
+                scan_data.len(),
+                config.interval
+            );
+            let advertise = peripheral::advertise_connectable(sd, advertisement, &config);
+            match select(advertise, ADV_COMMAND_CHANNEL.receive()).await {
+                Either::First(Ok(conn)) => {
                     debug!("BLE connection established!");
                     debug!("Connection handle: {:?}", conn.handle());
 
@@ -330,11 +400,11 @@ pub async fn advertising_task(sd: &'static Softdevice, bt_server: Server) {
                     // Auto-restart advertising after disconnection
                     {
                         let mut controller = ADV_CONTROLLER.lock().await;
-                        controller.advertising_requested = true;
+                        controller.advertising_requested = !controller.halted;
                         info!("Auto-restarting advertising after disconnection");
                     }
                 }
-                Err(e) => {
+                Either::First(Err(e)) => {
                     debug!("Advertising failed: {:?}", defmt::Debug2Format(&e));
 
                     // Update gap state to stopped on error
@@ -350,6 +420,12 @@ pub async fn advertising_task(sd: &'static Softdevice, bt_server: Server) {
                     // Timer::after(Duration::from_secs(1)).await;
                     embassy_futures::yield_now().await;
                 }
+                Either::Second(cmd) => {
+                    // Dropping the advertising future stopped advertising; it is
+                    // restarted with the new configuration if still requested
+                    debug!("Advertising interrupted by a command");
+                    apply_command(cmd).await;
+                }
             }
         } else {
             // Not advertising - update gap state if needed
@@ -362,10 +438,9 @@ pub async fn advertising_task(sd: &'static Softdevice, bt_server: Server) {
                 }
             }
 
-            // Brief delay when not advertising - use longer delay to reduce spam
-            for _ in 0..10000 {
-                embassy_futures::yield_now().await;
-            }
+            // Wait for the next advertising command
+            let cmd = ADV_COMMAND_CHANNEL.receive().await;
+            apply_command(cmd).await;
         }
     }
 }
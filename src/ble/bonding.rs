@@ -140,6 +140,12 @@ impl BondingStorage {
         Ok(())
     }
 
+    fn clear(&mut self) {
+        info!("BONDING: Clearing {} bonded devices", self.bonded_devices.len());
+        self.bonded_devices.clear();
+        self.next_bond_id = 1;
+    }
+
     fn device_count(&self) -> usize {
         let count = self.bonded_devices.len();
         debug!(
@@ -205,6 +211,12 @@ pub async fn remove_bonded_device(conn_handle: u16) -> Result<(), BondingError>
     storage.remove_bonded_device(conn_handle)
 }
 
+/// Remove every bonded device (FACTORY_RESET)
+pub async fn clear_bonded_devices() {
+    let mut storage = get_bonding_storage().lock().await;
+    storage.clear()
+}
+
 /// Get the number of bonded devices
 pub async fn bonded_device_count() -> usize {
     let storage = get_bonding_storage().lock().await;
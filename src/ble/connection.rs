@@ -13,6 +13,9 @@ use heapless::index_map::FnvIndexMap;
 /// Maximum number of simultaneous connections
 pub const MAX_CONNECTIONS: usize = 2;
 
+/// Largest ATT MTU configured in the SoftDevice
+pub const MAX_ATT_MTU: u16 = 128;
+
 /// Connection information
 #[derive(Format, Clone)]
 pub struct ConnectionInfo {
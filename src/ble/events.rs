@@ -6,91 +6,116 @@
 //! Based on nrf-softdevice patterns, events are handled through:
 //! - Connection events from peripheral::advertise_connectable()
 //! - GATT server events from gatt_server::run()
+//! - SoC events from the Softdevice::run_with_callback() callback, queued and
+//!   forwarded as `SocEvent` frames by soc_event_task
+//! - Modem system events (boot with the reset reason, TX pool running low),
+//!   forwarded as `SystemEvent` frames by system_event_task
 
-use defmt::debug;
-use heapless::Vec;
-use nrf_softdevice::ble::Connection;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use defmt::{debug, info, warn};
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
 use embassy_sync::mutex::Mutex;
+use embassy_time::{Duration, Instant, Timer};
+use heapless::Vec;
+use nrf_softdevice::ble::Connection;
 
-use crate::core::memory::TxPacket;
-use crate::core::protocol::{Packet, ResponseCode, MAX_PAYLOAD_SIZE};
-use crate::core::transport;
+use crate::core::memory::{self, TxPacketBuilder, TX_POOL_SIZE};
+use crate::core::power;
+use crate::core::protocol::events::{
+    EventHeader, EventMask, EventSubscription, SocEvent, SystemEvent, EVENT_HEADER_SIZE, MAX_CHAR_EVENT_FILTERS,
+};
+use crate::core::protocol::{ResponseCode, MAX_PAYLOAD_SIZE, UNSOLICITED_SEQ};
+use crate::core::transport::{self, LinkStatus, Transport};
 
 /// Event serialization buffer
 type EventBuffer = Vec<u8, MAX_PAYLOAD_SIZE>;
 
-/// Maximum number of registered event callbacks
-const MAX_EVENT_CALLBACKS: usize = 4;
-
-/// Event callback function type
-/// Parameters: (event_data: &[u8], context: u32)
-pub type EventCallbackFn = fn(&[u8], u32);
-
-/// Event callback registration entry
-#[derive(Clone, Copy)]
-pub struct CallbackEntry {
-    pub callback: EventCallbackFn,
-    pub context: u32,
-    pub active: bool,
-}
-
-/// Event callback registry
-pub struct CallbackRegistry {
-    callbacks: Vec<CallbackEntry, MAX_EVENT_CALLBACKS>,
+/// Host event subscription: enabled categories and muted characteristics
+pub struct EventFilter {
+    mask: EventMask,
+    muted_handles: Vec<u16, MAX_CHAR_EVENT_FILTERS>,
 }
 
-impl CallbackRegistry {
+impl EventFilter {
+    /// Forward every event (state after reset)
     pub const fn new() -> Self {
         Self {
-            callbacks: Vec::new(),
+            mask: EventMask::ALL,
+            muted_handles: Vec::new(),
         }
     }
 
-    /// Register a new event callback
-    pub fn register_callback(&mut self, callback: EventCallbackFn, context: u32) -> Result<(), ()> {
-        // Check if we have space for a new callback
-        if self.callbacks.len() >= MAX_EVENT_CALLBACKS {
-            // Try to find an inactive slot
-            for entry in self.callbacks.iter_mut() {
-                if !entry.active {
-                    entry.callback = callback;
-                    entry.context = context;
-                    entry.active = true;
-                    return Ok(());
-                }
-            }
-            return Err(()); // No space available
-        }
+    /// Check if an event category is enabled
+    pub fn category_enabled(&self, category: EventMask) -> bool {
+        self.mask.contains(category)
+    }
+
+    /// Check if an event passes the category mask and characteristic filters
+    pub fn allows(&self, event: &BleModemEvent) -> bool {
+        self.category_enabled(event.category())
+            && !event
+                .char_handle()
+                .is_some_and(|handle| self.muted_handles.contains(&handle))
+    }
 
-        // Add new callback
-        self.callbacks.push(CallbackEntry {
-            callback,
-            context,
-            active: true,
-        }).map_err(|_| ())?;
+    /// Replace the category mask
+    pub fn set_mask(&mut self, mask: EventMask) {
+        self.mask = mask;
+    }
 
+    /// Mute (`enabled == false`) or unmute the events of one characteristic
+    ///
+    /// Returns `Err(())` if the filter table is full.
+    pub fn set_char_filter(&mut self, char_handle: u16, enabled: bool) -> Result<(), ()> {
+        let position = self.muted_handles.iter().position(|&handle| handle == char_handle);
+        match (enabled, position) {
+            (true, Some(index)) => {
+                self.muted_handles.swap_remove(index);
+            }
+            (false, None) => self.muted_handles.push(char_handle).map_err(|_| ())?,
+            _ => {}
+        }
         Ok(())
     }
 
-    /// Clear all event callbacks
-    pub fn clear_callbacks(&mut self) {
-        self.callbacks.clear();
+    /// Unmute every characteristic
+    pub fn clear_char_filters(&mut self) {
+        self.muted_handles.clear();
     }
 
-    /// Dispatch event to all registered callbacks
-    pub fn dispatch_event(&self, event_data: &[u8]) {
-        for entry in self.callbacks.iter() {
-            if entry.active {
-                (entry.callback)(event_data, entry.context);
-            }
+    /// Current subscription as reported to the host
+    pub fn subscription(&self) -> EventSubscription {
+        EventSubscription {
+            mask: self.mask,
+            muted_handles: self.muted_handles.clone(),
         }
     }
 }
 
-/// Global callback registry
-static CALLBACK_REGISTRY: Mutex<CriticalSectionRawMutex, CallbackRegistry> = 
-    Mutex::new(CallbackRegistry::new());
+impl Default for EventFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global event filter, set by the host
+static EVENT_FILTER: Mutex<CriticalSectionRawMutex, EventFilter> = Mutex::new(EventFilter::new());
+
+/// Sequence number of the next event sent to the host
+static EVENT_SEQ: AtomicU32 = AtomicU32::new(0);
+
+/// Take the next event sequence number
+///
+/// Numbers are only taken by events that pass the host's filter, so a gap on
+/// the host side means an event was lost (link down or TX pool exhausted).
+fn next_event_seq() -> u32 {
+    EVENT_SEQ.fetch_add(1, Ordering::Relaxed)
+}
+
+/// SoC events waiting to be forwarded, with their capture timestamp (µs)
+static SOC_EVENTS: Channel<CriticalSectionRawMutex, (u64, SocEvent), 8> = Channel::new();
 
 /// BLE event types we forward to the host
 #[derive(Debug)]
@@ -127,6 +152,27 @@ pub enum BleModemEvent {
 }
 
 impl BleModemEvent {
+    /// Subscription category of the event
+    pub fn category(&self) -> EventMask {
+        match self {
+            BleModemEvent::Connected { .. } | BleModemEvent::Disconnected { .. } => EventMask::GAP_CONNECTION,
+            BleModemEvent::GattsWrite { .. } => EventMask::GATTS_WRITE,
+            BleModemEvent::GattsRead { .. } => EventMask::GATTS_READ,
+            BleModemEvent::MtuExchange { .. } => EventMask::MTU,
+            BleModemEvent::CccdWrite { .. } => EventMask::CCCD,
+        }
+    }
+
+    /// Characteristic the event refers to, if any
+    pub fn char_handle(&self) -> Option<u16> {
+        match self {
+            BleModemEvent::GattsWrite { char_handle, .. }
+            | BleModemEvent::GattsRead { char_handle, .. }
+            | BleModemEvent::CccdWrite { char_handle, .. } => Some(*char_handle),
+            _ => None,
+        }
+    }
+
     /// Serialize event to wire format for transmission to host
     pub fn serialize(&self) -> Result<EventBuffer, ()> {
         let mut buffer = EventBuffer::new();
@@ -209,30 +255,167 @@ impl BleModemEvent {
     }
 }
 
-/// Forward a BLE event to the host via SPI
+/// Forward a BLE event to the host over the host link (SPI or UART)
 pub async fn forward_event_to_host(event: BleModemEvent) -> Result<(), ()> {
-    // Serialize the event
-    let event_data = event.serialize()?;
+    forward_event(&transport::HOST_LINK, event).await
+}
 
-    // Dispatch to registered callbacks first
-    CALLBACK_REGISTRY.lock().await.dispatch_event(&event_data);
+/// Forward a BLE event to the host over `link`
+///
+/// Events the host has not subscribed to are silently discarded.
+pub async fn forward_event<T: Transport>(link: &T, event: BleModemEvent) -> Result<(), ()> {
+    let timestamp_us = Instant::now().as_micros();
 
-    // Create response packet with BLE event code
-    let packet = Packet::new_response(ResponseCode::BleEvent, &event_data).map_err(|_| ())?;
+    if !EVENT_FILTER.lock().await.allows(&event) {
+        debug!("Event filtered by host subscription: {:?}", event.category());
+        return Ok(());
+    }
 
-    // Serialize packet for transmission
-    let serialized = packet.serialize().map_err(|_| ())?;
+    send_event_frame(link, ResponseCode::BleEvent, timestamp_us, &event.serialize()?).await
+}
 
-    // Create TX packet
-    let tx_packet = TxPacket::new(&serialized).map_err(|_| ())?;
+/// Forward a SoC event to the host over `link`, unless the host masked SoC events
+pub async fn forward_soc_event<T: Transport>(link: &T, timestamp_us: u64, event: SocEvent) -> Result<(), ()> {
+    if !EVENT_FILTER.lock().await.category_enabled(EventMask::SOC) {
+        debug!("SoC event filtered by host subscription: {:?}", event);
+        return Ok(());
+    }
 
-    // Send via SPI
-    transport::send_response(tx_packet).await.map_err(|_| ())?;
+    let mut body: Vec<u8, 1> = Vec::new();
+    event.encode(&mut body).map_err(|_| ())?;
+    send_event_frame(link, ResponseCode::SocEvent, timestamp_us, &body).await
+}
+
+/// Forward a system event to the host over `link`, unless the host masked system events
+pub async fn forward_system_event<T: Transport>(link: &T, timestamp_us: u64, event: SystemEvent) -> Result<(), ()> {
+    if !EVENT_FILTER.lock().await.category_enabled(EventMask::SYSTEM) {
+        debug!("System event filtered by host subscription: {:?}", event);
+        return Ok(());
+    }
+
+    let mut body: Vec<u8, 8> = Vec::new();
+    event.encode(&mut body).map_err(|_| ())?;
+    send_event_frame(link, ResponseCode::SystemEvent, timestamp_us, &body).await
+}
+
+/// Send an event body behind its sequence number and timestamp
+///
+/// The frame is dropped while the link is down so events cannot fill the TX
+/// queue with nobody draining it.
+async fn send_event_frame<T: Transport>(
+    link: &T,
+    code: ResponseCode,
+    timestamp_us: u64,
+    body: &[u8],
+) -> Result<(), ()> {
+    let header = EventHeader {
+        seq: next_event_seq(),
+        timestamp_us,
+    };
+    let mut header_data: Vec<u8, EVENT_HEADER_SIZE> = Vec::new();
+    header.encode(&mut header_data).map_err(|_| ())?;
+
+    if link.link_status() == LinkStatus::Down {
+        debug!("Host link down, dropping event");
+        return Err(());
+    }
+
+    // Build the event in a pool buffer with the event code (events never answer a request)
+    let seq = transport::sequenced_framing().then_some(UNSOLICITED_SEQ);
+    let mut packet = TxPacketBuilder::new(seq);
+    packet.write(&header_data).map_err(|_| ())?;
+    packet.write(body).map_err(|_| ())?;
+    let tx_packet = packet.finish(code.to_u16()).map_err(|_| ())?;
+
+    // Send to the host
+    link.send_frame(tx_packet).await.map_err(|_| ())?;
 
     debug!("Event forwarded to host successfully");
     Ok(())
 }
 
+/// SoftDevice SoC event callback, passed to `Softdevice::run_with_callback`
+///
+/// Runs in the SoftDevice task, so the event is only timestamped and queued;
+/// soc_event_task forwards it. Events are dropped if the queue is full.
+pub fn on_soc_event(event: nrf_softdevice::SocEvent) {
+    let event = SocEvent::from_id(event as u32 as u8);
+    let timestamp_us = Instant::now().as_micros();
+
+    match event {
+        SocEvent::PowerFailureWarning => warn!("SoC: Power failure warning"),
+        SocEvent::FlashOperationError => warn!("SoC: Flash operation failed"),
+        _ => debug!("SoC: {:?}", event),
+    }
+
+    if SOC_EVENTS.try_send((timestamp_us, event)).is_err() {
+        warn!("SoC event queue full, dropping {:?}", event);
+    }
+}
+
+/// SoC event task - forwards queued SoC events to the host
+#[embassy_executor::task]
+pub async fn soc_event_task() {
+    info!("Starting SoC event task");
+
+    loop {
+        let (timestamp_us, event) = SOC_EVENTS.receive().await;
+        if forward_soc_event(&transport::HOST_LINK, timestamp_us, event)
+            .await
+            .is_err()
+        {
+            debug!("SoC event {:?} not forwarded", event);
+        }
+    }
+}
+
+/// Longest wait for the host link to come up before the boot event is sent anyway
+const BOOT_EVENT_LINK_TIMEOUT_MS: u64 = 1000;
+
+/// System event task - reports the reset reason, then warns the host when the TX
+/// buffer pool runs low
+///
+/// The warning needs one buffer itself; it is raised while a couple are still free.
+#[embassy_executor::task]
+pub async fn system_event_task() {
+    info!("Starting system event task");
+
+    send_boot_event().await;
+
+    loop {
+        let (timestamp_us, allocated) = memory::wait_pool_low().await;
+        let event = SystemEvent::PoolLow {
+            allocated,
+            size: TX_POOL_SIZE as u8,
+        };
+        if forward_system_event(&transport::HOST_LINK, timestamp_us, event)
+            .await
+            .is_err()
+        {
+            debug!("System event {:?} not forwarded", event);
+        }
+    }
+}
+
+/// Tell the host the modem started and why it reset
+///
+/// The link drivers start alongside this task, so give the link a moment to come up.
+async fn send_boot_event() {
+    let deadline = Instant::now() + Duration::from_millis(BOOT_EVENT_LINK_TIMEOUT_MS);
+    while transport::HOST_LINK.link_status() == LinkStatus::Down && Instant::now() < deadline {
+        Timer::after_millis(10).await;
+    }
+
+    let (reason, resetreas) = power::boot_reason();
+    let event = SystemEvent::Boot { reason, resetreas };
+    if forward_system_event(&transport::HOST_LINK, Instant::now().as_micros(), event)
+        .await
+        .is_err()
+    {
+        warn!("Boot event {:?} not forwarded", event);
+    }
+}
+
 /// Create a Connected event from nrf-softdevice Connection
 pub fn create_connected_event(conn: &Connection) -> BleModemEvent {
     // Note: nrf-softdevice Connection doesn't directly expose peer address
@@ -278,25 +461,24 @@ pub fn create_cccd_write_event(
     }
 }
 
-/// Register an event callback for BLE events
-/// 
-/// This function allows the host application to register a callback function
-/// that will be called whenever BLE events occur.
-/// 
-/// # Parameters
-/// - callback: Function pointer to the callback function
-/// - context: User-defined context value passed to the callback
-/// 
-/// # Returns
-/// - Ok(()) on success
-/// - Err(()) if the callback registry is full
-pub async fn register_event_callback(callback: EventCallbackFn, context: u32) -> Result<(), ()> {
-    CALLBACK_REGISTRY.lock().await.register_callback(callback, context)
+/// Set the event categories forwarded to the host
+pub async fn set_event_mask(mask: EventMask) {
+    EVENT_FILTER.lock().await.set_mask(mask);
+}
+
+/// Mute or unmute the events of one characteristic
+///
+/// Returns `Err(())` if `MAX_CHAR_EVENT_FILTERS` characteristics are already muted.
+pub async fn set_char_event_filter(char_handle: u16, enabled: bool) -> Result<(), ()> {
+    EVENT_FILTER.lock().await.set_char_filter(char_handle, enabled)
+}
+
+/// Unmute every characteristic
+pub async fn clear_char_event_filters() {
+    EVENT_FILTER.lock().await.clear_char_filters();
 }
 
-/// Clear all registered event callbacks
-/// 
-/// This function removes all previously registered event callbacks.
-pub async fn clear_event_callbacks() {
-    CALLBACK_REGISTRY.lock().await.clear_callbacks();
+/// Current event subscription
+pub async fn event_subscription() -> EventSubscription {
+    EVENT_FILTER.lock().await.subscription()
 }
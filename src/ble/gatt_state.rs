@@ -339,6 +339,7 @@ pub enum StateError {
     NameTooLong,
     InvalidHandle,
     ConnectionNotFound,
+    EventFiltersExhausted,
 }
 
 use embassy_sync::once_lock::OnceLock;
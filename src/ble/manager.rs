@@ -70,7 +70,7 @@ pub struct CharacteristicCreateResponse {
 }
 
 /// Service creation errors
-#[derive(Debug, Clone, Copy, defmt::Format)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, defmt::Format)]
 pub enum ServiceCreateError {
     RegisterError,
     UuidConversionFailed,
@@ -29,7 +29,7 @@ pub struct NotificationResponse {
 }
 
 /// Notification errors
-#[derive(Debug, Clone, Copy, Format)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Format)]
 pub enum NotificationError {
     ConnectionNotFound,
     CharacteristicNotFound,
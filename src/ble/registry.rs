@@ -10,6 +10,8 @@ use embassy_sync::once_lock::OnceLock;
 use heapless::Vec;
 use nrf_softdevice::ble::Uuid;
 
+use crate::core::protocol::codec::Uuid as ProtocolUuid;
+
 /// Maximum number of services we can register
 pub const MAX_SERVICES: usize = 8;
 
@@ -270,7 +272,7 @@ impl GattRegistry {
         (self.service_count, self.characteristic_count, self.uuid_base_count)
     }
 
-    /// Clear all entries (for testing)
+    /// Clear all entries (for testing and FACTORY_RESET)
     pub fn clear(&mut self) {
         self.service_count = 0;
         self.characteristic_count = 0;
@@ -308,37 +310,14 @@ impl BleUuid {
             }
         }
     }
+}
 
-    /// Parse UUID from payload data
-    pub fn from_payload(uuid_type: u8, uuid_data: &[u8]) -> Result<Self, RegistryError> {
-        match uuid_type {
-            0 => {
-                // 16-bit UUID
-                if uuid_data.len() < 2 {
-                    return Err(RegistryError::InvalidUuidType);
-                }
-                let uuid = u16::from_le_bytes([uuid_data[0], uuid_data[1]]);
-                Ok(BleUuid::Uuid16(uuid))
-            }
-            1 => {
-                // 128-bit UUID
-                if uuid_data.len() < 16 {
-                    return Err(RegistryError::InvalidUuidType);
-                }
-                let mut uuid = [0u8; 16];
-                uuid.copy_from_slice(&uuid_data[..16]);
-                Ok(BleUuid::Uuid128(uuid))
-            }
-            2 => {
-                // Vendor-specific UUID (base_id + offset)
-                if uuid_data.len() < 3 {
-                    return Err(RegistryError::InvalidUuidType);
-                }
-                let base_id = uuid_data[0];
-                let offset = u16::from_le_bytes([uuid_data[1], uuid_data[2]]);
-                Ok(BleUuid::VendorSpecific { base_id, offset })
-            }
-            _ => Err(RegistryError::InvalidUuidType),
+impl From<ProtocolUuid> for BleUuid {
+    fn from(uuid: ProtocolUuid) -> Self {
+        match uuid {
+            ProtocolUuid::Uuid16(uuid) => BleUuid::Uuid16(uuid),
+            ProtocolUuid::Uuid128(uuid) => BleUuid::Uuid128(uuid),
+            ProtocolUuid::VendorSpecific { base_id, offset } => BleUuid::VendorSpecific { base_id, offset },
         }
     }
 }
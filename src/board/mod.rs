@@ -0,0 +1,58 @@
+//! Board Support
+//!
+//! Pin assignments and SPI link parameters for the board the firmware runs on,
+//! so the transports stay board-agnostic. Each board is a submodule selected by a
+//! `board-*` cargo feature and splits the chip peripherals into a [`Board`].
+//!
+//! To support a new board, add a module next to `reference.rs` providing
+//! `split(Peripherals) -> Board` and the `LINK_WAKE_PIN` used by SHUTDOWN, gate
+//! it on a new `board-<name>` feature and build with
+//! `--no-default-features --features board-<name>`.
+
+#[cfg(not(any(feature = "uart", feature = "single-spi")))]
+use embassy_nrf::peripherals::TWISPI0;
+#[cfg(not(feature = "uart"))]
+use embassy_nrf::peripherals::TWISPI1;
+#[cfg(feature = "uart")]
+use embassy_nrf::peripherals::UARTE0;
+use embassy_nrf::Peri;
+
+#[cfg(feature = "single-spi")]
+use crate::core::single_spi::SingleSpiConfig;
+#[cfg(not(any(feature = "uart", feature = "single-spi")))]
+use crate::core::transport::{RxSpiConfig, TxSpiConfig};
+#[cfg(feature = "uart")]
+use crate::core::uart::UartConfig;
+
+#[cfg(feature = "board-reference")]
+mod reference;
+#[cfg(feature = "board-reference")]
+pub use reference::*;
+
+#[cfg(not(feature = "board-reference"))]
+compile_error!("No board selected: enable a `board-*` cargo feature (e.g. `board-reference`)");
+
+/// Host link resources of the selected board
+pub struct Board {
+    /// Device → Host SPI (pins, clock, mode)
+    #[cfg(not(any(feature = "uart", feature = "single-spi")))]
+    pub tx_spi: TxSpiConfig,
+    /// Host → Device SPI (pins, mode)
+    #[cfg(not(any(feature = "uart", feature = "single-spi")))]
+    pub rx_spi: RxSpiConfig,
+    #[cfg(not(any(feature = "uart", feature = "single-spi")))]
+    pub spim: Peri<'static, TWISPI0>,
+    /// SPI slave of either SPI link
+    #[cfg(not(feature = "uart"))]
+    pub spis: Peri<'static, TWISPI1>,
+
+    /// Full-duplex SPI link pins and mode
+    #[cfg(feature = "single-spi")]
+    pub single_spi: SingleSpiConfig,
+
+    /// UART link pins and byte counter resources
+    #[cfg(feature = "uart")]
+    pub uart: UartConfig,
+    #[cfg(feature = "uart")]
+    pub uarte: Peri<'static, UARTE0>,
+}
@@ -0,0 +1,103 @@
+//! Reference Board
+//!
+//! nRF52820 modem module as wired to the original host connector:
+//! - TX SPI (device → host): SS=P0.01, SCK=P0.00, MOSI=P0.04
+//! - RX SPI (host → device): SS=P0.07, SCK=P0.06, MISO=P0.05
+//! - SPI handshake (`spi-handshake` feature, not on the original wiring): host ready input P0.15,
+//!   device ready output P0.14
+//! - Single SPI (`single-spi` feature): SS=P0.07, SCK=P0.06, MOSI=P0.05, MISO=P0.04,
+//!   IRQ output P0.14 (the RX SPI pins, with the device data out and ready lines)
+//! - UART (`uart` feature, same connector pins): RXD=P0.05, TXD=P0.04, CTS=P0.07, RTS=P0.06
+//!
+//! All SPI links run CPOL=High, CPHA=Leading; the device clocks TX at 8MHz.
+
+use embassy_nrf::spim::{Frequency, Mode, Phase, Polarity};
+use embassy_nrf::Peripherals;
+
+use super::Board;
+#[cfg(feature = "single-spi")]
+use crate::core::single_spi::SingleSpiConfig;
+#[cfg(not(any(feature = "uart", feature = "single-spi")))]
+use crate::core::transport::{RxSpiConfig, TxSpiConfig};
+#[cfg(feature = "uart")]
+use crate::core::uart::UartConfig;
+
+/// TX SPI clock
+pub const SPI_FREQUENCY: Frequency = Frequency::M8;
+
+/// SPI mode of both links
+pub const SPI_MODE: Mode = Mode {
+    polarity: Polarity::IdleHigh,
+    phase: Phase::CaptureOnFirstTransition,
+};
+
+/// Pin that wakes the modem from System OFF when SHUTDOWN selects the host link:
+/// the RX SPI / single-SPI select line (P0.07), or RXD (P0.05) with the UART link
+#[cfg(not(feature = "uart"))]
+pub const LINK_WAKE_PIN: u8 = 7;
+#[cfg(feature = "uart")]
+pub const LINK_WAKE_PIN: u8 = 5;
+
+/// Split the chip peripherals into the board's host link resources
+#[cfg(not(any(feature = "uart", feature = "single-spi")))]
+pub fn split(p: Peripherals) -> Board {
+    Board {
+        tx_spi: TxSpiConfig {
+            cs_pin: p.P0_01.into(),
+            sck_pin: p.P0_00.into(),
+            mosi_pin: p.P0_04.into(), // Master out - device transmits to host
+            #[cfg(feature = "spi-handshake")]
+            host_ready_pin: Some(p.P0_15.into()),
+            #[cfg(not(feature = "spi-handshake"))]
+            host_ready_pin: None,
+            frequency: SPI_FREQUENCY,
+            mode: SPI_MODE,
+        },
+        rx_spi: RxSpiConfig {
+            cs_pin: p.P0_07.into(),
+            sck_pin: p.P0_06.into(),
+            miso_pin: p.P0_05.into(), // Slave in - host transmits to device
+            #[cfg(feature = "spi-handshake")]
+            ready_pin: Some(p.P0_14.into()),
+            #[cfg(not(feature = "spi-handshake"))]
+            ready_pin: None,
+            mode: SPI_MODE,
+        },
+        spim: p.TWISPI0,
+        spis: p.TWISPI1,
+    }
+}
+
+/// Split the chip peripherals into the board's host link resources
+#[cfg(feature = "single-spi")]
+pub fn split(p: Peripherals) -> Board {
+    Board {
+        single_spi: SingleSpiConfig {
+            cs_pin: p.P0_07.into(),
+            sck_pin: p.P0_06.into(),
+            miso_pin: p.P0_04.into(), // Device transmits to host
+            mosi_pin: p.P0_05.into(), // Host transmits to device
+            irq_pin: p.P0_14.into(),
+            mode: SPI_MODE,
+        },
+        spis: p.TWISPI1,
+    }
+}
+
+/// Split the chip peripherals into the board's host link resources
+#[cfg(feature = "uart")]
+pub fn split(p: Peripherals) -> Board {
+    Board {
+        uart: UartConfig {
+            rxd_pin: p.P0_05.into(),
+            txd_pin: p.P0_04.into(),
+            cts_pin: p.P0_07.into(),
+            rts_pin: p.P0_06.into(),
+            timer: p.TIMER1,
+            ppi_ch1: p.PPI_CH0,
+            ppi_ch2: p.PPI_CH1,
+            ppi_group: p.PPI_GROUP0,
+        },
+        uarte: p.UARTE0,
+    }
+}
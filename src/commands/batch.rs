@@ -0,0 +1,86 @@
+//! Batched Command Handler
+//!
+//! Runs the sub-commands of a BATCH request through the dispatcher, in order,
+//! and packs their responses into a single `Ack`.
+//!
+//! Response format: see `ble_modem_protocol::batch`
+
+use defmt::{debug, warn};
+use heapless::Vec;
+use nrf_softdevice::Softdevice;
+
+use super::{decode_request, execute_command, set_current_code, CommandError, ResponseBuilder};
+use crate::core::memory::TxPacket;
+use crate::core::protocol::batch::{BatchEntries, BatchEntry, BATCH_STOP_ON_ERROR};
+use crate::core::protocol::requests::BatchRequest;
+use crate::core::protocol::{Frame, ProtocolError, RequestCode, ResponseCode, MAX_MESSAGE_SIZE};
+
+/// Handle BATCH command
+///
+/// The whole batch is validated before anything runs. Each sub-result is the
+/// response the sub-command would have sent on its own; if the results do not
+/// fit in one response the batch fails with `BufferFull` after the sub-commands
+/// that ran, so batches of commands with large responses should be kept short.
+pub async fn handle_batch(payload: &[u8], sd: &Softdevice) -> Result<TxPacket, CommandError> {
+    let request: BatchRequest = decode_request(payload)?;
+    let stop_on_error = request.flags & BATCH_STOP_ON_ERROR != 0;
+
+    if BatchEntries::new(request.commands).any(|entry| entry.is_err()) {
+        debug!("Malformed batch entries");
+        return Err(CommandError::InvalidPayload);
+    }
+
+    let mut results: Vec<u8, MAX_MESSAGE_SIZE> = Vec::new();
+    let mut executed: u8 = 0;
+
+    for entry in BatchEntries::new(request.commands).flatten() {
+        // Errors built by the sub-command report its own request code
+        set_current_code(entry.code);
+
+        let response = match RequestCode::from_u16(entry.code) {
+            Some(request_code) => execute_command(request_code, entry.payload, sd).await,
+            None => Err(CommandError::UnknownCommand),
+        };
+        let response = response.or_else(ResponseBuilder::build_error);
+        set_current_code(RequestCode::Batch as u16);
+
+        let code = append_result(&mut results, &response?)?;
+        executed = executed.saturating_add(1);
+
+        if stop_on_error && code == ResponseCode::Error.to_u16() {
+            warn!(
+                "Batch stopped after sub-command {} ({:#06x}) failed",
+                executed, entry.code
+            );
+            break;
+        }
+    }
+
+    debug!("Batch executed {} sub-commands", executed);
+
+    let mut response = ResponseBuilder::new();
+    response.add_u8(executed)?.add_slice(&results)?;
+    response.build(ResponseCode::Ack)
+}
+
+/// Append a sub-command response to the batch results, returning its response code
+///
+/// Frame bodies of a fragmented response concatenate to the unfragmented body.
+fn append_result(results: &mut Vec<u8, MAX_MESSAGE_SIZE>, response: &TxPacket) -> Result<u16, CommandError> {
+    let mut body: Vec<u8, { MAX_MESSAGE_SIZE + 2 }> = Vec::new();
+    for frame in response.frames() {
+        body.extend_from_slice(Frame::parse(frame)?.body)
+            .map_err(|_| ProtocolError::BufferFull)?;
+    }
+    if body.len() < 2 {
+        return Err(CommandError::ProtocolError(ProtocolError::InvalidLength));
+    }
+
+    let code = u16::from_be_bytes([body[0], body[1]]);
+    BatchEntry {
+        code,
+        payload: &body[2..],
+    }
+    .encode(results)?;
+    Ok(code)
+}
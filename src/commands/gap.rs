@@ -8,9 +8,14 @@ use nrf_softdevice::ble::{Address, AddressType};
 use nrf_softdevice::Softdevice;
 
 use crate::ble::{advertising, gap_state};
-use crate::commands::{CommandError, ResponseBuilder};
+use crate::commands::{decode_request, CommandError, ResponseBuilder};
 use crate::core::memory::TxPacket;
-use crate::core::protocol::serialization::PayloadReader;
+use crate::core::protocol::codec::AdvData;
+use crate::core::protocol::requests::{
+    GapAdvSetConfigureRequest, GapAdvStartRequest, GapAdvStopRequest, GapConnParamUpdateRequest,
+    GapConnParamsSetRequest, GapDataLengthUpdateRequest, GapDisconnectRequest, GapGetNameRequest, GapPhyUpdateRequest,
+    GapSetAddrRequest, GapSetNameRequest, GapSetTxPowerRequest,
+};
 
 // Placeholder implementations - will be completed in later phases
 
@@ -39,14 +44,10 @@ pub async fn handle_get_addr(_payload: &[u8]) -> Result<TxPacket, CommandError>
 pub async fn handle_set_addr(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GAP: SET_ADDR");
 
-    if payload.len() < 7 {
-        // addr_type (1) + addr (6)
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let mut reader = PayloadReader::new(payload);
-    let addr_type_u8 = reader.read_u8()?;
-    let addr_bytes = reader.read_slice(6)?;
+    let GapSetAddrRequest {
+        addr_type: addr_type_u8,
+        addr: addr_array,
+    } = decode_request(payload)?;
 
     // Convert to AddressType enum
     let addr_type = match addr_type_u8 {
@@ -57,8 +58,6 @@ pub async fn handle_set_addr(payload: &[u8]) -> Result<TxPacket, CommandError> {
         _ => return ResponseBuilder::build_error(CommandError::InvalidPayload),
     };
 
-    let mut addr_array = [0u8; 6];
-    addr_array.copy_from_slice(addr_bytes);
     let addr = Address::new(addr_type, addr_array);
 
     // Set address using nrf-softdevice wrapper
@@ -77,14 +76,10 @@ pub async fn handle_set_addr(payload: &[u8]) -> Result<TxPacket, CommandError> {
 pub async fn handle_adv_start(payload: &[u8], _sd: &Softdevice) -> Result<TxPacket, CommandError> {
     debug!("GAP: ADV_START");
 
-    if payload.len() < 2 {
-        // adv_handle + conn_cfg_tag
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let mut reader = PayloadReader::new(payload);
-    let adv_handle = reader.read_u8()?;
-    let conn_cfg_tag = reader.read_u8()?;
+    let GapAdvStartRequest {
+        adv_handle,
+        conn_cfg_tag,
+    } = decode_request(payload)?;
 
     // Send command to advertising controller
     let cmd = advertising::AdvCommand::Start {
@@ -106,13 +101,7 @@ pub async fn handle_adv_start(payload: &[u8], _sd: &Softdevice) -> Result<TxPack
 pub async fn handle_adv_stop(payload: &[u8], _sd: &Softdevice) -> Result<TxPacket, CommandError> {
     debug!("GAP: ADV_STOP");
 
-    if payload.len() < 1 {
-        // adv_handle
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let mut reader = PayloadReader::new(payload);
-    let adv_handle = reader.read_u8()?;
+    let GapAdvStopRequest { adv_handle } = decode_request(payload)?;
 
     // Send command to advertising controller
     let cmd = advertising::AdvCommand::Stop { handle: adv_handle };
@@ -128,48 +117,69 @@ pub async fn handle_adv_stop(payload: &[u8], _sd: &Softdevice) -> Result<TxPacke
     response.build(crate::core::protocol::ResponseCode::Ack)
 }
 
+/// Handle GAP_ADV_SET_CONFIGURE command (0x0022)
+/// Sets the advertising data and parameters, applied straight away if advertising is active
+///
+/// Payload format:
+/// [Handle (1)] [Data Present (1)] [Adv Data Length (2)] [Scan Data Length (2)] [Adv Data] [Scan Data]
+/// [Interval (2)] [Timeout (2)] [Filter Policy (1)] (data and parameters optional, see `codec::AdvParams`)
 pub async fn handle_adv_configure(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GAP: ADV_CONFIGURE");
 
-    if payload.len() < 2 {
-        // At least handle + data present flag
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
+    let GapAdvSetConfigureRequest {
+        adv_handle: handle,
+        data_present,
+        data,
+        params,
+    } = decode_request(payload)?;
+
+    // Validate everything before applying anything
+    let data = data.filter(|_| data_present);
+    if let Some(AdvData { adv_data, scan_data }) = data {
+        if adv_data.len() > gap_state::MAX_ADV_DATA_LEN || scan_data.len() > gap_state::MAX_ADV_DATA_LEN {
+            debug!(
+                "Advertising data too long: {} bytes adv, {} bytes scan",
+                adv_data.len(),
+                scan_data.len()
+            );
+            return Err(CommandError::InvalidPayload);
+        }
+    }
+    if let Some(params) = params.filter(|params| !params.is_valid()) {
+        debug!(
+            "Invalid advertising parameters: interval {}, filter policy {}",
+            params.interval, params.filter_policy
+        );
+        return Err(CommandError::InvalidPayload);
     }
 
-    let mut reader = PayloadReader::new(payload);
-    let handle = reader.read_u8()?;
-    let data_present = reader.read_u8()? != 0;
-
-    // TODO: Parse advertising parameters and data from payload
-    // For now, we'll use a simplified approach that works with the controller
-
-    if data_present && reader.remaining() >= 4 {
-        // Read advertising data length and scan response length
-        let adv_data_len = reader.read_u16()? as usize;
-        let scan_rsp_len = reader.read_u16()? as usize;
-
-        // Validate lengths
-        if adv_data_len <= 31 && scan_rsp_len <= 31 && reader.remaining() >= adv_data_len + scan_rsp_len {
-            let adv_data = reader.read_slice(adv_data_len)?;
-            let scan_data = reader.read_slice(scan_rsp_len)?;
-
-            // Store in gap state for the advertising controller to use
-            {
-                let mut state = gap_state::gap_state().lock().await;
-                state.set_adv_data(adv_data);
-                state.set_scan_response(scan_data);
-                state.adv_handle = handle;
-            }
+    // Store in gap state for the advertising controller to use
+    {
+        let mut state = gap_state::gap_state().lock().await;
+        if let Some(AdvData { adv_data, scan_data }) = data {
+            state.set_adv_data(adv_data);
+            state.set_scan_response(scan_data);
+            state.adv_handle = handle;
 
             debug!(
                 "Configured advertising data: {} bytes adv, {} bytes scan",
-                adv_data_len, scan_rsp_len
+                adv_data.len(),
+                scan_data.len()
             );
         }
+        if let Some(params) = params {
+            state.adv_interval_min = params.interval;
+            state.adv_interval_max = params.interval;
+            state.adv_timeout = params.timeout;
+        }
     }
 
     // Send configure command to advertising controller
-    let cmd = advertising::AdvCommand::Configure { handle, data_present };
+    let cmd = advertising::AdvCommand::Configure {
+        handle,
+        data_present: data.is_some(),
+        params,
+    };
     let result = if advertising::send_command(cmd).is_ok() {
         nrf_softdevice::raw::NRF_SUCCESS
     } else {
@@ -185,12 +195,7 @@ pub async fn handle_adv_configure(payload: &[u8]) -> Result<TxPacket, CommandErr
 pub async fn handle_get_name(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GAP: GET_NAME");
 
-    if payload.len() < 1 {
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let mut reader = PayloadReader::new(payload);
-    let present = reader.read_u8()? != 0;
+    let GapGetNameRequest { present } = decode_request(payload)?;
 
     let mut response = ResponseBuilder::new();
     response.add_u32(nrf_softdevice::raw::NRF_SUCCESS)?;
@@ -215,17 +220,11 @@ pub async fn handle_get_name(payload: &[u8]) -> Result<TxPacket, CommandError> {
 pub async fn handle_set_name(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GAP: SET_NAME");
 
-    if payload.len() < 2 {
-        // At least security mode (2 bytes)
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let mut reader = PayloadReader::new(payload);
-    let sec_mode_sm = reader.read_u8()?; // Security mode sm field
-    let sec_mode_lv = reader.read_u8()?; // Security mode lv field
-
-    // Remaining payload is the device name
-    let name_bytes = &payload[2..];
+    let GapSetNameRequest {
+        sec_mode: sec_mode_sm,
+        sec_level: sec_mode_lv,
+        name: name_bytes,
+    } = decode_request(payload)?;
 
     // Set device name using SoftDevice API
     let sec_mode = nrf_softdevice::raw::ble_gap_conn_sec_mode_t {
@@ -288,16 +287,12 @@ pub async fn handle_conn_params_get(_payload: &[u8]) -> Result<TxPacket, Command
 pub async fn handle_conn_params_set(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GAP: CONN_PARAMS_SET");
 
-    if payload.len() < 8 {
-        // 4 x u16 = 8 bytes
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let mut reader = PayloadReader::new(payload);
-    let min_conn_interval = reader.read_u16()?;
-    let max_conn_interval = reader.read_u16()?;
-    let slave_latency = reader.read_u16()?;
-    let conn_sup_timeout = reader.read_u16()?;
+    let GapConnParamsSetRequest {
+        min_conn_interval,
+        max_conn_interval,
+        slave_latency,
+        conn_sup_timeout,
+    } = decode_request(payload)?;
 
     let conn_params = nrf_softdevice::raw::ble_gap_conn_params_t {
         min_conn_interval,
@@ -336,17 +331,13 @@ pub async fn handle_conn_params_set(payload: &[u8]) -> Result<TxPacket, CommandE
 pub async fn handle_conn_param_update(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GAP: CONN_PARAM_UPDATE requested");
 
-    if payload.len() != 10 {
-        debug!("GAP: Invalid payload length: {} (expected 10)", payload.len());
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    // Parse connection parameters
-    let conn_handle = u16::from_le_bytes([payload[0], payload[1]]);
-    let min_conn_interval = u16::from_le_bytes([payload[2], payload[3]]);
-    let max_conn_interval = u16::from_le_bytes([payload[4], payload[5]]);
-    let slave_latency = u16::from_le_bytes([payload[6], payload[7]]);
-    let conn_sup_timeout = u16::from_le_bytes([payload[8], payload[9]]);
+    let GapConnParamUpdateRequest {
+        conn_handle,
+        min_conn_interval,
+        max_conn_interval,
+        slave_latency,
+        conn_sup_timeout,
+    } = decode_request(payload)?;
 
     debug!("GAP: Updating connection parameters for handle {}: min={}, max={}, latency={}, timeout={}", 
            conn_handle, min_conn_interval, max_conn_interval, slave_latency, conn_sup_timeout);
@@ -369,7 +360,7 @@ pub async fn handle_conn_param_update(payload: &[u8]) -> Result<TxPacket, Comman
 
     if ret != nrf_softdevice::raw::NRF_SUCCESS {
         error!("GAP: Failed to update connection parameters: error code {}", ret);
-        return ResponseBuilder::build_error(CommandError::SoftDeviceError);
+        return ResponseBuilder::build_error(CommandError::SoftDeviceError(ret));
     }
 
     info!("GAP: Connection parameter update initiated successfully");
@@ -386,14 +377,11 @@ pub async fn handle_conn_param_update(payload: &[u8]) -> Result<TxPacket, Comman
 pub async fn handle_data_length_update(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GAP: DATA_LENGTH_UPDATE requested");
 
-    if payload.len() != 6 {
-        debug!("GAP: Invalid payload length: {} (expected 6)", payload.len());
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let conn_handle = u16::from_le_bytes([payload[0], payload[1]]);
-    let tx_octets = u16::from_le_bytes([payload[2], payload[3]]);
-    let tx_time_us = u16::from_le_bytes([payload[4], payload[5]]);
+    let GapDataLengthUpdateRequest {
+        conn_handle,
+        tx_octets,
+        tx_time_us,
+    } = decode_request(payload)?;
 
     debug!("GAP: Updating data length for handle {}: tx_octets={}, tx_time={}us", 
            conn_handle, tx_octets, tx_time_us);
@@ -423,7 +411,7 @@ pub async fn handle_data_length_update(payload: &[u8]) -> Result<TxPacket, Comma
 
     if ret != nrf_softdevice::raw::NRF_SUCCESS {
         error!("GAP: Failed to update data length: error code {}", ret);
-        return ResponseBuilder::build_error(CommandError::SoftDeviceError);
+        return ResponseBuilder::build_error(CommandError::SoftDeviceError(ret));
     }
 
     info!("GAP: Data length update initiated successfully");
@@ -441,15 +429,12 @@ pub async fn handle_data_length_update(payload: &[u8]) -> Result<TxPacket, Comma
 pub async fn handle_phy_update(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GAP: PHY_UPDATE requested");
 
-    if payload.len() != 6 {
-        debug!("GAP: Invalid payload length: {} (expected 6)", payload.len());
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let conn_handle = u16::from_le_bytes([payload[0], payload[1]]);
-    let tx_phys = payload[2];
-    let rx_phys = payload[3];
-    let _phy_options = u16::from_le_bytes([payload[4], payload[5]]); // Not used by SoftDevice API
+    let GapPhyUpdateRequest {
+        conn_handle,
+        tx_phys,
+        rx_phys,
+        coded_phy: _,
+    } = decode_request(payload)?;
 
     debug!("GAP: Updating PHY for handle {}: tx_phys=0x{:02X}, rx_phys=0x{:02X}", 
            conn_handle, tx_phys, rx_phys);
@@ -470,7 +455,7 @@ pub async fn handle_phy_update(payload: &[u8]) -> Result<TxPacket, CommandError>
 
     if ret != nrf_softdevice::raw::NRF_SUCCESS {
         error!("GAP: Failed to update PHY: error code {}", ret);
-        return ResponseBuilder::build_error(CommandError::SoftDeviceError);
+        return ResponseBuilder::build_error(CommandError::SoftDeviceError(ret));
     }
 
     info!("GAP: PHY update initiated successfully");
@@ -486,13 +471,7 @@ pub async fn handle_phy_update(payload: &[u8]) -> Result<TxPacket, CommandError>
 pub async fn handle_disconnect(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GAP: DISCONNECT requested");
 
-    if payload.len() != 3 {
-        debug!("GAP: Invalid payload length: {} (expected 3)", payload.len());
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let conn_handle = u16::from_le_bytes([payload[0], payload[1]]);
-    let reason = payload[2];
+    let GapDisconnectRequest { conn_handle, reason } = decode_request(payload)?;
 
     debug!("GAP: Disconnecting connection handle {} with reason {}", conn_handle, reason);
 
@@ -503,7 +482,7 @@ pub async fn handle_disconnect(payload: &[u8]) -> Result<TxPacket, CommandError>
 
     if ret != nrf_softdevice::raw::NRF_SUCCESS {
         error!("GAP: Failed to disconnect: error code {}", ret);
-        return ResponseBuilder::build_error(CommandError::SoftDeviceError);
+        return ResponseBuilder::build_error(CommandError::SoftDeviceError(ret));
     }
 
     info!("GAP: Connection disconnect initiated successfully");
@@ -520,14 +499,11 @@ pub async fn handle_disconnect(payload: &[u8]) -> Result<TxPacket, CommandError>
 pub async fn handle_set_tx_power(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GAP: SET_TX_POWER requested");
 
-    if payload.len() != 4 {
-        debug!("GAP: Invalid payload length: {} (expected 4)", payload.len());
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let role = payload[0];
-    let conn_handle = u16::from_le_bytes([payload[1], payload[2]]);
-    let tx_power = payload[3] as i8; // Convert to signed
+    let GapSetTxPowerRequest {
+        role,
+        conn_handle,
+        tx_power,
+    } = decode_request(payload)?;
 
     debug!("GAP: Setting TX power for role {} handle {}: {}dBm", role, conn_handle, tx_power);
 
@@ -567,7 +543,7 @@ pub async fn handle_set_tx_power(payload: &[u8]) -> Result<TxPacket, CommandErro
 
     if ret != nrf_softdevice::raw::NRF_SUCCESS {
         error!("GAP: Failed to set TX power: error code {}", ret);
-        return ResponseBuilder::build_error(CommandError::SoftDeviceError);
+        return ResponseBuilder::build_error(CommandError::SoftDeviceError(ret));
     }
 
     info!("GAP: TX power set successfully to {}dBm for role {}", tx_power, role);
@@ -8,9 +8,12 @@ use nrf_softdevice::ble::gatt_server::builder::ServiceBuilder;
 use nrf_softdevice::Softdevice;
 
 use crate::ble::registry::{with_registry, BleUuid, ServiceType};
-use crate::commands::{CommandError, ResponseBuilder};
+use crate::commands::{decode_request, CommandError, ResponseBuilder};
 use crate::core::memory::TxPacket;
-use crate::core::protocol::serialization::PayloadReader;
+use crate::core::protocol::requests::{
+    GattsCharacteristicAddRequest, GattsHvxRequest, GattsMtuReplyRequest, GattsServiceAddRequest,
+    GattsSysAttrSetRequest,
+};
 
 /// Handle GATTS_SERVICE_ADD command (0x0080)
 ///
@@ -22,73 +25,20 @@ use crate::core::protocol::serialization::PayloadReader;
 pub async fn handle_service_add(payload: &[u8], sd: &Softdevice) -> Result<TxPacket, CommandError> {
     debug!("GATTS: SERVICE_ADD requested");
 
-    if payload.len() < 4 {
-        debug!("GATTS: Invalid payload length: {} (expected >= 4)", payload.len());
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let mut reader = PayloadReader::new(payload);
+    let request: GattsServiceAddRequest = decode_request(payload)?;
 
-    // Parse UUID type
-    let uuid_type = reader.read_u8()?;
-    debug!("GATTS: UUID type: {}", uuid_type);
-
-    // Parse UUID based on type
-    let uuid_data = match uuid_type {
-        0 => {
-            // 16-bit UUID
-            if reader.remaining() < 2 {
-                return ResponseBuilder::build_error(CommandError::InvalidPayload);
-            }
-            &payload[1..3]
-        }
-        1 => {
-            // 128-bit UUID
-            if reader.remaining() < 16 {
-                return ResponseBuilder::build_error(CommandError::InvalidPayload);
-            }
-            &payload[1..17]
-        }
-        2 => {
-            // Vendor-specific UUID (base_id + offset)
-            if reader.remaining() < 3 {
-                return ResponseBuilder::build_error(CommandError::InvalidPayload);
-            }
-            &payload[1..4]
-        }
-        _ => {
-            debug!("GATTS: Invalid UUID type: {}", uuid_type);
-            return ResponseBuilder::build_error(CommandError::InvalidPayload);
-        }
-    };
-
-    // Parse service type (after UUID data)
-    let service_type_offset = 1 + uuid_data.len();
-    if payload.len() <= service_type_offset {
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let service_type_byte = payload[service_type_offset];
-    let service_type = match service_type_byte {
+    let service_type = match request.service_type {
         1 => ServiceType::Primary,
         2 => ServiceType::Secondary,
         _ => {
-            debug!("GATTS: Invalid service type: {}", service_type_byte);
+            debug!("GATTS: Invalid service type: {}", request.service_type);
             return ResponseBuilder::build_error(CommandError::InvalidPayload);
         }
     };
 
     debug!("GATTS: Service type: {:?}", service_type);
 
-    // Parse UUID
-    let ble_uuid = match BleUuid::from_payload(uuid_type, uuid_data) {
-        Ok(uuid) => uuid,
-        Err(_) => {
-            debug!("GATTS: Failed to parse UUID");
-            return ResponseBuilder::build_error(CommandError::InvalidPayload);
-        }
-    };
-
+    let ble_uuid = BleUuid::from(request.uuid);
     debug!("GATTS: Parsed UUID: {:?}", ble_uuid);
 
     // Convert to nrf-softdevice UUID
@@ -107,7 +57,7 @@ pub async fn handle_service_add(payload: &[u8], sd: &Softdevice) -> Result<TxPac
         Ok(handle) => handle,
         Err(e) => {
             error!("GATTS: Failed to create service via service manager: {:?}", e);
-            return ResponseBuilder::build_error(CommandError::SoftDeviceError);
+            return ResponseBuilder::build_error(e.into());
         }
     };
 
@@ -134,15 +84,9 @@ pub async fn handle_service_add(payload: &[u8], sd: &Softdevice) -> Result<TxPac
 pub async fn handle_characteristic_add(payload: &[u8], _sd: &Softdevice) -> Result<TxPacket, CommandError> {
     debug!("GATTS: CHARACTERISTIC_ADD requested");
 
-    if payload.len() < 8 {
-        debug!("GATTS: Invalid payload length: {} (expected >= 8)", payload.len());
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let mut reader = PayloadReader::new(payload);
+    let request: GattsCharacteristicAddRequest = decode_request(payload)?;
 
-    // Parse service handle
-    let service_handle = reader.read_u16()?;
+    let service_handle = request.service_handle;
     debug!("GATTS: Service handle: {}", service_handle);
 
     // Verify service exists
@@ -153,59 +97,18 @@ pub async fn handle_characteristic_add(payload: &[u8], _sd: &Softdevice) -> Resu
         return ResponseBuilder::build_error(CommandError::InvalidPayload);
     }
 
-    // Parse UUID type
-    let uuid_type = reader.read_u8()?;
-
-    // Parse UUID data length based on type
-    let uuid_len = match uuid_type {
-        0 => 2,  // 16-bit UUID
-        1 => 16, // 128-bit UUID
-        2 => 3,  // Vendor-specific (base_id + offset)
-        _ => {
-            debug!("GATTS: Invalid UUID type: {}", uuid_type);
-            return ResponseBuilder::build_error(CommandError::InvalidPayload);
-        }
-    };
-
-    if reader.remaining() < uuid_len {
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let uuid_data = reader.read_slice(uuid_len)?;
-
-    // Parse characteristic properties
-    let properties = reader.read_u8()?;
-    debug!("GATTS: Properties: 0x{:02X}", properties);
-
-    // Parse max length
-    let max_length = reader.read_u16()?;
-    debug!("GATTS: Max length: {}", max_length);
-
-    // Parse initial value
-    let initial_value_len = reader.read_u8()? as usize;
-    if reader.remaining() < initial_value_len {
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let initial_value = reader.read_slice(initial_value_len)?;
-    debug!("GATTS: Initial value length: {}", initial_value_len);
-
-    // Parse permissions (if remaining)
-    let permissions = if reader.remaining() > 0 {
-        reader.read_u8()?
-    } else {
-        0x00 // Default permissions
-    };
-
-    // Parse UUID
-    let ble_uuid = match BleUuid::from_payload(uuid_type, uuid_data) {
-        Ok(uuid) => uuid,
-        Err(_) => {
-            debug!("GATTS: Failed to parse characteristic UUID");
-            return ResponseBuilder::build_error(CommandError::InvalidPayload);
-        }
-    };
+    let properties = request.properties;
+    let max_length = request.max_length;
+    let initial_value = request.initial_value;
+    let permissions = request.permissions.unwrap_or(0x00); // Default permissions
+    debug!(
+        "GATTS: Properties: 0x{:02X}, max length: {}, initial value length: {}",
+        properties,
+        max_length,
+        initial_value.len()
+    );
 
+    let ble_uuid = BleUuid::from(request.uuid);
     debug!("GATTS: Parsed characteristic UUID: {:?}", ble_uuid);
 
     // Create the characteristic using the service manager
@@ -222,7 +125,7 @@ pub async fn handle_characteristic_add(payload: &[u8], _sd: &Softdevice) -> Resu
         Ok(handles) => handles,
         Err(e) => {
             error!("GATTS: Failed to create characteristic via service manager: {:?}", e);
-            return ResponseBuilder::build_error(CommandError::SoftDeviceError);
+            return ResponseBuilder::build_error(e.into());
         }
     };
 
@@ -268,29 +171,21 @@ pub async fn handle_characteristic_add(payload: &[u8], _sd: &Softdevice) -> Resu
 pub async fn handle_hvx(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GATTS: HVX requested");
 
-    if payload.len() < 7 {
-        debug!("GATTS: Invalid payload length: {} (expected >= 7)", payload.len());
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let mut reader = PayloadReader::new(payload);
-
-    let conn_handle = reader.read_u16()?;
-    let char_handle = reader.read_u16()?;
-    let hvx_type = reader.read_u8()?;
-    let data_length = reader.read_u16()? as usize;
+    let GattsHvxRequest {
+        conn_handle,
+        char_handle,
+        hvx_type,
+        data,
+    } = decode_request(payload)?;
 
     debug!(
         "GATTS: HVX - conn: {}, char: {}, type: {}, len: {}",
-        conn_handle, char_handle, hvx_type, data_length
+        conn_handle,
+        char_handle,
+        hvx_type,
+        data.len()
     );
 
-    if reader.remaining() < data_length {
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let data = reader.read_slice(data_length)?;
-
     // Send notification or indication using the notification service
     let result = match hvx_type {
         0x01 => {
@@ -313,7 +208,7 @@ pub async fn handle_hvx(payload: &[u8]) -> Result<TxPacket, CommandError> {
         }
         Err(e) => {
             error!("GATTS: Failed to send HVX: {:?}", e);
-            return ResponseBuilder::build_error(CommandError::SoftDeviceError);
+            return ResponseBuilder::build_error(e.into());
         }
     }
 
@@ -327,15 +222,7 @@ pub async fn handle_hvx(payload: &[u8]) -> Result<TxPacket, CommandError> {
 pub async fn handle_mtu_reply(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GATTS: MTU_REPLY requested");
 
-    if payload.len() < 4 {
-        debug!("GATTS: Invalid payload length: {} (expected 4)", payload.len());
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let mut reader = PayloadReader::new(payload);
-
-    let conn_handle = reader.read_u16()?;
-    let mtu = reader.read_u16()?;
+    let GattsMtuReplyRequest { conn_handle, mtu } = decode_request(payload)?;
 
     debug!("GATTS: MTU reply - conn: {}, MTU: {}", conn_handle, mtu);
 
@@ -369,24 +256,14 @@ pub async fn handle_mtu_reply(payload: &[u8]) -> Result<TxPacket, CommandError>
 pub async fn handle_sys_attr_set(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("GATTS: SYS_ATTR_SET requested");
 
-    if payload.len() < 4 {
-        debug!("GATTS: Invalid payload length: {} (expected >= 4)", payload.len());
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let mut reader = PayloadReader::new(payload);
-
-    let conn_handle = reader.read_u16()?;
-    let attr_length = reader.read_u16()? as usize;
+    let GattsSysAttrSetRequest {
+        conn_handle,
+        sys_attr: sys_attr_data,
+    } = decode_request(payload)?;
+    let attr_length = sys_attr_data.len();
 
     debug!("GATTS: Sys attr - conn: {}, len: {}", conn_handle, attr_length);
 
-    if reader.remaining() < attr_length {
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    let sys_attr_data = reader.read_slice(attr_length)?;
-
     // Store system attributes in the bonding service
     match crate::ble::bonding::set_system_attributes(conn_handle, sys_attr_data).await {
         Ok(()) => {
@@ -3,17 +3,26 @@
 //! This module handles all BLE modem commands received from the host.
 //! Commands are routed to appropriate handlers and responses are sent back.
 
+use core::cell::Cell;
+
 use defmt::{debug, error, Format};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
 use heapless::Vec;
 use nrf_softdevice::Softdevice;
 
-use crate::core::memory::{BufferError, TxPacket};
-use crate::core::protocol::serialization::*;
-use crate::core::protocol::{Packet, ProtocolError, RequestCode, ResponseCode, MAX_PAYLOAD_SIZE};
-use crate::core::transport;
+use crate::ble::manager::ServiceCreateError;
+use crate::ble::notifications::NotificationError;
+use crate::core::memory::{BufferError, TxPacket, TxPacketBuilder};
+use crate::core::protocol::codec::Request;
+use crate::core::protocol::error::{ErrorCategory, ErrorResponse, ERROR_RESPONSE_SIZE};
+use crate::core::protocol::{Packet, ProtocolError, RequestCode, ResponseCode};
+use crate::core::transport::{self, SpiError, Transport};
 
+pub mod batch;
 pub mod gap;
 pub mod gatts;
+pub mod registry;
 pub mod system;
 pub mod uuid;
 
@@ -25,8 +34,38 @@ pub enum CommandError {
     BufferError(BufferError),
     ProtocolError(ProtocolError),
     StateError(crate::ble::gatt_state::StateError),
-    SoftDeviceError,
+    /// SoftDevice call failed with this `NRF_ERROR_*` code
+    SoftDeviceError(u32),
     NotImplemented,
+    ServiceError(ServiceCreateError),
+    NotificationError(NotificationError),
+}
+
+impl CommandError {
+    /// Structured error payload for the host
+    ///
+    /// The detail byte is the inner variant's declaration index, so variants of
+    /// the wrapped error enums must only ever be appended.
+    pub fn to_response(self, request_code: u16) -> ErrorResponse {
+        let (category, detail, nrf_error) = match self {
+            CommandError::UnknownCommand => (ErrorCategory::UnknownCommand, 0, 0),
+            CommandError::InvalidPayload => (ErrorCategory::InvalidPayload, 0, 0),
+            CommandError::BufferError(err) => (ErrorCategory::Buffer, err as u8, 0),
+            CommandError::ProtocolError(err) => (ErrorCategory::Protocol, err as u8, 0),
+            CommandError::StateError(err) => (ErrorCategory::State, err as u8, 0),
+            CommandError::SoftDeviceError(code) => (ErrorCategory::SoftDevice, 0, code),
+            CommandError::NotImplemented => (ErrorCategory::NotImplemented, 0, 0),
+            CommandError::ServiceError(err) => (ErrorCategory::Service, err as u8, 0),
+            CommandError::NotificationError(err) => (ErrorCategory::Notification, err as u8, 0),
+        };
+
+        ErrorResponse {
+            category: category as u16,
+            request_code,
+            detail,
+            nrf_error,
+        }
+    }
 }
 
 impl From<BufferError> for CommandError {
@@ -47,38 +86,104 @@ impl From<crate::ble::gatt_state::StateError> for CommandError {
     }
 }
 
+impl From<ServiceCreateError> for CommandError {
+    fn from(err: ServiceCreateError) -> Self {
+        CommandError::ServiceError(err)
+    }
+}
+
+impl From<NotificationError> for CommandError {
+    fn from(err: NotificationError) -> Self {
+        CommandError::NotificationError(err)
+    }
+}
+
+/// Request currently being processed
+#[derive(Clone, Copy)]
+struct RequestContext {
+    /// Raw request code (reported in error responses)
+    code: u16,
+    /// Sequence number responses echo
+    seq: Option<u16>,
+}
+
+/// Context of the request currently being processed
+///
+/// Commands are processed one at a time, so responses built while a request is
+/// in flight echo its sequence number (or use legacy framing if it had none).
+static CURRENT_REQUEST: Mutex<CriticalSectionRawMutex, Cell<RequestContext>> =
+    Mutex::new(Cell::new(RequestContext { code: 0, seq: None }));
+
+/// Get the sequence number responses should echo
+pub fn current_seq() -> Option<u16> {
+    CURRENT_REQUEST.lock(|request| request.get().seq)
+}
+
+/// Get the raw code of the request being processed
+pub fn current_request_code() -> u16 {
+    CURRENT_REQUEST.lock(|request| request.get().code)
+}
+
+/// Report errors against `code` while keeping the request's sequence number
+fn set_current_code(code: u16) {
+    CURRENT_REQUEST.lock(|request| {
+        let seq = request.get().seq;
+        request.set(RequestContext { code, seq })
+    });
+}
+
+fn set_current_request(packet: &Packet) {
+    CURRENT_REQUEST.lock(|request| {
+        request.set(RequestContext {
+            code: packet.code,
+            seq: packet.seq,
+        })
+    });
+}
+
 /// Command response builder
+///
+/// Writes the response straight into TX pool buffers (see `TxPacketBuilder`),
+/// framed with the sequence number of the request being processed.
 pub struct ResponseBuilder {
-    buffer: Vec<u8, MAX_PAYLOAD_SIZE>,
+    packet: TxPacketBuilder,
 }
 
 impl ResponseBuilder {
     /// Create a new response builder
     pub fn new() -> Self {
-        Self { buffer: Vec::new() }
+        Self {
+            packet: TxPacketBuilder::new(current_seq()),
+        }
     }
 
     /// Add a u8 to the response
     pub fn add_u8(&mut self, value: u8) -> Result<&mut Self, CommandError> {
-        write_u8(&mut self.buffer, value)?;
-        Ok(self)
+        self.add_slice(&[value])
     }
 
     /// Add a u16 to the response
     pub fn add_u16(&mut self, value: u16) -> Result<&mut Self, CommandError> {
-        write_u16(&mut self.buffer, value)?;
-        Ok(self)
+        self.add_slice(&value.to_be_bytes())
     }
 
     /// Add a u32 to the response
     pub fn add_u32(&mut self, value: u32) -> Result<&mut Self, CommandError> {
-        write_u32(&mut self.buffer, value)?;
-        Ok(self)
+        self.add_slice(&value.to_be_bytes())
+    }
+
+    /// Add a u64 to the response
+    pub fn add_u64(&mut self, value: u64) -> Result<&mut Self, CommandError> {
+        self.add_slice(&value.to_be_bytes())
     }
 
     /// Add a byte slice to the response
     pub fn add_slice(&mut self, data: &[u8]) -> Result<&mut Self, CommandError> {
-        write_slice(&mut self.buffer, data)?;
+        self.packet.write(data).map_err(|e| match e {
+            // Payloads beyond MAX_MESSAGE_SIZE fail as they did before responses were pooled
+            BufferError::BufferTooSmall => CommandError::ProtocolError(ProtocolError::BufferFull),
+            e => CommandError::BufferError(e),
+        })?;
         Ok(self)
     }
 
@@ -87,12 +192,9 @@ impl ResponseBuilder {
         self.add_slice(s.as_bytes())
     }
 
-    /// Build the response packet
+    /// Build the response packet, fragmenting it if it exceeds a single frame
     pub fn build(self, response_code: ResponseCode) -> Result<TxPacket, CommandError> {
-        let packet = Packet::new_response(response_code, &self.buffer)?;
-        let serialized = packet.serialize()?;
-        let tx_packet = TxPacket::new(&serialized)?;
-        Ok(tx_packet)
+        Ok(self.packet.finish(response_code.to_u16())?)
     }
 
     /// Build an ACK response with no payload
@@ -107,18 +209,17 @@ impl ResponseBuilder {
         builder.build(ResponseCode::Error)
     }
 
-    /// Build an error response from CommandError
+    /// Build a structured error response from CommandError
+    ///
+    /// Payload format:
+    /// [Error Category (2)] [Request Code (2)] [Detail (1)] [NRF Error (4)]
     pub fn build_error(error: CommandError) -> Result<TxPacket, CommandError> {
-        let error_code = match error {
-            CommandError::UnknownCommand => 0x01,
-            CommandError::InvalidPayload => 0x02,
-            CommandError::BufferError(_) => 0x03,
-            CommandError::ProtocolError(_) => 0x04,
-            CommandError::StateError(_) => 0x05,
-            CommandError::SoftDeviceError => 0x06,
-            CommandError::NotImplemented => 0x07,
-        };
-        Self::build_error_code(error_code)
+        let mut buffer: Vec<u8, ERROR_RESPONSE_SIZE> = Vec::new();
+        error.to_response(current_request_code()).encode(&mut buffer)?;
+
+        let mut builder = Self::new();
+        builder.add_slice(&buffer)?;
+        builder.build(ResponseCode::Error)
     }
 }
 
@@ -128,91 +229,56 @@ impl Default for ResponseBuilder {
     }
 }
 
-/// Process a command packet and send response
-pub async fn process_command(packet: Packet, sd: &Softdevice) -> Result<(), CommandError> {
-    let request_code = packet.request_code().ok_or(CommandError::UnknownCommand)?;
+/// Decode a typed request payload from the protocol table
+///
+/// Short or malformed payloads are rejected with `InvalidPayload`.
+pub fn decode_request<'a, R: Request<'a>>(payload: &'a [u8]) -> Result<R, CommandError> {
+    R::decode(payload).map_err(|e| {
+        debug!(
+            "Invalid {:?} payload: {} bytes (expected >= {}): {:?}",
+            R::CODE,
+            payload.len(),
+            R::MIN_LEN,
+            e
+        );
+        CommandError::InvalidPayload
+    })
+}
 
-    debug!("Processing command: {:?}", request_code);
+/// Check if a command is implemented by this firmware build
+///
+/// Reported to the host in the HELLO capability bitmap; derived from the
+/// command registry, so it agrees with the commands the dispatcher rejects.
+pub fn is_supported(code: RequestCode) -> bool {
+    registry::descriptor(code).is_some_and(|descriptor| descriptor.is_supported())
+}
 
-    let response = match request_code {
-        // System Commands
-        RequestCode::GetInfo => system::handle_get_info(&packet.payload).await,
-        RequestCode::Echo => system::handle_echo(&packet.payload).await,
-        RequestCode::Shutdown => system::handle_shutdown(&packet.payload).await,
-        RequestCode::Reboot => system::handle_reboot(&packet.payload).await,
-        
-        // Event Management Commands
-        RequestCode::RegisterEventCallback => system::handle_register_event_callback(&packet.payload).await,
-        RequestCode::ClearEventCallbacks => system::handle_clear_event_callbacks(&packet.payload).await,
-
-        // UUID Management
-        RequestCode::RegisterUuidGroup => uuid::handle_register_uuid_group(&packet.payload).await,
-
-        // GAP Operations - Address Management
-        RequestCode::GapGetAddr => gap::handle_get_addr(&packet.payload).await,
-        RequestCode::GapSetAddr => gap::handle_set_addr(&packet.payload).await,
-
-        // GAP Operations - Advertising Control
-        RequestCode::GapAdvStart => gap::handle_adv_start(&packet.payload, sd).await,
-        RequestCode::GapAdvStop => gap::handle_adv_stop(&packet.payload, sd).await,
-        RequestCode::GapAdvSetConfigure => gap::handle_adv_configure(&packet.payload).await,
-
-        // GAP Operations - Device Configuration
-        RequestCode::GapGetName => gap::handle_get_name(&packet.payload).await,
-        RequestCode::GapSetName => gap::handle_set_name(&packet.payload).await,
-        RequestCode::GapConnParamsGet => gap::handle_conn_params_get(&packet.payload).await,
-        RequestCode::GapConnParamsSet => gap::handle_conn_params_set(&packet.payload).await,
-
-        // GAP Operations - Connection Management
-        RequestCode::GapConnParamUpdate => gap::handle_conn_param_update(&packet.payload).await,
-        RequestCode::GapDataLengthUpdate => gap::handle_data_length_update(&packet.payload).await,
-        RequestCode::GapPhyUpdate => gap::handle_phy_update(&packet.payload).await,
-        RequestCode::GapDisconnect => gap::handle_disconnect(&packet.payload).await,
-
-        // GAP Operations - Power & RSSI
-        RequestCode::GapSetTxPower => gap::handle_set_tx_power(&packet.payload).await,
-        RequestCode::GapStartRssiReporting => gap::handle_start_rssi_reporting(&packet.payload).await,
-        RequestCode::GapStopRssiReporting => gap::handle_stop_rssi_reporting(&packet.payload).await,
-
-        // GATT Server Operations
-        RequestCode::GattsServiceAdd => gatts::handle_service_add(&packet.payload, sd).await,
-        RequestCode::GattsCharacteristicAdd => gatts::handle_characteristic_add(&packet.payload, sd).await,
-        RequestCode::GattsMtuReply => gatts::handle_mtu_reply(&packet.payload).await,
-        RequestCode::GattsHvx => gatts::handle_hvx(&packet.payload).await,
-        RequestCode::GattsSysAttrGet => {
-            error!("GattsSysAttrGet not implemented in original firmware");
-            ResponseBuilder::build_error(CommandError::NotImplemented)
-        }
-        RequestCode::GattsSysAttrSet => gatts::handle_sys_attr_set(&packet.payload).await,
-
-        // Central mode commands (not implemented in peripheral-only configuration)
-        RequestCode::GapConnect
-        | RequestCode::GapConnectCancel
-        | RequestCode::GapScanStart
-        | RequestCode::GapScanStop
-        | RequestCode::GattcMtuRequest
-        | RequestCode::GattcServiceDiscover
-        | RequestCode::GattcCharacteristicsDiscover
-        | RequestCode::GattcDescriptorsDiscover
-        | RequestCode::GattcRead
-        | RequestCode::GattcWrite => {
-            debug!("Central mode command not supported: {:?}", request_code);
-            ResponseBuilder::build_error(CommandError::NotImplemented)
+/// Process a command packet and send the response over `link`
+pub async fn process_command<T: Transport>(packet: Packet, sd: &Softdevice, link: &T) -> Result<(), CommandError> {
+    set_current_request(&packet);
+
+    let Some(request_code) = packet.request_code() else {
+        error!("Unknown command code: {:#06x}", packet.code);
+        if let Ok(error_packet) = ResponseBuilder::build_error(CommandError::UnknownCommand) {
+            let _ = send_response(link, packet.seq, error_packet).await;
         }
+        return Err(CommandError::UnknownCommand);
     };
 
-    match response {
+    debug!("Processing command: {:?}", request_code);
+
+    match run_request(request_code, &packet.payload, sd).await {
         Ok(tx_packet) => {
             debug!("Command processed successfully, sending response");
-            transport::send_response(tx_packet)
+            send_response(link, packet.seq, tx_packet)
                 .await
                 .map_err(|_| CommandError::BufferError(BufferError::PoolExhausted))?;
         }
         Err(e) => {
             error!("Command processing failed: {:?}", e);
             // Try to send error response
-            if let Ok(error_packet) = ResponseBuilder::build_error(CommandError::UnknownCommand) {
-                let _ = transport::send_response(error_packet).await;
+            if let Ok(error_packet) = ResponseBuilder::build_error(e) {
+                let _ = send_response(link, packet.seq, error_packet).await;
             }
             return Err(e);
         }
@@ -221,6 +287,36 @@ pub async fn process_command(packet: Packet, sd: &Softdevice) -> Result<(), Comm
     Ok(())
 }
 
+/// Send the response to a request, keeping it in case the host retransmits the request
+async fn send_response<T: Transport>(link: &T, seq: Option<u16>, response: TxPacket) -> Result<(), SpiError> {
+    if let Some(seq) = seq {
+        transport::remember_response(seq, &response);
+    }
+    link.send_frame(response).await
+}
+
+/// Run a single command and build its response
+///
+/// Checks the request against the command registry before calling its
+/// handler. Used for the sub-commands of a batch, where BATCH is rejected.
+pub async fn execute_command(
+    request_code: RequestCode,
+    payload: &[u8],
+    sd: &Softdevice,
+) -> Result<TxPacket, CommandError> {
+    registry::dispatch(request_code, payload, sd).await
+}
+
+/// Run a top-level request, including BATCH
+async fn run_request(request_code: RequestCode, payload: &[u8], sd: &Softdevice) -> Result<TxPacket, CommandError> {
+    if request_code == RequestCode::Batch {
+        registry::check(request_code, payload)?;
+        batch::handle_batch(payload, sd).await
+    } else {
+        execute_command(request_code, payload, sd).await
+    }
+}
+
 /// Command processor state
 pub struct CommandProcessor {
     // Future: Add command processing state/statistics here
@@ -238,72 +334,7 @@ impl CommandProcessor {
 
         debug!("Processing command: {:?}", request_code);
 
-        match request_code {
-            // System Commands
-            RequestCode::GetInfo => system::handle_get_info(&packet.payload).await,
-            RequestCode::Echo => system::handle_echo(&packet.payload).await,
-            RequestCode::Shutdown => system::handle_shutdown(&packet.payload).await,
-            RequestCode::Reboot => system::handle_reboot(&packet.payload).await,
-            
-            // Event Management Commands
-            RequestCode::RegisterEventCallback => system::handle_register_event_callback(&packet.payload).await,
-            RequestCode::ClearEventCallbacks => system::handle_clear_event_callbacks(&packet.payload).await,
-
-            // UUID Management
-            RequestCode::RegisterUuidGroup => uuid::handle_register_uuid_group(&packet.payload).await,
-
-            // GAP Operations - Address Management
-            RequestCode::GapGetAddr => gap::handle_get_addr(&packet.payload).await,
-            RequestCode::GapSetAddr => gap::handle_set_addr(&packet.payload).await,
-
-            // GAP Operations - Advertising Control
-            RequestCode::GapAdvStart => gap::handle_adv_start(&packet.payload, sd).await,
-            RequestCode::GapAdvStop => gap::handle_adv_stop(&packet.payload, sd).await,
-            RequestCode::GapAdvSetConfigure => gap::handle_adv_configure(&packet.payload).await,
-
-            // GAP Operations - Device Configuration
-            RequestCode::GapGetName => gap::handle_get_name(&packet.payload).await,
-            RequestCode::GapSetName => gap::handle_set_name(&packet.payload).await,
-            RequestCode::GapConnParamsGet => gap::handle_conn_params_get(&packet.payload).await,
-            RequestCode::GapConnParamsSet => gap::handle_conn_params_set(&packet.payload).await,
-
-            // GAP Operations - Connection Management
-            RequestCode::GapConnParamUpdate => gap::handle_conn_param_update(&packet.payload).await,
-            RequestCode::GapDataLengthUpdate => gap::handle_data_length_update(&packet.payload).await,
-            RequestCode::GapPhyUpdate => gap::handle_phy_update(&packet.payload).await,
-            RequestCode::GapDisconnect => gap::handle_disconnect(&packet.payload).await,
-
-            // GAP Operations - Power & RSSI
-            RequestCode::GapSetTxPower => gap::handle_set_tx_power(&packet.payload).await,
-            RequestCode::GapStartRssiReporting => gap::handle_start_rssi_reporting(&packet.payload).await,
-            RequestCode::GapStopRssiReporting => gap::handle_stop_rssi_reporting(&packet.payload).await,
-
-            // GATT Server Operations
-            RequestCode::GattsServiceAdd => gatts::handle_service_add(&packet.payload, sd).await,
-            RequestCode::GattsCharacteristicAdd => gatts::handle_characteristic_add(&packet.payload, sd).await,
-            RequestCode::GattsMtuReply => gatts::handle_mtu_reply(&packet.payload).await,
-            RequestCode::GattsHvx => gatts::handle_hvx(&packet.payload).await,
-            RequestCode::GattsSysAttrGet => {
-                error!("GattsSysAttrGet not implemented in original firmware");
-                ResponseBuilder::build_error(CommandError::NotImplemented)
-            }
-            RequestCode::GattsSysAttrSet => gatts::handle_sys_attr_set(&packet.payload).await,
-
-            // Central mode commands (not implemented in peripheral-only configuration)
-            RequestCode::GapConnect
-            | RequestCode::GapConnectCancel
-            | RequestCode::GapScanStart
-            | RequestCode::GapScanStop
-            | RequestCode::GattcMtuRequest
-            | RequestCode::GattcServiceDiscover
-            | RequestCode::GattcCharacteristicsDiscover
-            | RequestCode::GattcDescriptorsDiscover
-            | RequestCode::GattcRead
-            | RequestCode::GattcWrite => {
-                debug!("Central mode command not supported: {:?}", request_code);
-                ResponseBuilder::build_error(CommandError::NotImplemented)
-            }
-        }
+        run_request(request_code, &packet.payload, sd).await
     }
 }
 
@@ -312,12 +343,17 @@ impl CommandProcessor {
 pub async fn command_processor_task(sd: &'static Softdevice) {
     defmt::info!("Starting command processor task");
 
+    run_dispatcher(&transport::HOST_LINK, sd).await
+}
+
+/// Receive and process commands from `link` forever
+pub async fn run_dispatcher<T: Transport>(link: &T, sd: &Softdevice) -> ! {
     loop {
-        // Wait for command from SPI
-        let packet = transport::receive_command().await;
+        // Wait for command from the host
+        let packet = link.receive_frame().await;
 
         // Process the command
-        if let Err(e) = process_command(packet, sd).await {
+        if let Err(e) = process_command(packet, sd, link).await {
             error!("Command processing error: {:?}", e);
         }
     }
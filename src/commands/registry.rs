@@ -0,0 +1,244 @@
+//! Command Registry
+//!
+//! Every request code the firmware answers is listed once in [`COMMANDS`] with
+//! its handler and the metadata the dispatcher checks before calling it: the
+//! minimum payload length (from the protocol table), the GAP role it needs and
+//! whether the handler drives the SoftDevice directly.
+//!
+//! Entry syntax: `Variant => handler [Role]`, `[Role, softdevice]` for
+//! handlers taking the `Softdevice`, or `[Role, unimplemented]` for commands
+//! answered with `NotImplemented`.
+
+use defmt::{debug, Format};
+use nrf_softdevice::{raw, Softdevice};
+
+use super::{gap, gatts, system, uuid, CommandError};
+use crate::core::memory::TxPacket;
+use crate::core::protocol::RequestCode;
+
+/// GAP role a command needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Format)]
+pub enum Role {
+    /// Usable in any role
+    Any,
+    /// Needs the peripheral role (advertising)
+    Peripheral,
+    /// Needs the central role (scanning, connecting, GATT client)
+    Central,
+}
+
+impl Role {
+    /// Check if this firmware build supports the role
+    ///
+    /// The firmware is peripheral-only; central commands are rejected.
+    pub const fn is_supported(self) -> bool {
+        !matches!(self, Role::Central)
+    }
+}
+
+/// Dispatcher metadata of a command
+#[derive(Debug, Clone, Copy, Format)]
+pub struct CommandDescriptor {
+    pub code: RequestCode,
+    /// Payloads shorter than this are rejected with `InvalidPayload`
+    pub min_len: usize,
+    pub role: Role,
+    /// Handler is passed the `Softdevice`, which must be enabled
+    pub needs_softdevice: bool,
+    /// Handler exists in this firmware build
+    pub implemented: bool,
+}
+
+impl CommandDescriptor {
+    /// Check if the command is answered by this firmware build
+    pub const fn is_supported(&self) -> bool {
+        self.implemented && self.role.is_supported()
+    }
+
+    /// Check a request against the descriptor before its handler runs
+    pub fn check(&self, payload: &[u8]) -> Result<(), CommandError> {
+        if !self.is_supported() {
+            debug!("{:?} not supported (role {:?})", self.code, self.role);
+            return Err(CommandError::NotImplemented);
+        }
+
+        if payload.len() < self.min_len {
+            debug!(
+                "Invalid {:?} payload: {} bytes (expected >= {})",
+                self.code,
+                payload.len(),
+                self.min_len
+            );
+            return Err(CommandError::InvalidPayload);
+        }
+
+        if self.needs_softdevice && !softdevice_enabled() {
+            debug!("{:?} needs the SoftDevice, which is not enabled", self.code);
+            return Err(CommandError::SoftDeviceError(raw::NRF_ERROR_SOFTDEVICE_NOT_ENABLED));
+        }
+
+        Ok(())
+    }
+}
+
+fn softdevice_enabled() -> bool {
+    let mut enabled: u8 = 0;
+    let ret = unsafe { raw::sd_softdevice_is_enabled(&mut enabled) };
+    ret == raw::NRF_SUCCESS && enabled != 0
+}
+
+macro_rules! command_table {
+    (
+        $(
+            $(#[$meta:meta])*
+            $variant:ident => $handler:path [ $role:ident $(, $kind:ident)? ]
+        ),* $(,)?
+    ) => {
+        /// Descriptors of every request code
+        pub static COMMANDS: &[CommandDescriptor] = &[
+            $(
+                CommandDescriptor {
+                    code: RequestCode::$variant,
+                    min_len: RequestCode::$variant.min_payload_len(),
+                    role: Role::$role,
+                    needs_softdevice: command_table!(@softdevice $($kind)?),
+                    implemented: command_table!(@implemented $($kind)?),
+                },
+            )*
+        ];
+
+        /// Call the handler of a command (after [`CommandDescriptor::check`])
+        async fn call_handler(
+            request_code: RequestCode,
+            payload: &[u8],
+            sd: &Softdevice,
+        ) -> Result<TxPacket, CommandError> {
+            match request_code {
+                $(
+                    $(#[$meta])*
+                    RequestCode::$variant => command_table!(@call $handler, payload, sd $(, $kind)?),
+                )*
+            }
+        }
+    };
+
+    (@softdevice) => { false };
+    (@softdevice softdevice) => { true };
+    (@softdevice unimplemented) => { false };
+
+    (@implemented) => { true };
+    (@implemented softdevice) => { true };
+    (@implemented unimplemented) => { false };
+
+    (@call $handler:path, $payload:ident, $sd:ident) => { $handler($payload).await };
+    (@call $handler:path, $payload:ident, $sd:ident, softdevice) => { $handler($payload, $sd).await };
+    (@call $handler:path, $payload:ident, $sd:ident, unimplemented) => { $handler($payload).await };
+}
+
+command_table! {
+    // System Commands
+    GetInfo => system::handle_get_info [Any],
+    Echo => system::handle_echo [Any],
+    Shutdown => system::handle_shutdown [Any],
+    Reboot => system::handle_reboot [Any],
+
+    // Capability Negotiation
+    Hello => system::handle_hello [Any],
+
+    // Event Subscription
+    SetEventMask => system::handle_set_event_mask [Any],
+    GetEventMask => system::handle_get_event_mask [Any],
+    SetCharEventFilter => system::handle_set_char_event_filter [Any],
+    ClearCharEventFilters => system::handle_clear_char_event_filters [Any],
+    GetClock => system::handle_get_clock [Any],
+
+    // Batches are run by process_command and cannot be nested
+    Batch => nested_batch [Any],
+
+    // Diagnostics
+    GetLinkStats => system::handle_get_link_stats [Any],
+    GetPoolStats => system::handle_get_pool_stats [Any],
+
+    // Maintenance
+    FactoryReset => system::handle_factory_reset [Any],
+
+    // UUID Management
+    RegisterUuidGroup => uuid::handle_register_uuid_group [Any],
+
+    // GAP Operations - Address Management
+    GapGetAddr => gap::handle_get_addr [Any],
+    GapSetAddr => gap::handle_set_addr [Any],
+
+    // GAP Operations - Advertising Control
+    GapAdvStart => gap::handle_adv_start [Peripheral, softdevice],
+    GapAdvStop => gap::handle_adv_stop [Peripheral, softdevice],
+    GapAdvSetConfigure => gap::handle_adv_configure [Peripheral],
+
+    // GAP Operations - Device Configuration
+    GapGetName => gap::handle_get_name [Any],
+    GapSetName => gap::handle_set_name [Any],
+    GapConnParamsGet => gap::handle_conn_params_get [Any],
+    GapConnParamsSet => gap::handle_conn_params_set [Any],
+
+    // GAP Operations - Connection Management
+    GapConnParamUpdate => gap::handle_conn_param_update [Any],
+    GapDataLengthUpdate => gap::handle_data_length_update [Any],
+    GapPhyUpdate => gap::handle_phy_update [Any],
+    GapDisconnect => gap::handle_disconnect [Any],
+
+    // GAP Operations - Power & RSSI
+    GapSetTxPower => gap::handle_set_tx_power [Any],
+    GapStartRssiReporting => gap::handle_start_rssi_reporting [Any],
+    GapStopRssiReporting => gap::handle_stop_rssi_reporting [Any],
+
+    // GAP Operations - Central (not implemented in peripheral-only configuration)
+    GapConnect => not_implemented [Central],
+    GapConnectCancel => not_implemented [Central],
+    GapScanStart => not_implemented [Central],
+    GapScanStop => not_implemented [Central],
+
+    // GATT Server Operations
+    GattsServiceAdd => gatts::handle_service_add [Any, softdevice],
+    GattsCharacteristicAdd => gatts::handle_characteristic_add [Any, softdevice],
+    GattsMtuReply => gatts::handle_mtu_reply [Any],
+    GattsHvx => gatts::handle_hvx [Any],
+    GattsSysAttrGet => not_implemented [Any, unimplemented],
+    GattsSysAttrSet => gatts::handle_sys_attr_set [Any],
+
+    // GATT Client Operations (not implemented in peripheral-only configuration)
+    GattcMtuRequest => not_implemented [Central],
+    GattcServiceDiscover => not_implemented [Central],
+    GattcCharacteristicsDiscover => not_implemented [Central],
+    GattcDescriptorsDiscover => not_implemented [Central],
+    GattcRead => not_implemented [Central],
+    GattcWrite => not_implemented [Central],
+}
+
+/// Look up the descriptor of a request code
+pub fn descriptor(code: RequestCode) -> Option<&'static CommandDescriptor> {
+    COMMANDS.iter().find(|descriptor| descriptor.code == code)
+}
+
+/// Check a request against its descriptor and run its handler
+///
+/// BATCH is rejected here; top-level batches are run by `process_command`.
+pub async fn dispatch(request_code: RequestCode, payload: &[u8], sd: &Softdevice) -> Result<TxPacket, CommandError> {
+    check(request_code, payload)?;
+    call_handler(request_code, payload, sd).await
+}
+
+/// Check a request against its descriptor without running it
+pub fn check(request_code: RequestCode, payload: &[u8]) -> Result<(), CommandError> {
+    descriptor(request_code)
+        .ok_or(CommandError::NotImplemented)?
+        .check(payload)
+}
+
+async fn nested_batch(_payload: &[u8]) -> Result<TxPacket, CommandError> {
+    debug!("Nested batch rejected");
+    Err(CommandError::InvalidPayload)
+}
+
+async fn not_implemented(_payload: &[u8]) -> Result<TxPacket, CommandError> {
+    Err(CommandError::NotImplemented)
+}
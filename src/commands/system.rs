@@ -1,31 +1,173 @@
 //! System Commands Implementation
 //!
 //! Handles system-level commands:
-//! - REQ_GET_INFO: Get firmware version
-//! - REQ_SHUTDOWN: Power down system  
-//! - REQ_REBOOT: System reset
+//! - REQ_GET_INFO: Firmware version and device identity
+//! - REQ_HELLO: Capability and version negotiation
+//! - REQ_SET_EVENT_MASK / REQ_GET_EVENT_MASK: Event subscription
+//! - REQ_GET_CLOCK: Device uptime for event timestamp alignment
+//! - REQ_GET_LINK_STATS: Link traffic and failure counters
+//! - REQ_GET_POOL_STATS: TX buffer pool usage
+//! - REQ_SHUTDOWN: Enter System OFF with a wake pin
+//! - REQ_REBOOT: System reset, optionally into the bootloader
 
-use defmt::{info, warn};
+use defmt::{debug, info, warn};
+use embassy_time::{Duration, Instant, Timer};
+use heapless::Vec;
 
-use crate::commands::{CommandError, ResponseBuilder};
-use crate::core::memory::TxPacket;
-use crate::ble::events::{register_event_callback, clear_event_callbacks, EventCallbackFn};
+use crate::ble::connection::{self, MAX_ATT_MTU, MAX_CONNECTIONS};
+use crate::ble::gatt_state::{self, ModemState, StateError};
+use crate::ble::registry::{self, MAX_CHARACTERISTICS, MAX_SERVICES};
+use crate::ble::{advertising, bonding, events};
+use crate::board;
+use crate::commands::{decode_request, is_supported, CommandError, ResponseBuilder};
+use crate::core::memory::{self, TxPacket, TX_POOL_SIZE};
+use crate::core::power::{self, WakeConfig};
+use crate::core::protocol::capabilities::{Capabilities, CommandBitmap, SoftDeviceVersion};
+use crate::core::protocol::events::EventMask;
+use crate::core::protocol::identity::DeviceIdentity;
+use crate::core::protocol::link::LINK_STATS_SIZE;
+use crate::core::protocol::pool::POOL_STATS_SIZE;
+use crate::core::protocol::power::{
+    RebootMode, ResetReason, WakePolarity, FACTORY_RESET_TOKEN, WAKE_PIN_LINK, WAKE_PIN_MAX,
+};
+use crate::core::protocol::requests::{
+    FactoryResetRequest, GetLinkStatsRequest, GetPoolStatsRequest, HelloRequest, RebootRequest,
+    SetCharEventFilterRequest, SetEventMaskRequest, ShutdownRequest,
+};
+use crate::core::protocol::{RequestCode, MAX_MESSAGE_SIZE, MAX_PAYLOAD_SIZE, PROTOCOL_VERSION};
+use crate::core::transport;
 
 /// Firmware version in BCD format (matches original C implementation)
 const FIRMWARE_VERSION_BCD: u32 = 0x0001; // Version 0.01
 
+/// Build identifier (git revision), set by build.rs
+const BUILD_ID: &str = env!("BLE_MODEM_BUILD_ID");
+
+/// Firmware crate version from Cargo.toml
+const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 /// Handle GET_INFO command (0x0001)
-/// Returns firmware version in BCD format
+/// Returns the firmware version in BCD format followed by the device identity
+///
+/// Response format: see `ble_modem_protocol::identity`
 pub async fn handle_get_info(_payload: &[u8]) -> Result<TxPacket, CommandError> {
     info!("System: GET_INFO requested");
 
+    let (device_id, addr_type, factory_address) = factory_identity();
+    let identity = DeviceIdentity {
+        firmware_version: FIRMWARE_VERSION_BCD,
+        device_id,
+        addr_type,
+        factory_address,
+        softdevice: softdevice_version(),
+        crate_version: CRATE_VERSION.try_into().unwrap_or_default(),
+        build_id: BUILD_ID.try_into().unwrap_or_default(),
+    };
+
+    let mut encoded: Vec<u8, MAX_PAYLOAD_SIZE> = Vec::new();
+    identity.encode(&mut encoded)?;
+
+    info!(
+        "System: Firmware 0x{:08X} ({}, {}), device ID {:016X}",
+        FIRMWARE_VERSION_BCD, CRATE_VERSION, BUILD_ID, device_id
+    );
+
     let mut response = ResponseBuilder::new();
-    response.add_u32(FIRMWARE_VERSION_BCD)?;
+    response.add_slice(&encoded)?;
+    response.build(crate::core::protocol::ResponseCode::Ack)
+}
+
+/// Read the device ID and factory address programmed in FICR
+///
+/// The address is returned LSB first; a random factory address gets the two
+/// top bits set, as the SoftDevice does when it uses it as a static address.
+fn factory_identity() -> (u64, u8, [u8; 6]) {
+    let ficr = embassy_nrf::pac::FICR;
+
+    // DEVICEID[1] is the high word, as in nrfjprog and Zephyr hwinfo
+    let device_id = (u64::from(ficr.deviceid(1).read()) << 32) | u64::from(ficr.deviceid(0).read());
+    let addr_type = (ficr.deviceaddrtype().read().0 & 1) as u8;
+
+    let low = ficr.deviceaddr(0).read().to_le_bytes();
+    let high = ficr.deviceaddr(1).read().to_le_bytes();
+    let mut address = [low[0], low[1], low[2], low[3], high[0], high[1]];
+    if addr_type == 1 {
+        address[5] |= 0xC0;
+    }
+
+    (device_id, addr_type, address)
+}
+
+/// Handle HELLO command (0x0006)
+/// Returns protocol version, supported commands, limits and build information
+///
+/// Payload format:
+/// [Host Protocol Version (2)] (optional, logged only)
+///
+/// Response format: see `ble_modem_protocol::capabilities`
+pub async fn handle_hello(payload: &[u8]) -> Result<TxPacket, CommandError> {
+    let HelloRequest { host_version } = decode_request(payload)?;
+    match host_version {
+        Some(host_version) => {
+            info!(
+                "System: HELLO from host protocol v{} (device v{})",
+                host_version, PROTOCOL_VERSION
+            )
+        }
+        None => info!("System: HELLO requested"),
+    }
+
+    // A new host session may reuse sequence numbers
+    transport::clear_duplicate_window();
+
+    let mut commands = CommandBitmap::new();
+    for &code in RequestCode::ALL.iter().filter(|code| is_supported(**code)) {
+        commands.insert(code);
+    }
+
+    let capabilities = Capabilities {
+        protocol_version: PROTOCOL_VERSION,
+        firmware_version: FIRMWARE_VERSION_BCD,
+        max_message_size: MAX_MESSAGE_SIZE as u16,
+        max_services: MAX_SERVICES as u8,
+        max_characteristics: MAX_CHARACTERISTICS as u8,
+        tx_pool_size: TX_POOL_SIZE as u8,
+        max_mtu: MAX_ATT_MTU,
+        max_connections: MAX_CONNECTIONS as u8,
+        softdevice: softdevice_version(),
+        commands,
+        build_id: BUILD_ID.try_into().unwrap_or_default(),
+    };
 
-    info!("System: Returning firmware version 0x{:08X}", FIRMWARE_VERSION_BCD);
+    let mut encoded: Vec<u8, MAX_PAYLOAD_SIZE> = Vec::new();
+    capabilities.encode(&mut encoded)?;
+
+    let mut response = ResponseBuilder::new();
+    response.add_slice(&encoded)?;
     response.build(crate::core::protocol::ResponseCode::Ack)
 }
 
+/// Read the SoftDevice version (zeroed if the SoftDevice does not answer)
+fn softdevice_version() -> SoftDeviceVersion {
+    let mut version = nrf_softdevice::raw::ble_version_t {
+        version_number: 0,
+        company_id: 0,
+        subversion_number: 0,
+    };
+
+    let ret = unsafe { nrf_softdevice::raw::sd_ble_version_get(&mut version) };
+    if ret != nrf_softdevice::raw::NRF_SUCCESS {
+        warn!("System: sd_ble_version_get failed: {}", ret);
+        return SoftDeviceVersion::default();
+    }
+
+    SoftDeviceVersion {
+        fwid: version.subversion_number,
+        ll_version: version.version_number,
+        company_id: version.company_id,
+    }
+}
+
 /// Handle ECHO command (0x0003)
 /// Echoes back the payload data
 pub async fn handle_echo(payload: &[u8]) -> Result<TxPacket, CommandError> {
@@ -39,94 +181,243 @@ pub async fn handle_echo(payload: &[u8]) -> Result<TxPacket, CommandError> {
 }
 
 /// Handle SHUTDOWN command (0x0002)
-/// Powers down the system
-pub async fn handle_shutdown(_payload: &[u8]) -> Result<TxPacket, CommandError> {
-    warn!("System: SHUTDOWN requested");
+/// Stops advertising, disconnects every link and enters System OFF after the ACK is sent
+///
+/// Payload format:
+/// [Wake Pin (1)] [Wake Polarity (1)] (both optional, see `ble_modem_protocol::power`)
+pub async fn handle_shutdown(payload: &[u8]) -> Result<TxPacket, CommandError> {
+    let ShutdownRequest {
+        wake_pin,
+        wake_polarity,
+    } = decode_request(payload)?;
 
-    // Send ACK first
-    let response = ResponseBuilder::build_ack()?;
+    let pin = match wake_pin.unwrap_or(WAKE_PIN_LINK) {
+        WAKE_PIN_LINK => board::LINK_WAKE_PIN,
+        pin if pin <= WAKE_PIN_MAX => pin,
+        pin => {
+            debug!("System: Invalid wake pin {}", pin);
+            return Err(CommandError::InvalidPayload);
+        }
+    };
+    let polarity = match wake_polarity {
+        Some(value) => WakePolarity::from_u8(value).ok_or(CommandError::InvalidPayload)?,
+        None => WakePolarity::Low,
+    };
+
+    warn!("System: SHUTDOWN requested, wake on P0.{} {:?}", pin, polarity);
+
+    advertising::halt().await;
+    disconnect_all().await;
 
-    // Note: In a real implementation, we would initiate system shutdown here
-    // This might involve:
-    // - Gracefully closing BLE connections
-    // - Stopping advertising
-    // - Entering deep sleep or system off mode
-    // - Potentially using nrf_softdevice::raw::sd_power_system_off()
+    // The power task waits for this ACK to be sent before powering off
+    power::request_system_off(WakeConfig { pin, polarity });
+    ResponseBuilder::build_ack()
+}
 
-    warn!("System: Shutdown ACK sent - system should power down");
+/// Longest wait for links to close before SHUTDOWN powers off anyway
+const DISCONNECT_TIMEOUT_MS: u64 = 500;
 
-    // For now, we just acknowledge the command
-    // In a production system, this would trigger actual shutdown
+/// Disconnect every link and wait for the connection manager to drop them
+async fn disconnect_all() {
+    let handles: Vec<u16, MAX_CONNECTIONS> =
+        connection::with_connection_manager(|manager| manager.active_handles().collect()).await;
 
-    Ok(response)
+    for &handle in &handles {
+        let ret = unsafe {
+            nrf_softdevice::raw::sd_ble_gap_disconnect(
+                handle,
+                nrf_softdevice::raw::BLE_HCI_REMOTE_USER_TERMINATED_CONNECTION as u8,
+            )
+        };
+        if ret != nrf_softdevice::raw::NRF_SUCCESS {
+            warn!("System: Failed to disconnect handle {}: {}", handle, ret);
+        }
+    }
+
+    let deadline = Instant::now() + Duration::from_millis(DISCONNECT_TIMEOUT_MS);
+    while connection::with_connection_manager(|manager| manager.connection_count()).await > 0 {
+        if Instant::now() >= deadline {
+            warn!("System: Links still open after {}ms", DISCONNECT_TIMEOUT_MS);
+            return;
+        }
+        Timer::after_millis(5).await;
+    }
+    info!("System: {} links closed", handles.len());
 }
 
 /// Handle REBOOT command (0x00F0)
-/// Performs system reset
-pub async fn handle_reboot(_payload: &[u8]) -> Result<TxPacket, CommandError> {
-    warn!("System: REBOOT requested");
+/// Resets the modem after the ACK is sent, optionally into the bootloader
+///
+/// Payload format:
+/// [Mode (1)] (optional, see `ble_modem_protocol::power::RebootMode`)
+pub async fn handle_reboot(payload: &[u8]) -> Result<TxPacket, CommandError> {
+    let RebootRequest { mode } = decode_request(payload)?;
+    let mode = match mode {
+        Some(value) => RebootMode::from_u8(value).ok_or(CommandError::InvalidPayload)?,
+        None => RebootMode::Application,
+    };
+
+    warn!("System: REBOOT requested into {:?}", mode);
+
+    // The power task waits for this ACK to be sent before resetting
+    power::request_reset(mode, ResetReason::HostRequested);
+    ResponseBuilder::build_ack()
+}
 
-    // Send ACK first
-    let response = ResponseBuilder::build_ack()?;
+/// Handle FACTORY_RESET command (0x000F)
+/// Wipes bonds, the GATT schema and the host-set configuration, then resets after the ACK is sent
+///
+/// Nothing is kept in flash yet, so the reset also restores every other
+/// setting (event mask, advertising configuration) to its default.
+///
+/// Payload format:
+/// [Token (4)] (must be `ble_modem_protocol::power::FACTORY_RESET_TOKEN`)
+pub async fn handle_factory_reset(payload: &[u8]) -> Result<TxPacket, CommandError> {
+    let FactoryResetRequest { token } = decode_request(payload)?;
+    if token != FACTORY_RESET_TOKEN {
+        warn!("System: FACTORY_RESET rejected, wrong token {:#010x}", token);
+        return Err(CommandError::InvalidPayload);
+    }
 
-    // Note: In a real implementation, we would initiate system reset here
-    // This could be done using:
-    // - cortex_m::peripheral::SCB::sys_reset()
-    // - nrf_softdevice::raw::sd_nvic_SystemReset()
-    // - embassy_nrf's reset functionality
+    warn!("System: FACTORY_RESET requested");
 
-    warn!("System: Reboot ACK sent - system should reset");
+    advertising::halt().await;
+    disconnect_all().await;
 
-    // For safety during development, we don't actually reset
-    // In production, uncomment the following line:
-    // cortex_m::peripheral::SCB::sys_reset();
+    bonding::clear_bonded_devices().await;
+    registry::with_registry(|registry| registry.clear()).await;
+    gatt_state::with_state(|state| *state = ModemState::new()).await;
 
-    Ok(response)
+    // The power task waits for this ACK to be sent before resetting
+    power::request_reset(RebootMode::Application, ResetReason::FactoryReset);
+    ResponseBuilder::build_ack()
 }
 
-/// Handle REGISTER_EVENT_CALLBACK command 
-/// Registers a callback for BLE events
-/// 
+/// Handle GET_CLOCK command (0x000B)
+/// Returns the device uptime on the clock used for event timestamps
+///
+/// Response format:
+/// - 8 bytes: Uptime in microseconds
+pub async fn handle_get_clock(_payload: &[u8]) -> Result<TxPacket, CommandError> {
+    let uptime_us = Instant::now().as_micros();
+    debug!("System: GET_CLOCK -> {} us", uptime_us);
+
+    let mut response = ResponseBuilder::new();
+    response.add_u64(uptime_us)?;
+    response.build(crate::core::protocol::ResponseCode::Ack)
+}
+
+/// Handle GET_LINK_STATS command (0x000D)
+/// Returns the link counters, optionally zeroing them afterwards
+///
 /// Payload format:
-/// - 4 bytes: Function pointer (callback address)
-/// - 4 bytes: Context value
-pub async fn handle_register_event_callback(payload: &[u8]) -> Result<TxPacket, CommandError> {
-    info!("System: REGISTER_EVENT_CALLBACK requested");
-
-    if payload.len() != 8 {
-        warn!("System: Invalid payload length for event callback registration");
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
+/// - 1 byte: Reset counters after reading (0 or 1)
+///
+/// Response format: see `ble_modem_protocol::link`
+pub async fn handle_get_link_stats(payload: &[u8]) -> Result<TxPacket, CommandError> {
+    let GetLinkStatsRequest { reset } = decode_request(payload)?;
+
+    let stats = transport::link_stats();
+    if reset {
+        transport::reset_link_stats();
     }
+    debug!("System: GET_LINK_STATS {:?} (reset: {})", stats, reset);
 
-    // Extract callback function pointer and context
-    let callback_ptr = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
-    let context = u32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]);
+    let mut buffer: Vec<u8, LINK_STATS_SIZE> = Vec::new();
+    stats.encode(&mut buffer)?;
 
-    // Safety: This is unsafe as we're casting a raw pointer to a function pointer
-    // In a real implementation, the host would pass verified function pointers
-    let callback: EventCallbackFn = unsafe { 
-        core::mem::transmute::<*const (), EventCallbackFn>(callback_ptr as *const ()) 
-    };
+    let mut response = ResponseBuilder::new();
+    response.add_slice(&buffer)?;
+    response.build(crate::core::protocol::ResponseCode::Ack)
+}
 
-    match register_event_callback(callback, context).await {
-        Ok(()) => {
-            info!("System: Event callback registered successfully");
-            ResponseBuilder::build_ack()
-        }
-        Err(()) => {
-            warn!("System: Failed to register event callback - registry full");
-            ResponseBuilder::build_error(CommandError::InvalidPayload)
-        }
+/// Handle GET_POOL_STATS command (0x000E)
+/// Returns the TX buffer pool usage, optionally restarting peak tracking
+///
+/// Payload format:
+/// - 1 byte: Reset peak and failure count after reading (0 or 1)
+///
+/// Response format: see `ble_modem_protocol::pool`
+pub async fn handle_get_pool_stats(payload: &[u8]) -> Result<TxPacket, CommandError> {
+    let GetPoolStatsRequest { reset } = decode_request(payload)?;
+
+    // Taken before the response buffer is allocated so it is not counted
+    let stats = memory::get_stats();
+    if reset {
+        memory::reset_stats();
     }
+    debug!("System: GET_POOL_STATS {:?} (reset: {})", stats, reset);
+
+    let mut buffer: Vec<u8, POOL_STATS_SIZE> = Vec::new();
+    stats.encode(&mut buffer)?;
+
+    let mut response = ResponseBuilder::new();
+    response.add_slice(&buffer)?;
+    response.build(crate::core::protocol::ResponseCode::Ack)
+}
+
+/// Handle SET_EVENT_MASK command (0x0007)
+/// Selects the event categories forwarded to the host
+///
+/// Payload format:
+/// - 4 bytes: Event mask (see `ble_modem_protocol::events::EventMask`)
+///
+/// Response format:
+/// - 4 bytes: Event mask in effect (unknown bits cleared)
+pub async fn handle_set_event_mask(payload: &[u8]) -> Result<TxPacket, CommandError> {
+    let SetEventMaskRequest { mask } = decode_request(payload)?;
+
+    let mask = EventMask::from_bits_truncate(mask);
+    events::set_event_mask(mask).await;
+    info!("System: Event mask set to 0x{:08X}", mask.bits());
+
+    let mut response = ResponseBuilder::new();
+    response.add_u32(mask.bits())?;
+    response.build(crate::core::protocol::ResponseCode::Ack)
+}
+
+/// Handle GET_EVENT_MASK command (0x0008)
+/// Returns the event mask and the muted characteristic handles
+///
+/// Response format: see `ble_modem_protocol::events`
+pub async fn handle_get_event_mask(_payload: &[u8]) -> Result<TxPacket, CommandError> {
+    let subscription = events::event_subscription().await;
+
+    let mut encoded: Vec<u8, MAX_PAYLOAD_SIZE> = Vec::new();
+    subscription.encode(&mut encoded)?;
+
+    let mut response = ResponseBuilder::new();
+    response.add_slice(&encoded)?;
+    response.build(crate::core::protocol::ResponseCode::Ack)
 }
 
-/// Handle CLEAR_EVENT_CALLBACKS command
-/// Clears all registered event callbacks
-pub async fn handle_clear_event_callbacks(_payload: &[u8]) -> Result<TxPacket, CommandError> {
-    info!("System: CLEAR_EVENT_CALLBACKS requested");
+/// Handle SET_CHAR_EVENT_FILTER command (0x0009)
+/// Mutes or unmutes the write/read/CCCD events of one characteristic
+///
+/// Payload format:
+/// - 2 bytes: Characteristic value handle
+/// - 1 byte: Enabled (0 = mute)
+pub async fn handle_set_char_event_filter(payload: &[u8]) -> Result<TxPacket, CommandError> {
+    let SetCharEventFilterRequest { char_handle, enabled } = decode_request(payload)?;
+
+    if events::set_char_event_filter(char_handle, enabled).await.is_err() {
+        warn!("System: Event filter table full, cannot mute handle {}", char_handle);
+        return ResponseBuilder::build_error(CommandError::StateError(StateError::EventFiltersExhausted));
+    }
+
+    info!(
+        "System: Events for handle {} {}",
+        char_handle,
+        if enabled { "enabled" } else { "muted" }
+    );
+    ResponseBuilder::build_ack()
+}
 
-    clear_event_callbacks().await;
+/// Handle CLEAR_CHAR_EVENT_FILTERS command (0x000A)
+/// Unmutes every characteristic
+pub async fn handle_clear_char_event_filters(_payload: &[u8]) -> Result<TxPacket, CommandError> {
+    events::clear_char_event_filters().await;
 
-    info!("System: All event callbacks cleared");
+    info!("System: Characteristic event filters cleared");
     ResponseBuilder::build_ack()
 }
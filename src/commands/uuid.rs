@@ -6,8 +6,9 @@
 use defmt::{debug, info};
 
 use crate::ble::gatt_state::with_state;
-use crate::commands::{CommandError, ResponseBuilder};
+use crate::commands::{decode_request, CommandError, ResponseBuilder};
 use crate::core::memory::TxPacket;
+use crate::core::protocol::requests::RegisterUuidGroupRequest;
 
 /// Handle REGISTER_UUID_GROUP command (0x0010)
 /// Registers a 128-bit vendor-specific UUID base
@@ -20,14 +21,7 @@ use crate::core::memory::TxPacket;
 pub async fn handle_register_uuid_group(payload: &[u8]) -> Result<TxPacket, CommandError> {
     debug!("UUID: REGISTER_UUID_GROUP requested");
 
-    if payload.len() != 16 {
-        debug!("UUID: Invalid payload length: {} (expected 16)", payload.len());
-        return ResponseBuilder::build_error(CommandError::InvalidPayload);
-    }
-
-    // Extract UUID base from payload
-    let mut uuid_base = [0u8; 16];
-    uuid_base.copy_from_slice(payload);
+    let RegisterUuidGroupRequest { uuid_base } = decode_request(payload)?;
 
     debug!("UUID: Registering UUID base: {:02X}", uuid_base);
 
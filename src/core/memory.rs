@@ -2,28 +2,272 @@
 //!
 //! This module provides static buffer pools for TX and RX operations.
 //! Uses atomic-pool for zero-allocation buffer management.
+//!
+//! Responses and events are written straight into pool buffers by
+//! [`TxPacketBuilder`], which leaves room in front of each frame body for the
+//! header and fills in headers and CRCs in place. Pool buffers live in RAM, so
+//! link drivers hand frames to EasyDMA without copying them.
+//!
+//! atomic-pool does not expose its usage, so TX buffers are counted as they are
+//! allocated and dropped. When the pool runs low a warning is signalled once,
+//! re-armed after usage falls back to `TX_POOL_LOW_REARM`.
+
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 
 use atomic_pool::{pool, Box};
-use defmt::Format;
+use defmt::{warn, Format};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::Instant;
+use heapless::Vec;
+
+use crate::core::protocol::fragment::{FragmentHeader, FRAGMENT_CHUNK_SIZE, MAX_FRAGMENTS, MAX_REASSEMBLED_SIZE};
+pub use crate::core::protocol::pool::PoolStats;
+use crate::core::protocol::{seal_frame, FRAME_HEADROOM, SEQUENCE_SIZE};
+use crate::core::transport;
 
 /// Buffer size: BLE_EVT_LEN_MAX (247) + 2 bytes for response code
 pub const BUFFER_SIZE: usize = 249;
 
+/// TX pool buffer size: a full frame plus headroom for building its header in place
+pub const TX_BUFFER_SIZE: usize = FRAME_HEADROOM + BUFFER_SIZE;
+
 /// Number of TX buffers (matches original C implementation)
 pub const TX_POOL_SIZE: usize = 8;
 
-// TX buffer pool - 8 buffers of 249 bytes each (doc comment not supported on macros)
-pool!(TxPool: [[u8; BUFFER_SIZE]; TX_POOL_SIZE]);
+/// Response code at the start of every response and event body
+const RESPONSE_CODE_SIZE: usize = 2;
+
+// TX buffer pool - 8 buffers of 257 bytes each (doc comment not supported on macros)
+pool!(TxPool: [[u8; TX_BUFFER_SIZE]; TX_POOL_SIZE]);
+
+/// Buffers in use at which the host is warned that the pool is nearly exhausted
+pub const TX_POOL_LOW_THRESHOLD: usize = TX_POOL_SIZE - 2;
+
+/// Buffers in use at or below which the low-pool warning is re-armed
+pub const TX_POOL_LOW_REARM: usize = TX_POOL_SIZE / 2;
+
+/// TX allocations that failed because every pool buffer was in use
+///
+/// Reported by GET_POOL_STATS and zeroed by [`reset_stats`] only; the link
+/// statistics keep their own count.
+static TX_POOL_EXHAUSTED: AtomicU32 = AtomicU32::new(0);
+
+/// TX buffers currently allocated
+static TX_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// Most TX buffers allocated at once since boot
+static TX_PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Set while the next low-pool crossing should be reported
+static TX_POOL_LOW_ARMED: AtomicBool = AtomicBool::new(true);
+
+/// Low-pool warning: (uptime µs, buffers allocated)
+static TX_POOL_LOW: Signal<CriticalSectionRawMutex, (u64, u8)> = Signal::new();
 
 /// RX buffer size for command reception
 pub const RX_BUFFER_SIZE: usize = BUFFER_SIZE;
 
 /// TX packet structure
+///
+/// Holds one frame, or every fragment of a packet too large for a single frame.
+/// Each frame occupies its own pool buffer.
 pub struct TxPacket {
+    frames: Vec<TxFrame, MAX_FRAGMENTS>,
+}
+
+/// A single frame in a pool buffer
+///
+/// The frame occupies `data[start..start + len]`. While a [`TxPacketBuilder`]
+/// writes it, `len` counts the body bytes at `FRAME_HEADROOM`.
+struct TxFrame {
     data: Box<TxPool>,
+    start: usize,
     len: usize,
 }
 
+impl TxFrame {
+    /// Take an empty buffer from the pool
+    fn alloc() -> Result<Self, BufferError> {
+        let Some(data) = Box::<TxPool>::new([0; TX_BUFFER_SIZE]) else {
+            TX_POOL_EXHAUSTED.fetch_add(1, Ordering::Relaxed);
+            transport::record_tx_pool_exhausted();
+            return Err(BufferError::PoolExhausted);
+        };
+        record_tx_alloc();
+
+        Ok(Self {
+            data,
+            start: FRAME_HEADROOM,
+            len: 0,
+        })
+    }
+
+    /// Copy an already serialized frame into a pool buffer
+    fn new(data: &[u8]) -> Result<Self, BufferError> {
+        if data.len() > BUFFER_SIZE {
+            return Err(BufferError::BufferTooSmall);
+        }
+
+        let mut frame = Self::alloc()?;
+        frame.data[FRAME_HEADROOM..FRAME_HEADROOM + data.len()].copy_from_slice(data);
+        frame.len = data.len();
+        Ok(frame)
+    }
+
+    /// Write the header in front of the body and the CRC behind it
+    fn seal(&mut self, seq: Option<u16>, fragment: Option<FragmentHeader>) -> Result<(), BufferError> {
+        let range = seal_frame(&mut self.data[..], seq, fragment, self.len).map_err(|_| BufferError::BufferTooSmall)?;
+        self.start = range.start;
+        self.len = range.len();
+        Ok(())
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        &self.data[self.start..self.start + self.len]
+    }
+}
+
+impl Drop for TxFrame {
+    fn drop(&mut self) {
+        // The pool buffer itself is released when `data` drops right after this
+        let allocated = TX_ALLOCATED.fetch_sub(1, Ordering::Relaxed).saturating_sub(1);
+        if allocated <= TX_POOL_LOW_REARM {
+            TX_POOL_LOW_ARMED.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Count a TX buffer allocation and raise the low-pool warning on crossing the threshold
+fn record_tx_alloc() {
+    let allocated = TX_ALLOCATED.fetch_add(1, Ordering::Relaxed) + 1;
+    TX_PEAK.fetch_max(allocated, Ordering::Relaxed);
+
+    if allocated >= TX_POOL_LOW_THRESHOLD && TX_POOL_LOW_ARMED.swap(false, Ordering::Relaxed) {
+        warn!(
+            "TX pool nearly exhausted: {}/{} buffers in use",
+            allocated, TX_POOL_SIZE
+        );
+        TX_POOL_LOW.signal((Instant::now().as_micros(), allocated as u8));
+    }
+}
+
+/// Wait for the next low-pool warning, returning its timestamp and the buffers in use
+pub async fn wait_pool_low() -> (u64, u8) {
+    TX_POOL_LOW.wait().await
+}
+
+/// Response or event packet built in place in pool buffers
+///
+/// The body (response code, then payload) is written straight into frame
+/// buffers behind room for the header; [`TxPacketBuilder::finish`] fills in the
+/// code, headers and CRCs. A body that outgrows one frame is split into
+/// fragments as it is written. Buffers are only taken once data is written, and
+/// return to the pool if the builder is dropped.
+pub struct TxPacketBuilder {
+    seq: Option<u16>,
+    frames: Vec<TxFrame, MAX_FRAGMENTS>,
+    /// Body bytes written, including the response code
+    body_len: usize,
+}
+
+impl TxPacketBuilder {
+    /// Start an empty packet framed with `seq`
+    pub const fn new(seq: Option<u16>) -> Self {
+        Self {
+            seq,
+            frames: Vec::new(),
+            body_len: 0,
+        }
+    }
+
+    /// Append payload bytes
+    ///
+    /// Fails with `BufferTooSmall` beyond `MAX_MESSAGE_SIZE` bytes of payload and
+    /// with `PoolExhausted` when no buffer is left for the next frame.
+    pub fn write(&mut self, mut data: &[u8]) -> Result<(), BufferError> {
+        if self.body_len.max(RESPONSE_CODE_SIZE) + data.len() > MAX_REASSEMBLED_SIZE {
+            return Err(BufferError::BufferTooSmall);
+        }
+
+        // The first frame starts with room for the response code
+        if self.frames.is_empty() {
+            let mut frame = TxFrame::alloc()?;
+            frame.len = RESPONSE_CODE_SIZE;
+            let _ = self.frames.push(frame);
+            self.body_len = RESPONSE_CODE_SIZE;
+        }
+
+        while !data.is_empty() {
+            let capacity = self.last_frame_capacity();
+            let last = self.frames.len() - 1;
+            if self.frames[last].len == capacity {
+                self.spill()?;
+                continue;
+            }
+
+            let frame = &mut self.frames[last];
+            let count = (capacity - frame.len).min(data.len());
+            let offset = FRAME_HEADROOM + frame.len;
+            frame.data[offset..offset + count].copy_from_slice(&data[..count]);
+            frame.len += count;
+            self.body_len += count;
+            data = &data[count..];
+        }
+        Ok(())
+    }
+
+    /// Fill in the response code, frame headers and CRCs
+    pub fn finish(mut self, code: u16) -> Result<TxPacket, BufferError> {
+        self.write(&[])?;
+        self.frames[0].data[FRAME_HEADROOM..FRAME_HEADROOM + RESPONSE_CODE_SIZE].copy_from_slice(&code.to_be_bytes());
+
+        let count = self.frames.len();
+        let total_len = self.body_len as u16;
+        for (index, frame) in self.frames.iter_mut().enumerate() {
+            let fragment = match index {
+                _ if count == 1 => None,
+                0 => Some(FragmentHeader::First { total_len }),
+                index if index == count - 1 => Some(FragmentHeader::Last { index: index as u8 }),
+                index => Some(FragmentHeader::Continuation { index: index as u8 }),
+            };
+            frame.seal(self.seq, fragment)?;
+        }
+
+        Ok(TxPacket { frames: self.frames })
+    }
+
+    /// Body bytes the last frame can hold
+    ///
+    /// A lone frame holds as much as fits; fragments hold `FRAGMENT_CHUNK_SIZE`.
+    fn last_frame_capacity(&self) -> usize {
+        if self.frames.len() > 1 {
+            return FRAGMENT_CHUNK_SIZE;
+        }
+        let seq_len = if self.seq.is_some() { SEQUENCE_SIZE } else { 0 };
+        BUFFER_SIZE - 2 - seq_len - 2
+    }
+
+    /// Continue in a new frame once the last one is full
+    ///
+    /// A full first frame becomes the first fragment: it keeps
+    /// `FRAGMENT_CHUNK_SIZE` bytes and the few beyond move to the new frame.
+    fn spill(&mut self) -> Result<(), BufferError> {
+        let mut next = TxFrame::alloc()?;
+        let Some(last) = self.frames.last_mut() else {
+            return Err(BufferError::InvalidSize);
+        };
+
+        let excess = last.len - FRAGMENT_CHUNK_SIZE;
+        let moved = FRAME_HEADROOM + FRAGMENT_CHUNK_SIZE..FRAME_HEADROOM + last.len;
+        next.data[FRAME_HEADROOM..FRAME_HEADROOM + excess].copy_from_slice(&last.data[moved]);
+        last.len = FRAGMENT_CHUNK_SIZE;
+        next.len = excess;
+
+        self.frames.push(next).map_err(|_| BufferError::BufferTooSmall)
+    }
+}
+
 /// RX buffer for incoming commands
 pub struct RxBuffer {
     data: [u8; RX_BUFFER_SIZE],
@@ -42,35 +286,33 @@ pub enum BufferError {
 }
 
 impl TxPacket {
-    /// Allocate a new TX packet from the pool
+    /// Allocate a TX packet from the pool holding a copy of one serialized frame
+    ///
+    /// Responses and events are built in place with [`TxPacketBuilder`] instead.
     pub fn new(data: &[u8]) -> Result<Self, BufferError> {
-        if data.len() > BUFFER_SIZE {
-            return Err(BufferError::BufferTooSmall);
-        }
-
-        let mut buffer = Box::<TxPool>::new([0; BUFFER_SIZE]).ok_or(BufferError::PoolExhausted)?;
-
-        buffer[..data.len()].copy_from_slice(data);
-
-        Ok(Self {
-            data: buffer,
-            len: data.len(),
-        })
+        let mut frames = Vec::new();
+        let _ = frames.push(TxFrame::new(data)?);
+        Ok(Self { frames })
     }
 
-    /// Get the packet data as a slice
+    /// Get the packet data as a slice (the first frame for fragmented packets)
     pub fn as_slice(&self) -> &[u8] {
-        &self.data[..self.len]
+        self.frames.first().map_or(&[], |frame| frame.as_slice())
     }
 
-    /// Get the packet length
+    /// Iterate over the frames to transmit, in order
+    pub fn frames(&self) -> impl Iterator<Item = &[u8]> {
+        self.frames.iter().map(|frame| frame.as_slice())
+    }
+
+    /// Get the packet length (summed over all frames)
     pub fn len(&self) -> usize {
-        self.len
+        self.frames.iter().map(|frame| frame.len).sum()
     }
 
     /// Check if packet is empty
     pub fn is_empty(&self) -> bool {
-        self.len == 0
+        self.len() == 0
     }
 }
 
@@ -169,25 +411,22 @@ impl Default for TxQueue {
     }
 }
 
-/// Buffer pool statistics
-#[derive(Debug, Clone, Copy, Format)]
-pub struct PoolStats {
-    pub tx_allocated: usize,
-    pub tx_available: usize,
-    pub rx_active: bool,
-}
-
-/// Get current pool statistics
+/// Get current TX pool statistics
 pub fn get_stats() -> PoolStats {
-    // TODO: atomic_pool doesn't expose available() method directly
-    // For now, we'll return placeholder values
     PoolStats {
-        tx_allocated: 0,            // Would need to track this manually
-        tx_available: TX_POOL_SIZE, // Assuming all available for now
-        rx_active: true,            // RX buffer is statically allocated
+        size: TX_POOL_SIZE as u8,
+        allocated: TX_ALLOCATED.load(Ordering::Relaxed) as u8,
+        peak: TX_PEAK.load(Ordering::Relaxed) as u8,
+        alloc_failures: TX_POOL_EXHAUSTED.load(Ordering::Relaxed),
     }
 }
 
+/// Restart peak tracking from the current usage and zero the failure count
+pub fn reset_stats() {
+    TX_PEAK.store(TX_ALLOCATED.load(Ordering::Relaxed), Ordering::Relaxed);
+    TX_POOL_EXHAUSTED.store(0, Ordering::Relaxed);
+}
+
 // Tests moved to external test files to avoid no_std conflicts
 
 /// Initialize buffer pool
@@ -1,8 +1,18 @@
 //! Core System Infrastructure
 //!
 //! Provides fundamental system services that are not BLE-specific.
-//! This includes memory management, wire protocol definitions, and transport layers.
+//! This includes memory management, power management, wire protocol definitions, and transport layers.
+//! The host link is the dual SPI pair by default, a single full-duplex SPI slave
+//! with the `single-spi` feature, or a UART with the `uart` feature.
 
 pub mod memory;
+pub mod power;
 pub mod protocol;
+#[cfg(feature = "single-spi")]
+pub mod single_spi;
 pub mod transport;
+#[cfg(feature = "uart")]
+pub mod uart;
+
+#[cfg(all(feature = "uart", feature = "single-spi"))]
+compile_error!("The `uart` and `single-spi` host links are exclusive");
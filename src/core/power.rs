@@ -0,0 +1,230 @@
+//! Power Management
+//!
+//! SHUTDOWN, REBOOT and FACTORY_RESET power down or reset the modem once their ACK has left
+//! it: the command handler requests the transition and [`power_task`] waits
+//! for every queued response and event to be transmitted before calling into
+//! the SoftDevice. For System OFF it first arms the wake pin's SENSE level;
+//! waking resets the chip. If System OFF cannot be entered the modem resets
+//! instead, as SHUTDOWN has already halted advertising and closed every link.
+//!
+//! The cause of a reset the firmware triggers itself (host request, panic) is
+//! kept in RAM that survives the reset and combined with `POWER.RESETREAS` at
+//! boot into the [`ResetReason`] reported by the boot event.
+
+use core::cell::Cell;
+use core::mem::MaybeUninit;
+use core::ptr::{addr_of_mut, read_volatile, write_volatile};
+
+use defmt::{error, info, warn, Format};
+use embassy_nrf::pac;
+use embassy_nrf::pac::gpio::vals::{Dir, Input, Pull, Sense};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant, Timer};
+use nrf_softdevice::raw;
+
+use crate::core::memory;
+use crate::core::protocol::power::{RebootMode, ResetReason, WakePolarity};
+
+/// Longest wait for queued frames to be transmitted before powering off
+pub const TX_FLUSH_TIMEOUT_MS: u64 = 200;
+
+/// Wake source armed before entering System OFF
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Format)]
+pub struct WakeConfig {
+    /// P0 pin number
+    pub pin: u8,
+    pub polarity: WakePolarity,
+}
+
+/// Power transition requested by a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Format)]
+pub enum PowerRequest {
+    /// Enter System OFF until the wake pin reaches its level
+    SystemOff(WakeConfig),
+    /// Reset into the application or the bootloader, reporting `reason` after boot
+    Reset { mode: RebootMode, reason: ResetReason },
+}
+
+/// Pending power transition
+static POWER_REQUEST: Signal<CriticalSectionRawMutex, PowerRequest> = Signal::new();
+
+/// Enter System OFF once the TX queue has drained
+pub fn request_system_off(wake: WakeConfig) {
+    POWER_REQUEST.signal(PowerRequest::SystemOff(wake));
+}
+
+/// Reset once the TX queue has drained, reporting `reason` after boot
+pub fn request_reset(mode: RebootMode, reason: ResetReason) {
+    POWER_REQUEST.signal(PowerRequest::Reset { mode, reason });
+}
+
+/// Power task: waits for a power request and carries it out
+#[embassy_executor::task]
+pub async fn power_task() {
+    loop {
+        let request = POWER_REQUEST.wait().await;
+        info!("Power: {:?} requested", request);
+
+        flush_tx().await;
+
+        match request {
+            PowerRequest::SystemOff(wake) => {
+                arm_wake_pin(wake);
+                let ret = unsafe { raw::sd_power_system_off() };
+                // Only returns on failure. Rather than stay up without advertising,
+                // reset; the boot event reports an unrequested soft reset.
+                error!("Power: Failed to enter System OFF: {}, resetting", ret);
+                unsafe { raw::sd_nvic_SystemReset() };
+            }
+            PowerRequest::Reset { mode, reason } => {
+                if mode == RebootMode::Bootloader {
+                    enter_bootloader_on_reset();
+                }
+                record_reset_cause(reason);
+                unsafe { raw::sd_nvic_SystemReset() };
+            }
+        }
+    }
+}
+
+/// `GPREGRET` value telling the Nordic bootloader to stay in DFU mode
+const BOOTLOADER_DFU_START: u32 = 0xB1;
+
+fn enter_bootloader_on_reset() {
+    let ret = unsafe {
+        raw::sd_power_gpregret_clr(0, 0xFF);
+        raw::sd_power_gpregret_set(0, BOOTLOADER_DFU_START)
+    };
+    if ret != raw::NRF_SUCCESS {
+        error!("Power: Failed to set GPREGRET: {}", ret);
+    }
+}
+
+/// Marks [`RESET_CAUSE`] as written by this firmware before the last reset
+const RESET_CAUSE_MAGIC: u32 = 0x5253_4341;
+
+/// [magic, reason ID], not initialized at startup so it survives a soft reset
+#[link_section = ".uninit.RESET_CAUSE"]
+static mut RESET_CAUSE: MaybeUninit<[u32; 2]> = MaybeUninit::uninit();
+
+/// Remember why the firmware is about to reset itself
+///
+/// Safe to call from the panic handler: it only writes RAM.
+pub fn record_reset_cause(reason: ResetReason) {
+    unsafe {
+        write_volatile(
+            addr_of_mut!(RESET_CAUSE).cast::<[u32; 2]>(),
+            [RESET_CAUSE_MAGIC, reason.id() as u32],
+        )
+    };
+}
+
+/// Take the cause recorded before the last reset, if any
+fn take_reset_cause() -> Option<ResetReason> {
+    let cause = addr_of_mut!(RESET_CAUSE).cast::<[u32; 2]>();
+    let [magic, id] = unsafe { read_volatile(cause) };
+    unsafe { write_volatile(cause, [0, 0]) };
+    (magic == RESET_CAUSE_MAGIC).then(|| ResetReason::from_id(id as u8))
+}
+
+/// `POWER.RESETREAS` bits
+const RESETREAS_RESETPIN: u32 = 1 << 0;
+const RESETREAS_DOG: u32 = 1 << 1;
+const RESETREAS_SREQ: u32 = 1 << 2;
+const RESETREAS_LOCKUP: u32 = 1 << 3;
+const RESETREAS_OFF: u32 = 1 << 16;
+const RESETREAS_DIF: u32 = 1 << 18;
+
+/// Reason and raw `RESETREAS` of the last reset, read by [`init`]
+static BOOT_REASON: Mutex<CriticalSectionRawMutex, Cell<(ResetReason, u32)>> =
+    Mutex::new(Cell::new((ResetReason::PowerOn, 0)));
+
+/// Determine why the modem started (call once, after enabling the SoftDevice)
+///
+/// `RESETREAS` is cleared afterwards so the next reset reports only its own cause.
+pub fn init() {
+    let mut resetreas: u32 = 0;
+    let ret = unsafe { raw::sd_power_reset_reason_get(&mut resetreas) };
+    if ret != raw::NRF_SUCCESS {
+        warn!("Power: Failed to read RESETREAS: {}", ret);
+    }
+    unsafe { raw::sd_power_reset_reason_clr(resetreas) };
+
+    let reason = reset_reason(resetreas, take_reset_cause());
+    if reason.is_fault() {
+        warn!("Power: Reset by {:?} (RESETREAS {:#010x})", reason, resetreas);
+    } else {
+        info!("Power: Reset by {:?} (RESETREAS {:#010x})", reason, resetreas);
+    }
+    BOOT_REASON.lock(|boot| boot.set((reason, resetreas)));
+}
+
+/// Reason and raw `RESETREAS` of the last reset
+pub fn boot_reason() -> (ResetReason, u32) {
+    BOOT_REASON.lock(|boot| boot.get())
+}
+
+fn reset_reason(resetreas: u32, recorded: Option<ResetReason>) -> ResetReason {
+    // A recorded cause only explains a software reset
+    if let Some(reason) = recorded.filter(|_| resetreas & RESETREAS_SREQ != 0) {
+        return reason;
+    }
+
+    if resetreas & RESETREAS_DOG != 0 {
+        ResetReason::Watchdog
+    } else if resetreas & RESETREAS_LOCKUP != 0 {
+        ResetReason::Lockup
+    } else if resetreas & RESETREAS_SREQ != 0 {
+        ResetReason::SoftReset
+    } else if resetreas & RESETREAS_RESETPIN != 0 {
+        ResetReason::Pin
+    } else if resetreas & RESETREAS_OFF != 0 {
+        ResetReason::WakeFromOff
+    } else if resetreas & RESETREAS_DIF != 0 {
+        ResetReason::Debugger
+    } else {
+        ResetReason::PowerOn
+    }
+}
+
+/// Wait until every TX pool buffer is released, i.e. all frames were sent
+async fn flush_tx() {
+    let deadline = Instant::now() + Duration::from_millis(TX_FLUSH_TIMEOUT_MS);
+
+    while memory::get_stats().allocated > 0 {
+        if Instant::now() >= deadline {
+            warn!(
+                "Power: {} TX buffers still queued after {}ms, powering off anyway",
+                memory::get_stats().allocated,
+                TX_FLUSH_TIMEOUT_MS
+            );
+            return;
+        }
+        Timer::after_millis(1).await;
+    }
+}
+
+/// Configure the wake pin as an input sensing the wake level
+///
+/// The pin is pulled away from the wake level so a floating line does not
+/// wake the modem straight away.
+fn arm_wake_pin(wake: WakeConfig) {
+    let (pull, sense) = match wake.polarity {
+        WakePolarity::Low => (Pull::PULLUP, Sense::LOW),
+        WakePolarity::High => (Pull::PULLDOWN, Sense::HIGH),
+    };
+
+    pac::P0.pin_cnf(wake.pin as usize).write(|w| {
+        w.set_dir(Dir::INPUT);
+        w.set_input(Input::CONNECT);
+        w.set_pull(pull);
+        w.set_sense(sense);
+    });
+
+    let high = pac::P0.in_().read().pin(wake.pin as usize);
+    if high == (wake.polarity == WakePolarity::High) {
+        warn!("Power: Wake pin P0.{} is already at its wake level", wake.pin);
+    }
+}
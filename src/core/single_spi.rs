@@ -0,0 +1,172 @@
+//! Single-SPI Communication Layer
+//!
+//! Alternative to the dual SPI link for hosts that can only be SPI master,
+//! enabled with the `single-spi` cargo feature. SPIS1 carries both directions in
+//! full-duplex transactions clocked by the host:
+//! - The device raises the IRQ line while a response or event is waiting
+//! - Each transaction shifts the waiting frame out on MISO (zeros if there is
+//!   none) while the host shifts a request in on MOSI, or zeros to only poll
+//! - Frames keep the `Packet` framing; both sides drop the zero padding using
+//!   the length header (`Frame::declared_length`)
+//!
+//! A frame leaves the queue only once a transaction clocked all of it out, so the
+//! host should clock at least `MAX_PAYLOAD_SIZE` bytes while the IRQ line is high.
+//! A frame queued while an empty transaction is armed raises the IRQ line
+//! straight away and goes out in the transaction after that one.
+
+use core::convert::Infallible;
+
+use defmt::{debug, error, info};
+use embassy_futures::select::{select, Either};
+use embassy_nrf::gpio::{AnyPin, Level, Output, OutputDrive};
+use embassy_nrf::peripherals::TWISPI1;
+use embassy_nrf::spis::{self, Spis};
+use embassy_nrf::Peri;
+
+use crate::core::memory::TxPacket;
+use crate::core::protocol::fragment::{Reassembler, MAX_REASSEMBLED_SIZE, REASSEMBLY_TIMEOUT_MS};
+use crate::core::protocol::Frame;
+use crate::core::transport::{deliver_frame, record_frame_sent, record_rx_error, Irqs, LinkStatus, HOST_LINK};
+
+/// Single-SPI Configuration (SPIS1 - Slave, both directions)
+/// Pins: SS, SCK, MISO (device → host), MOSI (host → device), IRQ output,
+/// assigned by the board definition (`crate::board`)
+/// Config: board mode (the host supplies the clock), MSB First
+pub struct SingleSpiConfig {
+    pub cs_pin: Peri<'static, AnyPin>,
+    pub sck_pin: Peri<'static, AnyPin>,
+    pub miso_pin: Peri<'static, AnyPin>,
+    pub mosi_pin: Peri<'static, AnyPin>,
+    pub irq_pin: Peri<'static, AnyPin>,
+    pub mode: spis::Mode,
+}
+
+/// Single-SPI task - handles both directions on SPIS1
+/// Sends packets from HOST_LINK and forwards received frames to HOST_LINK
+#[embassy_executor::task]
+pub async fn single_spi_task(config: SingleSpiConfig, spis1: Peri<'static, TWISPI1>) {
+    info!("Starting single-SPI task (SPIS1 - Slave, full duplex)");
+
+    let mut irq = Output::new(config.irq_pin, Level::Low, OutputDrive::Standard);
+
+    let mut spi_config = spis::Config::default();
+    spi_config.mode = config.mode;
+    // Bytes clocked past the armed frame read as zero padding
+    spi_config.orc = 0x00;
+    spi_config.def = 0x00;
+
+    let mut spi = Spis::new(
+        spis1,
+        Irqs,
+        config.cs_pin,
+        config.sck_pin,
+        config.miso_pin,
+        config.mosi_pin,
+        spi_config,
+    );
+    let mut reassembler = Reassembler::<MAX_REASSEMBLED_SIZE>::new(REASSEMBLY_TIMEOUT_MS);
+
+    // Packet being sent and the index of its next frame
+    let mut pending: Option<(TxPacket, usize)> = None;
+
+    info!(
+        "Single-SPI configured: Slave mode, {:?}",
+        defmt::Debug2Format(&config.mode)
+    );
+    HOST_LINK.set_link_status(LinkStatus::Up);
+
+    loop {
+        if pending.is_none() {
+            pending = HOST_LINK.try_next_outgoing().map(|packet| (packet, 0));
+        }
+
+        // Pool buffers are in RAM, so EasyDMA reads the frame where it was built
+        // (an idle transaction still needs a RAM pointer, hence the stack slice)
+        let idle = [0u8; 1];
+        let tx_frame: &[u8] = pending
+            .as_ref()
+            .and_then(|(packet, index)| packet.frames().nth(*index))
+            .unwrap_or(&idle[..0]);
+        let tx_len = tx_frame.len();
+        if tx_len > 0 {
+            irq.set_high();
+        } else {
+            irq.set_low();
+        }
+
+        let mut rx_buffer = [0u8; 256];
+        let mut queued = None;
+        let result = {
+            let transfer = spi.transfer(&mut rx_buffer, tx_frame);
+            // Nothing armed: tell the host as soon as something is queued
+            let wait_outgoing = async {
+                if tx_len == 0 {
+                    queued = Some(HOST_LINK.next_outgoing().await);
+                    irq.set_high();
+                }
+                core::future::pending::<Infallible>().await
+            };
+            match select(transfer, wait_outgoing).await {
+                Either::First(result) => result,
+                Either::Second(never) => match never {},
+            }
+        };
+        if let Some(packet) = queued {
+            pending = Some((packet, 0));
+        }
+
+        match result {
+            Ok((rx_len, tx_sent)) => {
+                if tx_len > 0 {
+                    if tx_sent >= tx_len {
+                        debug!("Single-SPI: Sent {} bytes", tx_len);
+                        record_frame_sent();
+                        advance_frame(&mut pending);
+                    } else {
+                        debug!("Single-SPI: Host clocked {} of {} bytes, resending", tx_sent, tx_len);
+                    }
+                }
+
+                let received = &rx_buffer[..rx_len];
+                match Frame::declared_length(received) {
+                    // Padding past the frame is dropped; a short frame is NAKed by the parser
+                    Some(len) => deliver_frame(&mut reassembler, &received[..len.min(rx_len)]),
+                    None => debug!("Single-SPI: Poll without request"),
+                }
+            }
+            Err(e) => {
+                error!("Single-SPI: Transfer error: {:?}", defmt::Debug2Format(&e));
+                record_rx_error();
+            }
+        }
+    }
+}
+
+/// Move to the next frame of the pending packet, releasing it after the last one
+fn advance_frame(pending: &mut Option<(TxPacket, usize)>) {
+    let done = match pending.as_mut() {
+        Some((packet, index)) => {
+            *index += 1;
+            *index >= packet.frames().count()
+        }
+        None => false,
+    };
+    if done {
+        // Release packet buffers back to pool
+        *pending = None;
+    }
+}
+
+/// Initialize single-SPI communication and spawn its task
+pub async fn init_and_spawn(
+    spawner: &embassy_executor::Spawner,
+    config: SingleSpiConfig,
+    spis1: Peri<'static, TWISPI1>,
+) -> Result<(), embassy_executor::SpawnError> {
+    info!("Initializing single-SPI communication...");
+
+    spawner.spawn(single_spi_task(config, spis1))?;
+
+    info!("Single-SPI task spawned successfully");
+    Ok(())
+}
@@ -3,42 +3,78 @@
 //! This module handles dual SPI communication:
 //! - TX SPI (SPIM0 - Master): Device → Host communication
 //! - RX SPI (SPIS1 - Slave): Host → Device communication
+//!
+//! Two optional GPIO handshake lines pace the link:
+//! - Device ready (output): high while the device can accept a frame,
+//!   held low (busy) while the command queue is full
+//! - Host ready (input): the TX task only clocks a frame out while the
+//!   host drives it high
+//!
+//! Without them the device transmits whenever it has data and drops requests
+//! that arrive while the command queue is full.
+//!
+//! Pins, SPI clock and SPI mode come from the board definition (`crate::board`).
+//!
+//! Request frames that are corrupted, incomplete or dropped are answered with a
+//! NAK and counted; a retransmitted request gets its kept response again, or a
+//! NAK. See `ble_modem_protocol::link`.
+//!
+//! The command dispatcher and event forwarding only see the [`Transport`] trait.
+//! Link drivers (this SPI pair, the single-SPI link, the UART) feed the [`HOST_LINK`] channel transport;
+//! a `ChannelTransport` without a driver is an in-memory loopback for tests.
+
+use core::cell::RefCell;
+use core::future::Future;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
 
 use defmt::{debug, error, info, warn, Format};
-use embassy_nrf::gpio::{Level, Output, OutputDrive};
-use embassy_nrf::peripherals::{P0_00, P0_01, P0_04, P0_05, P0_06, P0_07, TWISPI0, TWISPI1};
+use embassy_nrf::gpio::{AnyPin, Input, Level, Output, OutputDrive, Pull};
+use embassy_nrf::peripherals::{TWISPI0, TWISPI1};
 use embassy_nrf::spim::{self, Frequency, Spim};
 use embassy_nrf::spis::{self, Spis};
 use embassy_nrf::{bind_interrupts, Peri};
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
-use embassy_sync::channel::Channel;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::channel::{Channel, TrySendError};
+use embassy_sync::signal::Signal;
+use embassy_time::{with_timeout, Duration, Instant};
 
-use crate::core::memory::{BufferError, TxPacket};
-use crate::core::protocol::{Packet, ProtocolError};
+use crate::core::memory::{BufferError, TxPacket, TxPacketBuilder};
+use crate::core::protocol::fragment::{Reassembler, MAX_REASSEMBLED_SIZE, REASSEMBLY_TIMEOUT_MS};
+use crate::core::protocol::link::{DuplicateWindow, LinkStats, Nak, NakReason, CACHED_RESPONSE_SIZE, NAK_SIZE};
+use crate::core::protocol::{Frame, Packet, ProtocolError, ResponseCode, UNSOLICITED_SEQ};
 
-bind_interrupts!(struct Irqs {
+bind_interrupts!(pub(crate) struct Irqs {
     TWISPI0 => spim::InterruptHandler<TWISPI0>;
     TWISPI1 => spis::InterruptHandler<TWISPI1>;
 });
 
 /// TX SPI Configuration (SPIM0 - Master)
-/// Pins: SS=P0.01, SCK=P0.00, MOSI=P0.04 (master out - device transmits to host)
-/// Config: 8MHz, CPOL=High, CPHA=Leading, MSB First
+/// Pins: SS, SCK, MOSI (master out - device transmits to host), optional host ready input
+/// Config: board clock and mode, MSB First
 pub struct TxSpiConfig {
-    pub cs_pin: Peri<'static, P0_01>,
-    pub sck_pin: Peri<'static, P0_00>,
-    pub mosi_pin: Peri<'static, P0_04>,
+    pub cs_pin: Peri<'static, AnyPin>,
+    pub sck_pin: Peri<'static, AnyPin>,
+    pub mosi_pin: Peri<'static, AnyPin>,
+    pub host_ready_pin: Option<Peri<'static, AnyPin>>,
+    pub frequency: Frequency,
+    pub mode: spim::Mode,
 }
 
 /// RX SPI Configuration (SPIS1 - Slave)
-/// Pins: SS=P0.07, SCK=P0.06, MISO=P0.05 (slave in - host transmits to device)
-/// Config: CPOL=High, CPHA=Leading, MSB First
+/// Pins: SS, SCK, MISO (slave in - host transmits to device), optional device ready output
+/// Config: board mode (the host supplies the clock), MSB First
 pub struct RxSpiConfig {
-    pub cs_pin: Peri<'static, P0_07>,
-    pub sck_pin: Peri<'static, P0_06>,
-    pub miso_pin: Peri<'static, P0_05>,
+    pub cs_pin: Peri<'static, AnyPin>,
+    pub sck_pin: Peri<'static, AnyPin>,
+    pub miso_pin: Peri<'static, AnyPin>,
+    pub ready_pin: Option<Peri<'static, AnyPin>>,
+    pub mode: spis::Mode,
 }
 
+/// How long the TX task waits for the host ready line before reporting the link down
+pub const HOST_READY_TIMEOUT_MS: u64 = 100;
+
 /// SPI communication errors
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Format)]
 pub enum SpiError {
@@ -68,68 +104,388 @@ impl From<ProtocolError> for SpiError {
     }
 }
 
-/// Channel for TX packets (from command processor to TX SPI task)
-pub static TX_CHANNEL: Channel<CriticalSectionRawMutex, TxPacket, 8> = Channel::new();
+/// Host link status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Format)]
+pub enum LinkStatus {
+    /// No link driver running, or its last transfer failed
+    Down,
+    /// Link driver running and transferring
+    Up,
+}
+
+/// Host link used by the command dispatcher and event forwarding
+///
+/// Carries complete packets: framing, fragmentation and the physical link
+/// (SPI pair, UART, loopback) stay behind the implementation.
+pub trait Transport {
+    /// Receive the next request from the host
+    fn receive_frame(&self) -> impl Future<Output = Packet>;
+
+    /// Queue a response or event for the host
+    fn send_frame(&self, packet: TxPacket) -> impl Future<Output = Result<(), SpiError>>;
+
+    /// Current state of the underlying link
+    fn link_status(&self) -> LinkStatus;
+}
+
+/// Transport backed by packet channels
+///
+/// The dispatcher uses the [`Transport`] side; a link driver (or test code, for
+/// a loopback) uses the other side: [`ChannelTransport::deliver`] for received
+/// requests and [`ChannelTransport::next_outgoing`] for packets to transmit.
+pub struct ChannelTransport {
+    /// Requests waiting for the dispatcher
+    rx: Channel<CriticalSectionRawMutex, Packet, 1>,
+    /// Raised when the dispatcher takes a request off the queue
+    rx_space: Signal<CriticalSectionRawMutex, ()>,
+    /// Responses and events waiting for the link driver
+    tx: Channel<CriticalSectionRawMutex, TxPacket, 8>,
+    /// Most packets waiting in `tx` at once
+    tx_high_water: AtomicUsize,
+    link_up: AtomicBool,
+}
+
+impl ChannelTransport {
+    /// Create a transport with no link driver attached (status down)
+    pub const fn new() -> Self {
+        Self {
+            rx: Channel::new(),
+            rx_space: Signal::new(),
+            tx: Channel::new(),
+            tx_high_water: AtomicUsize::new(0),
+            link_up: AtomicBool::new(false),
+        }
+    }
+
+    /// Hand a received request to the dispatcher, returning it if the queue is full
+    pub fn deliver(&self, packet: Packet) -> Result<(), Packet> {
+        self.rx.try_send(packet).map_err(|TrySendError::Full(packet)| packet)
+    }
+
+    /// Queue a packet for the link driver without waiting, returning it if the queue is full
+    pub fn try_send_frame(&self, packet: TxPacket) -> Result<(), TxPacket> {
+        self.tx.try_send(packet).map_err(|TrySendError::Full(packet)| packet)?;
+        self.record_tx_depth();
+        Ok(())
+    }
+
+    /// Most packets that were waiting in the TX queue at once
+    pub fn tx_high_water(&self) -> usize {
+        self.tx_high_water.load(Ordering::Relaxed)
+    }
 
-/// Channel for RX packets (from RX SPI task to command processor)
-pub static RX_CHANNEL: Channel<CriticalSectionRawMutex, Packet, 1> = Channel::new();
+    /// Restart the TX queue high-water mark from the current depth
+    pub fn reset_tx_high_water(&self) {
+        self.tx_high_water.store(self.tx.len(), Ordering::Relaxed);
+    }
+
+    fn record_tx_depth(&self) {
+        self.tx_high_water.fetch_max(self.tx.len(), Ordering::Relaxed);
+    }
+
+    /// Wait for the next packet to transmit
+    pub async fn next_outgoing(&self) -> TxPacket {
+        self.tx.receive().await
+    }
+
+    /// Take the next packet to transmit, if any (non-blocking)
+    pub fn try_next_outgoing(&self) -> Option<TxPacket> {
+        self.tx.try_receive().ok()
+    }
+
+    /// Record the link driver's view of the link
+    pub fn set_link_status(&self, status: LinkStatus) {
+        self.link_up.store(status == LinkStatus::Up, Ordering::Relaxed);
+    }
+
+    /// Check if the TX queue has space
+    pub fn tx_has_space(&self) -> bool {
+        !self.tx.is_full()
+    }
+
+    /// Check if a request is waiting for the dispatcher
+    pub fn rx_has_data(&self) -> bool {
+        !self.rx.is_empty()
+    }
+
+    /// Check if the request queue can take another request
+    pub fn rx_has_space(&self) -> bool {
+        !self.rx.is_full()
+    }
+
+    /// Wait until the request queue can take another request
+    pub async fn wait_rx_space(&self) {
+        while self.rx.is_full() {
+            self.rx_space.wait().await;
+        }
+    }
+}
+
+impl Default for ChannelTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transport for ChannelTransport {
+    async fn receive_frame(&self) -> Packet {
+        let packet = self.rx.receive().await;
+        self.rx_space.signal(());
+        packet
+    }
+
+    async fn send_frame(&self, packet: TxPacket) -> Result<(), SpiError> {
+        self.tx.send(packet).await;
+        self.record_tx_depth();
+        Ok(())
+    }
+
+    fn link_status(&self) -> LinkStatus {
+        if self.link_up.load(Ordering::Relaxed) {
+            LinkStatus::Up
+        } else {
+            LinkStatus::Down
+        }
+    }
+}
+
+/// Link to the host, driven by the SPI pair, the single-SPI link or the UART
+pub static HOST_LINK: ChannelTransport = ChannelTransport::new();
+
+/// Whether the host uses sequenced framing (tracks the most recent valid request)
+static SEQUENCED_FRAMING: AtomicBool = AtomicBool::new(false);
+
+/// Check if the host uses sequenced framing
+///
+/// Unsolicited frames (events) are only sequenced once the host has shown it
+/// understands the format, so legacy hosts keep receiving legacy frames.
+pub fn sequenced_framing() -> bool {
+    SEQUENCED_FRAMING.load(Ordering::Relaxed)
+}
+
+/// Recent sequenced requests, for detecting retransmissions
+static DUPLICATE_WINDOW: Mutex<CriticalSectionRawMutex, RefCell<DuplicateWindow>> =
+    Mutex::new(RefCell::new(DuplicateWindow::new()));
+
+/// Forget the requests remembered for duplicate detection (host restarted)
+pub fn clear_duplicate_window() {
+    DUPLICATE_WINDOW.lock(|window| window.borrow_mut().clear());
+}
+
+/// Keep the response to the request with sequence number `seq` for replaying to a retransmission
+///
+/// Only single-frame responses up to `CACHED_RESPONSE_SIZE` bytes are kept; a
+/// retransmission of any other request is answered with a `Duplicate` NAK.
+pub fn remember_response(seq: u16, response: &TxPacket) {
+    let mut frames = response.frames();
+    let kept = match (frames.next(), frames.next()) {
+        (Some(frame), None) => DUPLICATE_WINDOW.lock(|window| window.borrow_mut().record_response(seq, frame)),
+        _ => false,
+    };
+    if !kept {
+        debug!("RX: Response to {} not kept for retransmissions", seq);
+    }
+}
+
+/// Queue the response kept for the request `packet` repeats, if there is one
+fn replay_response(packet: &Packet) -> bool {
+    let frame: Option<heapless::Vec<u8, CACHED_RESPONSE_SIZE>> = DUPLICATE_WINDOW.lock(|window| {
+        window
+            .borrow()
+            .cached_response(packet)
+            .and_then(|frame| heapless::Vec::from_slice(frame).ok())
+    });
+
+    frame.is_some_and(|frame| TxPacket::new(&frame).is_ok_and(|response| HOST_LINK.try_send_frame(response).is_ok()))
+}
+
+/// Link traffic and failure counters kept by the transport
+///
+/// The TX pool and TX queue figures of [`LinkStats`] are tracked where they happen.
+struct LinkCounters {
+    frames_received: AtomicU32,
+    frames_sent: AtomicU32,
+    invalid_length: AtomicU32,
+    invalid_crc: AtomicU32,
+    malformed: AtomicU32,
+    fragment_errors: AtomicU32,
+    rx_overflows: AtomicU32,
+    duplicates: AtomicU32,
+    rx_errors: AtomicU32,
+    tx_errors: AtomicU32,
+    naks_dropped: AtomicU32,
+    tx_pool_exhausted: AtomicU32,
+}
+
+impl LinkCounters {
+    const fn new() -> Self {
+        Self {
+            frames_received: AtomicU32::new(0),
+            frames_sent: AtomicU32::new(0),
+            invalid_length: AtomicU32::new(0),
+            invalid_crc: AtomicU32::new(0),
+            malformed: AtomicU32::new(0),
+            fragment_errors: AtomicU32::new(0),
+            rx_overflows: AtomicU32::new(0),
+            duplicates: AtomicU32::new(0),
+            rx_errors: AtomicU32::new(0),
+            tx_errors: AtomicU32::new(0),
+            naks_dropped: AtomicU32::new(0),
+            tx_pool_exhausted: AtomicU32::new(0),
+        }
+    }
+
+    /// Counter for frames rejected with `reason`
+    fn for_reason(&self, reason: NakReason) -> &AtomicU32 {
+        match reason {
+            NakReason::InvalidLength => &self.invalid_length,
+            NakReason::InvalidCrc => &self.invalid_crc,
+            NakReason::Malformed => &self.malformed,
+            NakReason::Fragment => &self.fragment_errors,
+            NakReason::Overflow => &self.rx_overflows,
+            NakReason::Duplicate => &self.duplicates,
+        }
+    }
+
+    fn all(&self) -> [&AtomicU32; 12] {
+        [
+            &self.frames_received,
+            &self.frames_sent,
+            &self.invalid_length,
+            &self.invalid_crc,
+            &self.malformed,
+            &self.fragment_errors,
+            &self.rx_overflows,
+            &self.duplicates,
+            &self.rx_errors,
+            &self.tx_errors,
+            &self.naks_dropped,
+            &self.tx_pool_exhausted,
+        ]
+    }
+
+    fn snapshot(&self) -> LinkStats {
+        LinkStats {
+            frames_received: self.frames_received.load(Ordering::Relaxed),
+            frames_sent: self.frames_sent.load(Ordering::Relaxed),
+            invalid_length: self.invalid_length.load(Ordering::Relaxed),
+            invalid_crc: self.invalid_crc.load(Ordering::Relaxed),
+            malformed: self.malformed.load(Ordering::Relaxed),
+            fragment_errors: self.fragment_errors.load(Ordering::Relaxed),
+            rx_overflows: self.rx_overflows.load(Ordering::Relaxed),
+            duplicates: self.duplicates.load(Ordering::Relaxed),
+            rx_errors: self.rx_errors.load(Ordering::Relaxed),
+            tx_errors: self.tx_errors.load(Ordering::Relaxed),
+            naks_dropped: self.naks_dropped.load(Ordering::Relaxed),
+            tx_pool_exhausted: self.tx_pool_exhausted.load(Ordering::Relaxed),
+            tx_queue_high_water: HOST_LINK.tx_high_water() as u32,
+        }
+    }
+}
+
+static LINK_COUNTERS: LinkCounters = LinkCounters::new();
+
+/// Link traffic and failure counters since boot or the last reset
+pub fn link_stats() -> LinkStats {
+    LINK_COUNTERS.snapshot()
+}
+
+/// Zero every link counter
+pub fn reset_link_stats() {
+    for counter in LINK_COUNTERS.all() {
+        counter.store(0, Ordering::Relaxed);
+    }
+    HOST_LINK.reset_tx_high_water();
+}
+
+/// Count a frame the link driver transmitted
+pub(crate) fn record_frame_sent() {
+    LINK_COUNTERS.frames_sent.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count a TX buffer allocation that failed because the pool was empty
+///
+/// Kept apart from the pool statistics' failure count so each is reset on its own.
+pub(crate) fn record_tx_pool_exhausted() {
+    LINK_COUNTERS.tx_pool_exhausted.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count a receive transfer the link driver reported as failed
+pub(crate) fn record_rx_error() {
+    LINK_COUNTERS.rx_errors.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count a frame the link driver failed to transmit
+pub(crate) fn record_tx_error() {
+    LINK_COUNTERS.tx_errors.fetch_add(1, Ordering::Relaxed);
+}
 
 /// TX SPI task - handles Device → Host communication
-/// Receives packets from TX_CHANNEL and transmits them via SPIM0
+/// Receives packets from HOST_LINK and transmits them via SPIM0
 #[embassy_executor::task]
-pub async fn tx_spi_task(
-    cs_pin: Peri<'static, P0_01>,
-    sck_pin: Peri<'static, P0_00>,
-    mosi_pin: Peri<'static, P0_04>,
-    spim0: Peri<'static, TWISPI0>,
-) {
+pub async fn tx_spi_task(tx_config: TxSpiConfig, spim0: Peri<'static, TWISPI0>) {
     info!("Starting TX SPI task (SPIM0 - Master)");
 
     // Configure SPI pins
-    let mut cs = Output::new(cs_pin, Level::High, OutputDrive::Standard);
+    let mut cs = Output::new(tx_config.cs_pin, Level::High, OutputDrive::Standard);
+    let mut host_ready = tx_config.host_ready_pin.map(|pin| Input::new(pin, Pull::Down));
 
     let mut config = spim::Config::default();
-    config.frequency = Frequency::M8;
-    config.mode = spim::Mode {
-        polarity: spim::Polarity::IdleHigh,
-        phase: spim::Phase::CaptureOnFirstTransition,
-    };
+    config.frequency = tx_config.frequency;
+    config.mode = tx_config.mode;
 
-    let mut spi = Spim::new_txonly(spim0, Irqs, sck_pin, mosi_pin, config);
+    let mut spi = Spim::new_txonly(spim0, Irqs, tx_config.sck_pin, tx_config.mosi_pin, config);
 
-    info!("TX SPI configured: 8MHz, CPOL=High, CPHA=Leading");
+    info!(
+        "TX SPI configured: {:?}, {:?}",
+        defmt::Debug2Format(&tx_config.frequency),
+        defmt::Debug2Format(&tx_config.mode)
+    );
     debug!("TX SPI: Entering main loop, waiting for packets...");
+    HOST_LINK.set_link_status(LinkStatus::Up);
 
     loop {
         // Wait for packet to transmit
-        debug!("TX SPI: Waiting for packet from TX_CHANNEL...");
-        let tx_packet = TX_CHANNEL.receive().await;
-        debug!("TX SPI: Received packet from TX_CHANNEL");
-
-        // Get serialized data
-        let data = tx_packet.as_slice();
-
-        debug!("TX SPI: Sending {} bytes", data.len());
-
-        // Pull SS low to start transmission
-        cs.set_low();
+        debug!("TX SPI: Waiting for packet from HOST_LINK...");
+        let tx_packet = HOST_LINK.next_outgoing().await;
+        debug!("TX SPI: Received packet from HOST_LINK");
+
+        // Fragmented packets are sent one frame per transaction
+        for data in tx_packet.frames() {
+            debug!("TX SPI: Sending {} bytes", data.len());
+
+            if let Some(host_ready) = host_ready.as_mut() {
+                if !wait_host_ready(host_ready).await {
+                    // Drop the rest of the packet rather than stalling the TX queue
+                    warn!(
+                        "TX SPI: Host not ready after {}ms, dropping packet",
+                        HOST_READY_TIMEOUT_MS
+                    );
+                    record_tx_error();
+                    break;
+                }
+            }
 
-        // EasyDMA requires data in RAM - copy to local buffer
-        let mut tx_buffer = [0u8; 256];
-        let len = data.len().min(256);
-        tx_buffer[..len].copy_from_slice(&data[..len]);
+            // Pull SS low to start transmission
+            cs.set_low();
 
-        let transfer_result = spi.write(&tx_buffer[..len]).await;
+            // Pool buffers are in RAM, so EasyDMA reads the frame where it was built
+            let transfer_result = spi.write(data).await;
 
-        // Release SS
-        cs.set_high();
+            // Release SS
+            cs.set_high();
 
-        match transfer_result {
-            Ok(_) => {
-                debug!("TX SPI: Transfer completed successfully");
-            }
-            Err(e) => {
-                error!("TX SPI: Transfer failed: {:?}", defmt::Debug2Format(&e));
+            match transfer_result {
+                Ok(_) => {
+                    debug!("TX SPI: Transfer completed successfully");
+                    record_frame_sent();
+                    HOST_LINK.set_link_status(LinkStatus::Up);
+                }
+                Err(e) => {
+                    error!("TX SPI: Transfer failed: {:?}", defmt::Debug2Format(&e));
+                    record_tx_error();
+                    HOST_LINK.set_link_status(LinkStatus::Down);
+                }
             }
         }
 
@@ -138,91 +494,189 @@ pub async fn tx_spi_task(
     }
 }
 
+/// Wait for the host to drive its ready line high
+///
+/// Returns false and marks the link down if the host stays busy longer than
+/// `HOST_READY_TIMEOUT_MS`; the link comes back up with the next frame sent.
+async fn wait_host_ready(host_ready: &mut Input<'static>) -> bool {
+    if host_ready.is_high() {
+        return true;
+    }
+
+    debug!("TX SPI: Waiting for host ready...");
+    let timeout = Duration::from_millis(HOST_READY_TIMEOUT_MS);
+    if with_timeout(timeout, host_ready.wait_for_high()).await.is_err() {
+        HOST_LINK.set_link_status(LinkStatus::Down);
+        return false;
+    }
+    true
+}
+
 /// RX SPI task - handles Host → Device communication
-/// Receives data via SPIS1 and forwards packets to RX_CHANNEL
+/// Receives data via SPIS1 and forwards packets to HOST_LINK
 #[embassy_executor::task]
-pub async fn rx_spi_task(
-    cs_pin: Peri<'static, P0_07>,
-    sck_pin: Peri<'static, P0_06>,
-    miso_pin: Peri<'static, P0_05>,
-    spis1: Peri<'static, TWISPI1>,
-) {
+pub async fn rx_spi_task(rx_config: RxSpiConfig, spis1: Peri<'static, TWISPI1>) {
     info!("Starting RX SPI task (SPIS1 - Slave)");
 
-    let mut config = spis::Config::default();
-    config.mode = spis::Mode {
-        polarity: spis::Polarity::IdleHigh,
-        phase: spis::Phase::CaptureOnFirstTransition,
-    };
+    // Device ready line starts low (busy) until the first read is armed
+    let mut ready = rx_config
+        .ready_pin
+        .map(|pin| Output::new(pin, Level::Low, OutputDrive::Standard));
 
-    let mut spi = Spis::new_rxonly(spis1, Irqs, cs_pin, sck_pin, miso_pin, config);
+    let mut config = spis::Config::default();
+    config.mode = rx_config.mode;
 
-    info!("RX SPI configured: Slave mode, CPOL=High, CPHA=Leading");
+    let mut spi = Spis::new_rxonly(
+        spis1,
+        Irqs,
+        rx_config.cs_pin,
+        rx_config.sck_pin,
+        rx_config.miso_pin,
+        config,
+    );
+    let mut reassembler = Reassembler::<MAX_REASSEMBLED_SIZE>::new(REASSEMBLY_TIMEOUT_MS);
+
+    info!(
+        "RX SPI configured: Slave mode, {:?}",
+        defmt::Debug2Format(&rx_config.mode)
+    );
     debug!("RX SPI: Entering main loop, waiting for host...");
 
     loop {
         // Buffer for incoming data (EasyDMA requires RAM buffers)
         let mut rx_buffer = [0u8; 256];
 
+        // With a ready line, hold busy until the dispatcher has room for a request
+        if let Some(ready) = ready.as_mut() {
+            if !HOST_LINK.rx_has_space() {
+                debug!("RX SPI: Command queue full, holding busy");
+                HOST_LINK.wait_rx_space().await;
+            }
+            ready.set_high();
+        }
+
         debug!("RX SPI: Waiting for host transmission...");
 
         // Wait for SPI transaction from host
         debug!("RX SPI: Calling spi.read()...");
-        match spi.read(&mut rx_buffer).await {
+        let read_result = spi.read(&mut rx_buffer).await;
+
+        // Busy while the frame is parsed and queued
+        if let Some(ready) = ready.as_mut() {
+            ready.set_low();
+        }
+
+        match read_result {
             Ok(rx_len) => {
                 if rx_len > 0 {
                     debug!("RX SPI: Received {} bytes", rx_len);
 
-                    // Try to parse as protocol packet
-                    match Packet::new_request(&rx_buffer[..rx_len]) {
-                        Ok(packet) => {
-                            debug!("RX SPI: Valid packet received, code: {:#04x}", packet.code);
-
-                            // Send to command processor
-                            if RX_CHANNEL.try_send(packet).is_err() {
-                                warn!("RX SPI: RX channel full, dropping packet");
-                            }
-                        }
-                        Err(e) => {
-                            warn!("RX SPI: Invalid packet received: {:?}", e);
-                        }
-                    }
+                    deliver_frame(&mut reassembler, &rx_buffer[..rx_len]);
                 } else {
                     debug!("RX SPI: Empty transfer received");
                 }
             }
             Err(e) => {
                 error!("RX SPI: Transfer error: {:?}", defmt::Debug2Format(&e));
+                record_rx_error();
                 // Timer::after(Duration::from_millis(10)).await;
             }
         }
     }
 }
 
-/// Send a response packet via TX SPI
-pub async fn send_response(packet: TxPacket) -> Result<(), SpiError> {
-    TX_CHANNEL.send(packet).await;
-    Ok(())
-}
+/// Parse a frame received by any link and forward complete requests to HOST_LINK
+///
+/// Frames that are not forwarded are NAKed so the host can retransmit.
+pub(crate) fn deliver_frame<const N: usize>(reassembler: &mut Reassembler<N>, data: &[u8]) {
+    LINK_COUNTERS.frames_received.fetch_add(1, Ordering::Relaxed);
+
+    match parse_request(reassembler, data) {
+        Ok(Some(packet)) => {
+            debug!("RX: Valid packet received, code: {:#04x}", packet.code);
+            SEQUENCED_FRAMING.store(packet.seq.is_some(), Ordering::Relaxed);
+
+            // Retransmission of a request that was already queued: send its
+            // response again if it was kept, NAK it otherwise
+            if DUPLICATE_WINDOW.lock(|window| window.borrow().is_duplicate(&packet)) {
+                if replay_response(&packet) {
+                    warn!("RX: Duplicate request {:?}, sending its response again", packet.seq);
+                    LINK_COUNTERS.duplicates.fetch_add(1, Ordering::Relaxed);
+                } else {
+                    warn!("RX: Duplicate request {:?}, not running it again", packet.seq);
+                    reject_frame(NakReason::Duplicate, packet.seq);
+                }
+                return;
+            }
 
-/// Check if TX channel has space
-pub fn tx_has_space() -> bool {
-    !TX_CHANNEL.is_full()
+            // A dropped request is not remembered, so the host can resend it
+            if !HOST_LINK.rx_has_space() {
+                warn!("RX: RX channel full, dropping packet");
+                reject_frame(NakReason::Overflow, packet.seq);
+                return;
+            }
+            DUPLICATE_WINDOW.lock(|window| window.borrow_mut().remember(&packet));
+
+            // Send to command processor (this task is the only producer, so there is room)
+            if HOST_LINK.deliver(packet).is_err() {
+                warn!("RX: RX channel full, dropping packet");
+            }
+        }
+        Ok(None) => {
+            debug!("RX: Fragment received, waiting for the rest");
+        }
+        Err(e) => {
+            warn!("RX: Invalid packet received: {:?}", e);
+            reject_frame(NakReason::from_error(e), None);
+        }
+    }
 }
 
-/// Check if RX channel has data
-pub fn rx_has_data() -> bool {
-    !RX_CHANNEL.is_empty()
+/// Count a rejected request frame and NAK it
+///
+/// `seq` is the rejected request's sequence number, if its frame could be
+/// trusted. NAKs are dropped (and counted) if the TX queue is full.
+pub(crate) fn reject_frame(reason: NakReason, seq: Option<u16>) {
+    LINK_COUNTERS.for_reason(reason).fetch_add(1, Ordering::Relaxed);
+
+    let nak = Nak {
+        reason,
+        seq: seq.unwrap_or(UNSOLICITED_SEQ),
+    };
+    let frame_seq = sequenced_framing().then_some(nak.seq);
+
+    let sent = build_nak(&nak, frame_seq).is_ok_and(|packet| HOST_LINK.try_send_frame(packet).is_ok());
+    if !sent {
+        warn!("RX: Could not queue NAK ({:?})", reason);
+        LINK_COUNTERS.naks_dropped.fetch_add(1, Ordering::Relaxed);
+    }
 }
 
-/// Try to receive a command packet (non-blocking)
-pub fn try_receive_command() -> Option<Packet> {
-    RX_CHANNEL.try_receive().ok()
+fn build_nak(nak: &Nak, frame_seq: Option<u16>) -> Result<TxPacket, SpiError> {
+    let mut payload: heapless::Vec<u8, NAK_SIZE> = heapless::Vec::new();
+    nak.encode(&mut payload)?;
+    let mut packet = TxPacketBuilder::new(frame_seq);
+    packet.write(&payload)?;
+    Ok(packet.finish(ResponseCode::Nak.to_u16())?)
 }
 
-/// Receive a command packet (blocking)
-pub async fn receive_command() -> Packet {
-    RX_CHANNEL.receive().await
+/// Parse a received frame, reassembling fragmented requests
+///
+/// Returns `Ok(None)` while a fragmented request is still incomplete.
+fn parse_request<const N: usize>(
+    reassembler: &mut Reassembler<N>,
+    data: &[u8],
+) -> Result<Option<Packet>, ProtocolError> {
+    let frame = Frame::parse(data)?;
+    if frame.fragment.is_none() {
+        return Packet::from_request_frame(&frame).map(Some);
+    }
+
+    let now_ms = Instant::now().as_millis();
+    match reassembler.push(&frame, now_ms)? {
+        Some(complete) => Packet::from_request_frame(&complete).map(Some),
+        None => Ok(None),
+    }
 }
 
 /// Initialize SPI communication and spawn tasks
@@ -234,24 +688,17 @@ pub async fn init_and_spawn(
     spis1: Peri<'static, TWISPI1>,
 ) -> Result<(), embassy_executor::SpawnError> {
     info!("Initializing SPI communication...");
-    info!("TX SPI: SPIM0, 8MHz, pins SCK=P0.00, SS=P0.01, MOSI=P0.04");
-    info!("RX SPI: SPIS1, slave mode, pins SCK=P0.06, SS=P0.07, MOSI=P0.05");
+    info!(
+        "Handshake: host ready {}, device ready {}",
+        tx_config.host_ready_pin.is_some(),
+        rx_config.ready_pin.is_some()
+    );
 
     // Spawn TX SPI task
-    spawner.spawn(tx_spi_task(
-        tx_config.cs_pin,
-        tx_config.sck_pin,
-        tx_config.mosi_pin,
-        spim0,
-    ))?;
+    spawner.spawn(tx_spi_task(tx_config, spim0))?;
 
     // Spawn RX SPI task
-    spawner.spawn(rx_spi_task(
-        rx_config.cs_pin,
-        rx_config.sck_pin,
-        rx_config.miso_pin,
-        spis1,
-    ))?;
+    spawner.spawn(rx_spi_task(rx_config, spis1))?;
 
     info!("SPI tasks spawned successfully");
     Ok(())
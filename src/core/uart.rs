@@ -0,0 +1,193 @@
+//! UART Communication Layer
+//!
+//! Alternative to the dual SPI link, enabled with the `uart` cargo feature.
+//! Carries the same protocol frames over UARTE0 with RTS/CTS hardware flow control:
+//! - Each frame is COBS-encoded and terminated by a 0x00 delimiter
+//! - Received frames go through the same reassembly path into HOST_LINK
+//! - Packets queued on HOST_LINK are sent one frame at a time
+//!
+//! The UART reuses the SPI connector pins, so only one link can be built in.
+
+use defmt::{debug, error, info, warn};
+use embassy_nrf::buffered_uarte::{self, BufferedUarte, BufferedUarteRx, BufferedUarteTx};
+use embassy_nrf::gpio::AnyPin;
+use embassy_nrf::peripherals::{PPI_CH0, PPI_CH1, PPI_GROUP0, TIMER1, UARTE0};
+use embassy_nrf::uarte::{self, Baudrate, Parity};
+use embassy_nrf::{bind_interrupts, Peri};
+
+use crate::core::protocol::cobs::{self, CobsDecoder, DELIMITER};
+use crate::core::protocol::fragment::{Reassembler, MAX_REASSEMBLED_SIZE, REASSEMBLY_TIMEOUT_MS};
+use crate::core::protocol::link::NakReason;
+use crate::core::protocol::MAX_PAYLOAD_SIZE;
+use crate::core::transport::{
+    deliver_frame, record_frame_sent, record_rx_error, record_tx_error, reject_frame, LinkStatus, HOST_LINK,
+};
+
+bind_interrupts!(struct Irqs {
+    UARTE0 => buffered_uarte::InterruptHandler<UARTE0>;
+});
+
+/// Largest COBS-encoded frame (without the delimiter)
+const MAX_ENCODED_FRAME_SIZE: usize = cobs::max_encoded_len(MAX_PAYLOAD_SIZE);
+
+/// UART baud rate
+const UART_BAUDRATE: Baudrate = Baudrate::BAUD1M;
+
+/// Driver ring buffer sizes, each holding at least two encoded frames
+const UART_RX_BUFFER_SIZE: usize = 512;
+const UART_TX_BUFFER_SIZE: usize = 512;
+
+static mut UART_RX_BUFFER: [u8; UART_RX_BUFFER_SIZE] = [0; UART_RX_BUFFER_SIZE];
+static mut UART_TX_BUFFER: [u8; UART_TX_BUFFER_SIZE] = [0; UART_TX_BUFFER_SIZE];
+
+/// UART Configuration (UARTE0)
+/// Pins: RXD, TXD, CTS, RTS, assigned by the board definition (`crate::board`)
+/// Config: 1Mbaud, 8N1, RTS/CTS flow control
+/// TIMER1 and PPI channels 0-1 / group 0 count received bytes (not used by the SoftDevice)
+pub struct UartConfig {
+    pub rxd_pin: Peri<'static, AnyPin>,
+    pub txd_pin: Peri<'static, AnyPin>,
+    pub cts_pin: Peri<'static, AnyPin>,
+    pub rts_pin: Peri<'static, AnyPin>,
+    pub timer: Peri<'static, TIMER1>,
+    pub ppi_ch1: Peri<'static, PPI_CH0>,
+    pub ppi_ch2: Peri<'static, PPI_CH1>,
+    pub ppi_group: Peri<'static, PPI_GROUP0>,
+}
+
+/// UART TX task - handles Device → Host communication
+/// Receives packets from HOST_LINK and writes them COBS-encoded
+#[embassy_executor::task]
+pub async fn uart_tx_task(mut tx: BufferedUarteTx<'static>) {
+    info!("Starting UART TX task");
+
+    // Leading delimiter terminates any partial frame the host saw before boot
+    if let Err(e) = write_all(&mut tx, &[DELIMITER]).await {
+        error!("UART TX: Write failed: {:?}", defmt::Debug2Format(&e));
+    }
+    HOST_LINK.set_link_status(LinkStatus::Up);
+
+    loop {
+        let tx_packet = HOST_LINK.next_outgoing().await;
+
+        for data in tx_packet.frames() {
+            let mut encoded = [0u8; MAX_ENCODED_FRAME_SIZE + 1];
+            let len = match cobs::encode(data, &mut encoded) {
+                Ok(len) => len,
+                Err(e) => {
+                    // Frames never exceed MAX_PAYLOAD_SIZE, so this is a bug upstream
+                    error!("UART TX: Frame encoding failed: {:?}", e);
+                    continue;
+                }
+            };
+            encoded[len] = DELIMITER;
+
+            debug!("UART TX: Sending {} bytes ({} encoded)", data.len(), len + 1);
+            match write_all(&mut tx, &encoded[..len + 1]).await {
+                Ok(()) => {
+                    record_frame_sent();
+                    HOST_LINK.set_link_status(LinkStatus::Up);
+                }
+                Err(e) => {
+                    error!("UART TX: Write failed: {:?}", defmt::Debug2Format(&e));
+                    record_tx_error();
+                    HOST_LINK.set_link_status(LinkStatus::Down);
+                }
+            }
+        }
+
+        // Release packet buffer back to pool
+        drop(tx_packet);
+    }
+}
+
+/// UART RX task - handles Host → Device communication
+/// Splits the byte stream on delimiters and forwards packets to HOST_LINK
+#[embassy_executor::task]
+pub async fn uart_rx_task(mut rx: BufferedUarteRx<'static>) {
+    info!("Starting UART RX task");
+
+    let mut decoder = CobsDecoder::<MAX_ENCODED_FRAME_SIZE>::new();
+    let mut reassembler = Reassembler::<MAX_REASSEMBLED_SIZE>::new(REASSEMBLY_TIMEOUT_MS);
+
+    loop {
+        let mut chunk = [0u8; 64];
+        let len = match rx.read(&mut chunk).await {
+            Ok(len) => len,
+            Err(e) => {
+                error!("UART RX: Read error: {:?}", defmt::Debug2Format(&e));
+                record_rx_error();
+                decoder.reset();
+                continue;
+            }
+        };
+
+        for &byte in &chunk[..len] {
+            match decoder.push(byte) {
+                Ok(Some(frame)) => {
+                    debug!("UART RX: Received {} bytes", frame.len());
+                    deliver_frame(&mut reassembler, frame);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("UART RX: Dropping malformed frame: {:?}", e);
+                    reject_frame(NakReason::Malformed, None);
+                }
+            }
+        }
+    }
+}
+
+/// Write a whole buffer, waiting for the driver ring buffer as needed
+async fn write_all(tx: &mut BufferedUarteTx<'static>, mut data: &[u8]) -> Result<(), buffered_uarte::Error> {
+    while !data.is_empty() {
+        let written = tx.write(data).await?;
+        data = &data[written..];
+    }
+    tx.flush().await
+}
+
+/// Initialize UART communication and spawn tasks
+pub async fn init_and_spawn(
+    spawner: &embassy_executor::Spawner,
+    config: UartConfig,
+    uarte0: Peri<'static, UARTE0>,
+) -> Result<(), embassy_executor::SpawnError> {
+    info!("Initializing UART communication...");
+    info!("UART: UARTE0, 1Mbaud, 8N1, RTS/CTS");
+
+    let mut uart_config = uarte::Config::default();
+    uart_config.baudrate = UART_BAUDRATE;
+    uart_config.parity = Parity::EXCLUDED;
+
+    // SAFETY: the buffers are only handed out here, and init_and_spawn runs once
+    let (rx_buffer, tx_buffer) = unsafe {
+        (
+            &mut *core::ptr::addr_of_mut!(UART_RX_BUFFER),
+            &mut *core::ptr::addr_of_mut!(UART_TX_BUFFER),
+        )
+    };
+
+    let uart = BufferedUarte::new_with_rtscts(
+        uarte0,
+        config.timer,
+        config.ppi_ch1,
+        config.ppi_ch2,
+        config.ppi_group,
+        config.rxd_pin,
+        config.txd_pin,
+        config.cts_pin,
+        config.rts_pin,
+        Irqs,
+        uart_config,
+        rx_buffer,
+        tx_buffer,
+    );
+    let (rx, tx) = uart.split();
+
+    spawner.spawn(uart_tx_task(tx))?;
+    spawner.spawn(uart_rx_task(rx))?;
+
+    info!("UART tasks spawned successfully");
+    Ok(())
+}
@@ -7,8 +7,10 @@
 //!
 //! - `core`: System infrastructure (memory, protocol, transport)
 //! - `ble`: BLE protocol implementation (GAP, GATT services)
+//! - `board`: Pin assignments of the selected board
 //! - `commands`: Application command processing
 
 pub mod ble;
+pub mod board;
 pub mod commands;
 pub mod core;
@@ -1,6 +1,8 @@
 #![no_std]
 #![no_main]
 
+use ::core::sync::atomic::{AtomicBool, Ordering};
+
 use defmt::{error, info, unwrap};
 use embassy_executor::Spawner;
 use embassy_futures::yield_now;
@@ -8,16 +10,13 @@ use embassy_nrf::config::Config;
 use embassy_nrf::interrupt;
 // Advertisement builder imports removed - now using advertising controller
 use nrf_softdevice::{Config as SdConfig, Softdevice};
-use {defmt_rtt as _, panic_probe as _};
-
-// panic-probe provides the panic handler with RTT output and debugger support
+use defmt_rtt as _;
 
 mod ble;
+mod board;
 mod commands;
 mod core;
 
-use core::transport::{RxSpiConfig, TxSpiConfig};
-
 use ble::services::Server;
 
 #[embassy_executor::main]
@@ -31,6 +30,7 @@ async fn main(spawner: Spawner) {
     nrf_config.time_interrupt_priority = interrupt::Priority::P2;
 
     let peripherals = embassy_nrf::init(nrf_config);
+    let board = board::split(peripherals);
 
     info!("Embassy initialized, configuring SoftDevice...");
 
@@ -43,11 +43,11 @@ async fn main(spawner: Spawner) {
             accuracy: nrf_softdevice::raw::NRF_CLOCK_LF_ACCURACY_50_PPM as u8,
         }),
         conn_gap: Some(nrf_softdevice::raw::ble_gap_conn_cfg_t {
-            conn_count: 2, // Balanced - more than minimal but less than full example
+            conn_count: ble::connection::MAX_CONNECTIONS as u8, // Balanced - more than minimal but less than full example
             event_length: 24,
         }),
         conn_gatt: Some(nrf_softdevice::raw::ble_gatt_conn_cfg_t {
-            att_mtu: 128, // Match working example
+            att_mtu: ble::connection::MAX_ATT_MTU, // Match working example
         }),
         gatts_attr_tab_size: Some(nrf_softdevice::raw::ble_gatts_cfg_attr_tab_size_t {
             attr_tab_size: nrf_softdevice::raw::BLE_GATTS_ATTR_TAB_SIZE_DEFAULT, // Use default like working example
@@ -72,6 +72,22 @@ async fn main(spawner: Spawner) {
     let sd = Softdevice::enable(&sd_config);
     info!("SoftDevice enabled successfully!");
 
+    // Work out why we reset before anything else can reset again
+    core::power::init();
+
+    // Raise a PowerFailureWarning SoC event when the supply drops below 2.7V
+    let ret = unsafe {
+        nrf_softdevice::raw::sd_power_pof_threshold_set(
+            nrf_softdevice::raw::NRF_POWER_THRESHOLDS_NRF_POWER_THRESHOLD_V27 as u8,
+        );
+        nrf_softdevice::raw::sd_power_pof_enable(1)
+    };
+    if ret == nrf_softdevice::raw::NRF_SUCCESS {
+        info!("Power-failure warning enabled");
+    } else {
+        error!("Failed to enable power-failure warning: {}", ret);
+    }
+
     // Initialize Bluetooth GATT server
     let server = Server::new(sd).unwrap_or_else(|_| {
         defmt::panic!("Failed to initialize Server");
@@ -81,30 +97,17 @@ async fn main(spawner: Spawner) {
     // Spawn SoftDevice task (CRITICAL for timer functionality!)
     unwrap!(spawner.spawn(softdevice_task(sd)));
 
-    // Configure SPI peripherals
-    let tx_spi_config = TxSpiConfig {
-        cs_pin: peripherals.P0_01,
-        sck_pin: peripherals.P0_00,
-        mosi_pin: peripherals.P0_04, // Master out - device transmits to host
-    };
+    // Initialize and spawn SPI tasks (pins, clock and mode from the board definition)
+    #[cfg(not(any(feature = "uart", feature = "single-spi")))]
+    unwrap!(core::transport::init_and_spawn(&spawner, board.tx_spi, board.rx_spi, board.spim, board.spis).await);
 
-    let rx_spi_config = RxSpiConfig {
-        cs_pin: peripherals.P0_07,
-        sck_pin: peripherals.P0_06,
-        miso_pin: peripherals.P0_05, // Slave in - host transmits to device
-    };
+    // Initialize and spawn the full-duplex SPI task (one SPI slave, IRQ line to the host)
+    #[cfg(feature = "single-spi")]
+    unwrap!(core::single_spi::init_and_spawn(&spawner, board.single_spi, board.spis).await);
 
-    // Initialize and spawn SPI tasks
-    unwrap!(
-        core::transport::init_and_spawn(
-            &spawner,
-            tx_spi_config,
-            rx_spi_config,
-            peripherals.TWISPI0,
-            peripherals.TWISPI1,
-        )
-        .await
-    );
+    // Initialize and spawn UART tasks (same connector pins as the SPI link)
+    #[cfg(feature = "uart")]
+    unwrap!(core::uart::init_and_spawn(&spawner, board.uart, board.uarte).await);
 
     // // Initialize other modules
     ble::gatt_state::init();
@@ -129,6 +132,15 @@ async fn main(spawner: Spawner) {
     // info!("Spawning notification service task...");
     unwrap!(spawner.spawn(ble::notifications::notification_service_task()));
     //
+    // Spawn SoC event task to forward SoftDevice SoC events
+    unwrap!(spawner.spawn(ble::events::soc_event_task()));
+    //
+    // Spawn system event task to warn the host when the TX pool runs low
+    unwrap!(spawner.spawn(ble::events::system_event_task()));
+    //
+    // Spawn power task to enter System OFF on SHUTDOWN
+    unwrap!(spawner.spawn(core::power::power_task()));
+    //
     // Event forwarding is now handled directly in the advertising task
 
     info!("Main thread starting heartbeat loop...");
@@ -183,5 +195,18 @@ async fn heartbeat_task() {
 
 #[embassy_executor::task]
 async fn softdevice_task(sd: &'static Softdevice) -> ! {
-    sd.run().await
+    sd.run_with_callback(ble::events::on_soc_event).await
+}
+
+/// Set on the first panic so a panic while logging goes straight to reset
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+/// Remember the panic for the boot event, log it over RTT and reset
+#[panic_handler]
+fn panic(info: &::core::panic::PanicInfo) -> ! {
+    core::power::record_reset_cause(core::protocol::power::ResetReason::Panic);
+    if !PANICKING.swap(true, Ordering::Relaxed) {
+        error!("{}", defmt::Display2Format(info));
+    }
+    cortex_m::peripheral::SCB::sys_reset()
 }
@@ -3,8 +3,10 @@
 
 mod common;
 
-use nrf52820_s140_firmware::commands::{CommandError, ResponseBuilder};
+use nrf52820_s140_firmware::commands::registry::{self, Role};
+use nrf52820_s140_firmware::commands::{is_supported, system, CommandError, ResponseBuilder};
 use nrf52820_s140_firmware::core::protocol::{Packet, RequestCode, ResponseCode};
+use nrf52820_s140_firmware::core::transport::{ChannelTransport, LinkStatus, Transport};
 
 #[defmt_test::tests]
 mod tests {
@@ -73,6 +75,77 @@ mod tests {
         assert_eq!(packet.code, ResponseCode::Ack.to_u16());
     }
 
+    #[test]
+    fn test_loopback_transport_roundtrip() {
+        // A ChannelTransport without a link driver acts as an in-memory loopback
+        static LOOPBACK: ChannelTransport = ChannelTransport::new();
+        assert_eq!(LOOPBACK.link_status(), LinkStatus::Down);
+
+        let request = Packet::new_request_for_sending(RequestCode::Echo, b"ping").unwrap();
+        assert!(LOOPBACK.deliver(request).is_ok());
+        assert!(LOOPBACK.rx_has_data());
+
+        embassy_futures::block_on(async {
+            let packet = LOOPBACK.receive_frame().await;
+            assert_eq!(packet.request_code(), Some(RequestCode::Echo));
+            let response = system::handle_echo(&packet.payload).await.unwrap();
+            LOOPBACK.send_frame(response).await.unwrap();
+        });
+
+        let response = LOOPBACK.try_next_outgoing().unwrap();
+        let packet = Packet::parse_response(response.as_slice()).unwrap();
+        assert_eq!(packet.code, ResponseCode::Ack.to_u16());
+        assert_eq!(packet.payload.as_slice(), b"ping");
+        assert!(LOOPBACK.try_next_outgoing().is_none());
+    }
+
+    #[test]
+    fn test_rx_queue_space_after_receive() {
+        // The SPI busy line waits on this when the command queue is full
+        static LINK: ChannelTransport = ChannelTransport::new();
+        assert!(LINK.rx_has_space());
+
+        let request = Packet::new_request_for_sending(RequestCode::Echo, b"one").unwrap();
+        assert!(LINK.deliver(request).is_ok());
+        assert!(!LINK.rx_has_space());
+
+        let second = Packet::new_request_for_sending(RequestCode::Echo, b"two").unwrap();
+        assert!(LINK.deliver(second).is_err());
+
+        embassy_futures::block_on(async {
+            LINK.receive_frame().await;
+            LINK.wait_rx_space().await;
+        });
+        assert!(LINK.rx_has_space());
+    }
+
+    #[test]
+    fn test_registry_covers_every_request() {
+        for &code in RequestCode::ALL.iter() {
+            let descriptor = registry::descriptor(code).expect("request code missing from registry");
+            assert_eq!(descriptor.min_len, code.min_payload_len());
+        }
+        assert_eq!(registry::COMMANDS.len(), RequestCode::ALL.len());
+    }
+
+    #[test]
+    fn test_registry_rejects_before_handler() {
+        // Central-only commands are refused by role, not by a handler
+        let connect = registry::descriptor(RequestCode::GapConnect).unwrap();
+        assert_eq!(connect.role, Role::Central);
+        assert!(!is_supported(RequestCode::GapConnect));
+        assert_eq!(connect.check(&[]), Err(CommandError::NotImplemented));
+
+        // Short payloads are refused with InvalidPayload
+        assert_eq!(
+            registry::check(RequestCode::GapSetAddr, &[0; 6]),
+            Err(CommandError::InvalidPayload)
+        );
+        assert_eq!(registry::check(RequestCode::GapSetAddr, &[0; 7]), Ok(()));
+        assert!(is_supported(RequestCode::GapSetAddr));
+        assert!(!is_supported(RequestCode::GattsSysAttrGet));
+    }
+
     #[test]
     fn test_uuid_register_command() {
         // Test UUID registration command code
@@ -5,9 +5,10 @@
 mod common;
 
 use nrf52820_s140_firmware::ble::events::{
-    BleModemEvent, create_disconnected_event, 
+    BleModemEvent, EventFilter, create_disconnected_event, 
     create_gatts_write_event, create_cccd_write_event
 };
+use nrf52820_s140_firmware::core::protocol::events::{EventMask, MAX_CHAR_EVENT_FILTERS};
 use proptest::prelude::*;
 
 #[defmt_test::tests]
@@ -571,4 +572,39 @@ mod tests {
         // But have different event type
         assert_eq!(write_serialized[0], 0x50); // BLE_GATTS_EVT_WRITE
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_event_filter_mask_and_char_filters() {
+        let mut filter = EventFilter::new();
+        let disconnected = create_disconnected_event(1, 0x13);
+        let write = create_gatts_write_event(1, 20, &[0x01]).unwrap();
+        let cccd = create_cccd_write_event(1, 21, true, false);
+
+        // Everything is forwarded after reset
+        assert!(filter.allows(&disconnected));
+        assert!(filter.allows(&write));
+        assert!(filter.allows(&cccd));
+
+        // Category mask
+        filter.set_mask(EventMask::GATTS_WRITE.union(EventMask::CCCD));
+        assert!(!filter.allows(&disconnected));
+        assert!(filter.allows(&write));
+
+        // Muting one characteristic leaves the others alone
+        filter.set_char_filter(20, false).unwrap();
+        assert!(!filter.allows(&write));
+        assert!(filter.allows(&cccd));
+        assert_eq!(filter.subscription().muted_handles.as_slice(), &[20]);
+
+        filter.set_char_filter(20, true).unwrap();
+        assert!(filter.allows(&write));
+
+        // Filter table capacity
+        for handle in 0..MAX_CHAR_EVENT_FILTERS as u16 {
+            filter.set_char_filter(100 + handle, false).unwrap();
+        }
+        assert!(filter.set_char_filter(200, false).is_err());
+        filter.clear_char_filters();
+        assert!(filter.subscription().muted_handles.is_empty());
+    }
+}
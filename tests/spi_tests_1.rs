@@ -154,8 +154,8 @@ mod tests {
                 RequestCode::Echo,
                 RequestCode::Shutdown,
                 RequestCode::Reboot,
-                RequestCode::RegisterEventCallback,
-                RequestCode::ClearEventCallbacks,
+                RequestCode::SetEventMask,
+                RequestCode::GetEventMask,
                 RequestCode::RegisterUuidGroup,
                 RequestCode::GapGetAddr,
                 RequestCode::GapSetAddr,